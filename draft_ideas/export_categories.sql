@@ -0,0 +1,12 @@
+select c.name as name,
+	c.start_symbol as start_symbol,
+	array(
+		select st.name
+		from category_semantic cs
+		inner join semantic_tag st
+		on st.id = cs.semantic_tag
+		where cs.category = c.id
+		order by cs.id
+	)::varchar[] as semantic_tags
+from category c
+order by c.id;
@@ -0,0 +1,7 @@
+select nts.name as non_terminal_symbol, p.production, p.weight
+from non_terminal_symbol nts
+inner join language l
+on l.id = nts.language and l.code = $1
+left join production p
+on p.non_terminal_symbol = nts.id
+order by nts.id, p.id;
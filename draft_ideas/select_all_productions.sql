@@ -0,0 +1,7 @@
+select p.id, nts.name, p.production
+from production p
+inner join non_terminal_symbol nts
+on nts.id = p.non_terminal_symbol
+inner join language l
+on l.id = nts.language and l.code = $1
+order by p.id;
@@ -1,4 +1,4 @@
-select w.id as id, w."content" as content, w.non_repeatable as non_repeatable,
+select w.id as id, w."content" as content, w.non_repeatable as non_repeatable, w.weight as weight,
 	coalesce((
 		select array_agg(sem.semantic_tag) 
 		from word_semantic sem 
@@ -12,6 +12,8 @@ select w.id as id, w."content" as content, w.non_repeatable as non_repeatable,
 		where gram.word = w.id
 	), array[]::integer[]) as grammar_output
 from word w
+inner join language l
+on l.id = w.language and l.code = <LANGUAGE>
 where array_contains_and_intersects(
 	(
     select array_agg(ws.semantic_tag)
@@ -27,7 +29,6 @@ and coalesce((
   where wg.word = w.id
 ), array[]::integer[]) @> array[<CONTEXTUAL_GRAMMAR_TAGS_PLACEHOLDERS>]::integer[]
 and (
-  id not in (<USED_WORDS>)
+  w.id not in (<USED_WORDS>)
 )
-order by random()
-limit 1;
+order by w.id;
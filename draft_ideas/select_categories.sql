@@ -0,0 +1,13 @@
+select c.name as name,
+	c.start_symbol as start_symbol,
+	array(
+		select st.name
+		from category_semantic cs
+		inner join semantic_tag st
+		on st.id = cs.semantic_tag
+		where cs.category = c.id
+		order by st.name
+	)::varchar[] as semantic_tags
+from category c
+where $1::varchar is null or c.name = $1
+order by c.name;
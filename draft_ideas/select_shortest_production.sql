@@ -1,6 +1,7 @@
-select p.production
+select p.production, p.weight
 from production p
 inner join non_terminal_symbol nts
 on nts.id = p.non_terminal_symbol and nts.name = $1
-order by nts_amount asc
-limit 1;
+inner join language l
+on l.id = nts.language and l.code = $2
+order by nts_amount asc, p.id asc;
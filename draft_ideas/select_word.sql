@@ -0,0 +1,29 @@
+select w.id as id, w."content" as content, l.code as language, w.non_repeatable as non_repeatable, w.weight as weight,
+	array(
+		select st.name
+		from word_semantic ws
+		inner join semantic_tag st
+		on st.id = ws.semantic_tag
+		where ws.word = w.id
+		order by ws.id
+	)::varchar[] as semantic_tags,
+	array(
+		select gt.name
+		from word_grammar_compatibility wg
+		inner join grammar_tag gt
+		on gt.id = wg.grammar_tag
+		where wg.word = w.id
+		order by wg.id
+	)::varchar[] as grammar_compatibilities,
+	array(
+		select gt.name
+		from word_grammar_requirements wg
+		inner join grammar_tag gt
+		on gt.id = wg.grammar_tag
+		where wg.word = w.id
+		order by wg.id
+	)::varchar[] as grammar_requirements
+from word w
+inner join language l
+on l.id = w.language
+where w.id = $1;
@@ -0,0 +1,6 @@
+-- Add down migration script here
+ALTER TABLE generated_phrase DROP COLUMN category;
+
+DROP TABLE
+  category_semantic,
+  category;
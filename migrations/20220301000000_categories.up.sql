@@ -0,0 +1,21 @@
+-- Add up migration script here
+CREATE TABLE category (
+  id serial primary key not null,
+  name varchar(32) unique not null,
+  non_terminal_symbol int,
+  foreign key (non_terminal_symbol) references non_terminal_symbol (id)
+);
+
+CREATE TABLE category_semantic (
+  id serial primary key not null,
+  category int not null,
+  semantic_tag int not null,
+  foreign key (category) references category (id),
+  foreign key (semantic_tag) references semantic_tag (id)
+);
+CREATE INDEX idx_category_semantic_category ON category_semantic (category);
+CREATE UNIQUE INDEX idx_category_semantic_uniqueness ON category_semantic (category, semantic_tag);
+
+ALTER TABLE generated_phrase ADD COLUMN category int;
+ALTER TABLE generated_phrase ADD CONSTRAINT fk_generated_phrase_category foreign key (category) references category (id);
+CREATE INDEX idx_generated_phrase_category ON generated_phrase (category);
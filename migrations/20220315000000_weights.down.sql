@@ -0,0 +1,6 @@
+-- Add down migration script here
+ALTER TABLE word DROP CONSTRAINT chk_word_weight;
+ALTER TABLE word DROP COLUMN weight;
+
+ALTER TABLE production DROP CONSTRAINT chk_production_weight;
+ALTER TABLE production DROP COLUMN weight;
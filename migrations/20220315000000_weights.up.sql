@@ -0,0 +1,6 @@
+-- Add up migration script here
+ALTER TABLE production ADD COLUMN weight int not null default 1;
+ALTER TABLE production ADD CONSTRAINT chk_production_weight check (weight > 0);
+
+ALTER TABLE word ADD COLUMN weight int not null default 1;
+ALTER TABLE word ADD CONSTRAINT chk_word_weight check (weight > 0);
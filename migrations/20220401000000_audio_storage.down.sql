@@ -0,0 +1,6 @@
+-- Add down migration script here
+SELECT lo_unlink(audio) FROM generated_phrase_speech WHERE audio IS NOT NULL;
+
+ALTER TABLE generated_phrase_speech DROP COLUMN audio;
+ALTER TABLE generated_phrase_speech DROP COLUMN size;
+ALTER TABLE generated_phrase_speech DROP COLUMN content_type;
@@ -0,0 +1,4 @@
+-- Add up migration script here
+ALTER TABLE generated_phrase_speech ADD COLUMN content_type text;
+ALTER TABLE generated_phrase_speech ADD COLUMN size bigint;
+ALTER TABLE generated_phrase_speech ADD COLUMN audio oid;
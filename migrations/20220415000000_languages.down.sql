@@ -0,0 +1,33 @@
+-- Add down migration script here
+DELETE FROM generated_phrase_speech WHERE lang <> 'ita';
+CREATE TYPE lang AS ENUM ('ita');
+ALTER TABLE generated_phrase_speech DROP CONSTRAINT fk_generated_phrase_speech_lang;
+ALTER TABLE generated_phrase_speech ALTER COLUMN lang TYPE lang USING lang::lang;
+
+DELETE FROM generated_phrase_speech WHERE generated_phrase IN (SELECT id FROM generated_phrase WHERE language <> (SELECT id FROM language WHERE code = 'ita'));
+DELETE FROM generated_phrase WHERE language <> (SELECT id FROM language WHERE code = 'ita');
+DROP INDEX idx_generated_phrase_uniqueness;
+CREATE UNIQUE INDEX idx_generated_phrase_uniqueness ON generated_phrase (content);
+ALTER TABLE generated_phrase DROP COLUMN language;
+
+DELETE FROM word_semantic WHERE word IN (SELECT id FROM word WHERE language <> (SELECT id FROM language WHERE code = 'ita'));
+DELETE FROM word_grammar_compatibility WHERE word IN (SELECT id FROM word WHERE language <> (SELECT id FROM language WHERE code = 'ita'));
+DELETE FROM word_grammar_requirements WHERE word IN (SELECT id FROM word WHERE language <> (SELECT id FROM language WHERE code = 'ita'));
+DELETE FROM word WHERE language <> (SELECT id FROM language WHERE code = 'ita');
+DROP INDEX idx_word_language;
+DROP INDEX idx_word_uniqueness;
+ALTER TABLE word ADD CONSTRAINT word_content_key UNIQUE (content);
+ALTER TABLE word DROP COLUMN language;
+
+DELETE FROM production WHERE non_terminal_symbol IN (SELECT id FROM non_terminal_symbol WHERE language <> (SELECT id FROM language WHERE code = 'ita'));
+DELETE FROM non_terminal_symbol WHERE language <> (SELECT id FROM language WHERE code = 'ita');
+DROP INDEX idx_non_terminal_symbol_uniqueness;
+ALTER TABLE non_terminal_symbol ADD CONSTRAINT non_terminal_symbol_name_key UNIQUE (name);
+ALTER TABLE non_terminal_symbol DROP COLUMN language;
+
+ALTER TABLE category ADD COLUMN non_terminal_symbol int;
+ALTER TABLE category ADD CONSTRAINT category_non_terminal_symbol_fkey foreign key (non_terminal_symbol) references non_terminal_symbol (id);
+UPDATE category c SET non_terminal_symbol = nts.id FROM non_terminal_symbol nts WHERE nts.name = c.start_symbol;
+ALTER TABLE category DROP COLUMN start_symbol;
+
+DROP TABLE language;
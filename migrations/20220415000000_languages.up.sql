@@ -0,0 +1,39 @@
+-- Add up migration script here
+CREATE TABLE language (
+  id serial primary key not null,
+  code varchar(8) unique not null,
+  name varchar(32) not null,
+  locale varchar(16) not null
+);
+INSERT INTO language (code, name, locale) VALUES ('ita', 'Italiano', 'it');
+
+-- Every language has its own symbols, categories start from the symbol with the same name in each of them
+ALTER TABLE category ADD COLUMN start_symbol varchar(8);
+UPDATE category c SET start_symbol = nts.name FROM non_terminal_symbol nts WHERE nts.id = c.non_terminal_symbol;
+ALTER TABLE category DROP COLUMN non_terminal_symbol;
+
+ALTER TABLE non_terminal_symbol ADD COLUMN language int;
+UPDATE non_terminal_symbol SET language = (SELECT id FROM language WHERE code = 'ita');
+ALTER TABLE non_terminal_symbol ALTER COLUMN language SET NOT NULL;
+ALTER TABLE non_terminal_symbol ADD CONSTRAINT fk_non_terminal_symbol_language foreign key (language) references language (id);
+ALTER TABLE non_terminal_symbol DROP CONSTRAINT non_terminal_symbol_name_key;
+CREATE UNIQUE INDEX idx_non_terminal_symbol_uniqueness ON non_terminal_symbol (language, name);
+
+ALTER TABLE word ADD COLUMN language int;
+UPDATE word SET language = (SELECT id FROM language WHERE code = 'ita');
+ALTER TABLE word ALTER COLUMN language SET NOT NULL;
+ALTER TABLE word ADD CONSTRAINT fk_word_language foreign key (language) references language (id);
+ALTER TABLE word DROP CONSTRAINT word_content_key;
+CREATE UNIQUE INDEX idx_word_uniqueness ON word (language, content);
+CREATE INDEX idx_word_language ON word (language);
+
+ALTER TABLE generated_phrase ADD COLUMN language int;
+UPDATE generated_phrase SET language = (SELECT id FROM language WHERE code = 'ita');
+ALTER TABLE generated_phrase ALTER COLUMN language SET NOT NULL;
+ALTER TABLE generated_phrase ADD CONSTRAINT fk_generated_phrase_language foreign key (language) references language (id);
+DROP INDEX idx_generated_phrase_uniqueness;
+CREATE UNIQUE INDEX idx_generated_phrase_uniqueness ON generated_phrase (language, content);
+
+ALTER TABLE generated_phrase_speech ALTER COLUMN lang TYPE varchar(8) USING lang::text;
+ALTER TABLE generated_phrase_speech ADD CONSTRAINT fk_generated_phrase_speech_lang foreign key (lang) references language (code) ON UPDATE CASCADE;
+DROP TYPE lang;
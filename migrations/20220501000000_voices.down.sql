@@ -0,0 +1,22 @@
+-- Add down migration script here
+ALTER TABLE generated_phrase_speech ADD COLUMN lang varchar(8);
+ALTER TABLE generated_phrase_speech ADD COLUMN gender gender;
+UPDATE generated_phrase_speech s SET lang = l.code, gender = v.gender
+FROM voice v INNER JOIN language l ON l.id = v.language
+WHERE v.id = s.voice;
+
+-- Only the first speech of every language and gender can be kept
+SELECT lo_unlink(s.audio) FROM generated_phrase_speech s WHERE s.audio IS NOT NULL AND EXISTS (SELECT 1 FROM generated_phrase_speech o WHERE o.generated_phrase = s.generated_phrase AND o.lang = s.lang AND o.gender = s.gender AND o.id < s.id);
+DELETE FROM generated_phrase_speech s WHERE EXISTS (SELECT 1 FROM generated_phrase_speech o WHERE o.generated_phrase = s.generated_phrase AND o.lang = s.lang AND o.gender = s.gender AND o.id < s.id);
+UPDATE generated_phrase_speech SET content_type = NULL, size = NULL WHERE audio IS NULL;
+
+ALTER TABLE generated_phrase_speech ALTER COLUMN lang SET NOT NULL;
+ALTER TABLE generated_phrase_speech ALTER COLUMN gender SET NOT NULL;
+ALTER TABLE generated_phrase_speech ADD CONSTRAINT fk_generated_phrase_speech_lang foreign key (lang) references language (code) ON UPDATE CASCADE;
+DROP INDEX idx_generated_phrase_speech_uniqueness;
+ALTER TABLE generated_phrase_speech DROP CONSTRAINT fk_generated_phrase_speech_voice;
+ALTER TABLE generated_phrase_speech DROP COLUMN voice;
+CREATE UNIQUE INDEX idx_generated_phrase_speech_uniqueness ON generated_phrase_speech (generated_phrase, lang, gender);
+CREATE INDEX idx_generated_phrase_speech_lookup ON generated_phrase_speech (generated_phrase, lang, gender);
+
+DROP TABLE voice;
@@ -0,0 +1,37 @@
+-- Add up migration script here
+CREATE TABLE voice (
+  id serial primary key not null,
+  language int not null,
+  gender gender not null,
+  provider varchar(16) not null,
+  name varchar(32) not null,
+  rate real not null default 1,
+  pitch real not null default 1,
+  foreign key (language) references language (id)
+);
+CREATE UNIQUE INDEX idx_voice_uniqueness ON voice (provider, language, name);
+CREATE INDEX idx_voice_language ON voice (language);
+
+INSERT INTO voice (language, gender, provider, name)
+SELECT l.id, v.gender::gender, v.provider, v.name
+FROM language l
+CROSS JOIN (VALUES ('male', 'wrapper', 'male'), ('female', 'wrapper', 'female'), ('male', 'espeak', 'm3'), ('female', 'espeak', 'f3')) AS v (gender, provider, name)
+ORDER BY l.id, v.provider DESC, v.gender DESC;
+
+-- espeak is the only provider handing out file URLs
+ALTER TABLE generated_phrase_speech ADD COLUMN voice int;
+UPDATE generated_phrase_speech s SET voice = v.id
+FROM voice v INNER JOIN language l ON l.id = v.language
+WHERE l.code = s.lang AND v.gender = s.gender AND v.provider = CASE WHEN s.url LIKE 'file:%' THEN 'espeak' ELSE 'wrapper' END;
+ALTER TABLE generated_phrase_speech ALTER COLUMN voice SET NOT NULL;
+ALTER TABLE generated_phrase_speech ADD CONSTRAINT fk_generated_phrase_speech_voice foreign key (voice) references voice (id);
+
+DROP INDEX idx_generated_phrase_speech_lookup;
+DROP INDEX idx_generated_phrase_speech_uniqueness;
+ALTER TABLE generated_phrase_speech DROP CONSTRAINT fk_generated_phrase_speech_lang;
+ALTER TABLE generated_phrase_speech DROP COLUMN lang;
+ALTER TABLE generated_phrase_speech DROP COLUMN gender;
+CREATE UNIQUE INDEX idx_generated_phrase_speech_uniqueness ON generated_phrase_speech (generated_phrase, voice);
+
+-- Files were named after language and gender: they are stored again, named after their voice, when next asked for
+UPDATE generated_phrase_speech SET content_type = NULL, size = NULL WHERE audio IS NULL;
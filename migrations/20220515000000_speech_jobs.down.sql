@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP TABLE speech_job;
+DROP TYPE speech_job_status;
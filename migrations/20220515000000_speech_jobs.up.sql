@@ -0,0 +1,20 @@
+-- Add up migration script here
+CREATE TYPE speech_job_status AS ENUM ('pending', 'done', 'failed');
+
+CREATE TABLE speech_job (
+  id uuid primary key not null default gen_random_uuid(),
+  generated_phrase uuid not null,
+  voice int not null,
+  status speech_job_status not null default 'pending',
+  url text,
+  error text,
+  created_at timestamptz not null default now(),
+  -- Set when a worker picks the job up
+  started_at timestamptz,
+  finished_at timestamptz,
+  constraint fk_speech_job_generated_phrase foreign key (generated_phrase) references generated_phrase (id) ON DELETE CASCADE,
+  constraint fk_speech_job_voice foreign key (voice) references voice (id)
+);
+-- A single pending job for the same speech, requesting it again joins that job
+CREATE UNIQUE INDEX idx_speech_job_pending_uniqueness ON speech_job (generated_phrase, voice) WHERE status = 'pending';
+CREATE INDEX idx_speech_job_queue ON speech_job (created_at) WHERE status = 'pending';
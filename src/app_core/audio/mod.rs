@@ -0,0 +1,397 @@
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use actix_web::rt::task::spawn_blocking;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{Client, Url};
+use sqlx::types::Uuid;
+use sqlx::{Pool, Postgres, Transaction};
+
+use crate::app_core::errors::AppError;
+use crate::app_core::AppResult;
+use crate::settings::{AudioStorageName, Settings, TtsProviderName};
+
+pub mod range;
+
+#[cfg(test)]
+#[path = "./unit_tests/mod.rs"]
+mod tests;
+
+/// Where synthesized audio is kept once the TTS provider returned it.
+pub enum AudioStorage {
+    /// `remote`, the default: nothing is kept, speeches point at the provider URL.
+    Remote,
+    /// `filesystem`: files under a directory, sharded by the hash of their speech.
+    Filesystem(PathBuf),
+    /// `database`: Postgres large objects, referenced by their speech.
+    Database,
+}
+
+pub struct AudioSettings {
+    pub storage: AudioStorage,
+    /// Root URL clients reach this server at, prefixing the `/audio` links.
+    pub public_url: String,
+    /// Largest audio kept, in bytes: bigger downloads are given up.
+    pub max_size: u64,
+    /// The output directory of `espeak`, the only provider whose `file://` URLs are read.
+    pub local_dir: Option<PathBuf>,
+}
+
+impl From<&Settings> for AudioSettings {
+    fn from(settings: &Settings) -> Self {
+        let storage = match settings.audio.storage {
+            AudioStorageName::Remote => AudioStorage::Remote,
+            AudioStorageName::Filesystem => {
+                AudioStorage::Filesystem(settings.audio.storage_dir.clone())
+            }
+            AudioStorageName::Database => AudioStorage::Database,
+        };
+
+        Self {
+            storage,
+            public_url: settings.server.public_url.clone(),
+            max_size: settings.audio.max_size_bytes,
+            local_dir: match settings.tts.provider {
+                TtsProviderName::Espeak => Some(settings.tts.espeak.output_dir.clone()),
+                TtsProviderName::Wrapper => None,
+            },
+        }
+    }
+}
+
+/// Identifies the audio of a phrase spoken in one of the voices of the catalogue.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AudioKey {
+    pub phrase: Uuid,
+    pub voice: i32,
+}
+
+/// Where the audio handed out by the TTS provider is downloaded from.
+#[derive(Debug, PartialEq)]
+enum AudioSource {
+    Http(Url),
+    File { path: PathBuf, dir: PathBuf },
+}
+
+enum AudioLocation {
+    File(PathBuf),
+    Database,
+}
+
+/// Audio copied into the storage, still to be recorded on its speech.
+pub struct CopiedAudio {
+    content_type: String,
+    size: i64,
+    /// The bytes of a large object, only created along with the record.
+    large_object: Option<Vec<u8>>,
+}
+
+/// Audio kept by this server, ready to be read.
+pub struct StoredAudio {
+    pub content_type: String,
+    pub size: u64,
+    key: AudioKey,
+    location: AudioLocation,
+}
+
+/// Keeps a copy of synthesized audio, so that it outlives whatever the TTS provider does with its own.
+pub struct AudioArchive {
+    settings: AudioSettings,
+    client: Client,
+}
+
+impl AudioArchive {
+    pub fn new(settings: AudioSettings, client: Client) -> Self {
+        match &settings.storage {
+            AudioStorage::Remote => tracing::info!("Audio is left to the TTS provider"),
+            AudioStorage::Filesystem(dir) => {
+                tracing::info!("Storing audio into {}", dir.display())
+            }
+            AudioStorage::Database => tracing::info!("Storing audio into the database"),
+        }
+
+        Self { settings, client }
+    }
+
+    /// The link to the `/audio` route serving the audio of `key`.
+    pub fn served_url(&self, key: &AudioKey) -> String {
+        format!(
+            "{}/audio/{}/{}",
+            self.settings.public_url.trim_end_matches('/'),
+            key.phrase,
+            key.voice
+        )
+    }
+
+    /// Whether a speech stored with these columns can be served by this server.
+    pub fn is_archived(&self, key: &AudioKey, has_content_type: bool, in_database: bool) -> bool {
+        has_content_type && self.location(key, in_database).is_some()
+    }
+
+    /// A large object is served whatever the storage, so that switching to files does not lose it.
+    fn location(&self, key: &AudioKey, in_database: bool) -> Option<AudioLocation> {
+        match &self.settings.storage {
+            _ if in_database => Some(AudioLocation::Database),
+            AudioStorage::Filesystem(dir) => Some(AudioLocation::File(dir.join(shard_path(key)))),
+            _ => None,
+        }
+    }
+
+    /// Downloads the audio at `url` and writes it into the storage, if any. Meant to run before the
+    /// transaction recording it, so that a slow provider never holds a lock.
+    pub async fn copy(&self, key: &AudioKey, url: &str) -> AppResult<Option<CopiedAudio>> {
+        if let AudioStorage::Remote = self.settings.storage {
+            return Ok(None);
+        }
+        let (content_type, bytes) = self.download(url).await?;
+        let size = bytes.len() as i64;
+
+        let large_object = match &self.settings.storage {
+            AudioStorage::Remote => return Ok(None),
+            AudioStorage::Filesystem(dir) => {
+                let path = dir.join(shard_path(key));
+                blocking(move || {
+                    if let Some(parent) = path.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    std::fs::write(path, bytes)
+                })
+                .await?;
+                None
+            }
+            AudioStorage::Database => Some(bytes),
+        };
+
+        Ok(Some(CopiedAudio {
+            content_type,
+            size,
+            large_object,
+        }))
+    }
+
+    /// Records the copied audio on its speech, within the transaction that stored the speech.
+    pub async fn archive(
+        &self,
+        transaction: &mut Transaction<'_, Postgres>,
+        key: &AudioKey,
+        audio: CopiedAudio,
+    ) -> AppResult<()> {
+        let update = match audio.large_object {
+            None => "UPDATE generated_phrase_speech SET content_type = $3, size = $4 WHERE generated_phrase = $1 AND voice = $2",
+            Some(_) => "UPDATE generated_phrase_speech SET content_type = $3, size = $4, audio = lo_from_bytea(0, $5) WHERE generated_phrase = $1 AND voice = $2",
+        };
+
+        let mut query = sqlx::query(update)
+            .bind(key.phrase)
+            .bind(key.voice)
+            .bind(audio.content_type)
+            .bind(audio.size);
+        if let Some(bytes) = audio.large_object {
+            query = query.bind(bytes);
+        }
+        query
+            .execute(transaction)
+            .await
+            .map(|_| ())
+            .map_err(AppError::for_storage_in_sql)
+    }
+
+    pub async fn find(
+        &self,
+        pool: &Pool<Postgres>,
+        key: &AudioKey,
+    ) -> AppResult<Option<StoredAudio>> {
+        let row = sqlx::query_as::<Postgres, (Option<String>, Option<i64>, bool)>(
+            "SELECT content_type, size, audio IS NOT NULL FROM generated_phrase_speech WHERE generated_phrase = $1 AND voice = $2",
+        )
+        .bind(key.phrase)
+        .bind(key.voice)
+        .fetch_optional(pool)
+        .await
+        .map_err(AppError::for_storage_in_sql)?;
+
+        Ok(row.and_then(|(content_type, size, in_database)| {
+            Some(StoredAudio {
+                content_type: content_type?,
+                size: size? as u64,
+                location: self.location(key, in_database)?,
+                key: key.clone(),
+            })
+        }))
+    }
+
+    /// Reads the bytes from `first` to `last`, both included.
+    pub async fn read(
+        &self,
+        pool: &Pool<Postgres>,
+        audio: &StoredAudio,
+        first: u64,
+        last: u64,
+    ) -> AppResult<Vec<u8>> {
+        let length = last + 1 - first;
+
+        match &audio.location {
+            AudioLocation::File(path) => {
+                let path = path.clone();
+                blocking(move || {
+                    let mut file = std::fs::File::open(path)?;
+                    file.seek(SeekFrom::Start(first))?;
+                    let mut bytes = Vec::with_capacity(length as usize);
+                    file.take(length).read_to_end(&mut bytes)?;
+                    Ok(bytes)
+                })
+                .await
+            }
+            AudioLocation::Database => sqlx::query_scalar::<Postgres, Vec<u8>>(
+                "SELECT lo_get(audio, $3, $4) FROM generated_phrase_speech WHERE generated_phrase = $1 AND voice = $2",
+            )
+            .bind(audio.key.phrase)
+            .bind(audio.key.voice)
+            .bind(first as i64)
+            .bind(length as i32)
+            .fetch_one(pool)
+            .await
+            .map_err(AppError::for_storage_in_sql),
+        }
+    }
+
+    /// Audio larger than `max_size` is refused, even when its size is not told upfront.
+    async fn download(&self, url: &str) -> AppResult<(String, Vec<u8>)> {
+        let max_size = self.settings.max_size;
+        let too_large =
+            || AppError::for_storage_download(format!("audio exceeds {max_size} bytes"));
+
+        match audio_source(url, self.settings.local_dir.as_deref())? {
+            AudioSource::File { path, dir } => {
+                let content_type = content_type_of(&path);
+
+                let bytes = blocking(move || {
+                    let mut bytes = Vec::new();
+                    std::fs::File::open(confined(&path, &dir)?)?
+                        .take(max_size + 1)
+                        .read_to_end(&mut bytes)?;
+                    Ok(bytes)
+                })
+                .await?;
+                if bytes.len() as u64 > max_size {
+                    return Err(too_large());
+                }
+
+                Ok((content_type.to_owned(), bytes))
+            }
+            AudioSource::Http(url) => {
+                let mut response = self
+                    .client
+                    .get(url.clone())
+                    .send()
+                    .await
+                    .and_then(|response| response.error_for_status())
+                    .map_err(|error| AppError::for_storage_download(format!("{error}")))?;
+                let content_type = response
+                    .headers()
+                    .get(CONTENT_TYPE)
+                    .and_then(|value| value.to_str().ok())
+                    .filter(|value| *value != "application/octet-stream")
+                    .map_or_else(
+                        || content_type_of(Path::new(url.path())).to_owned(),
+                        ToOwned::to_owned,
+                    );
+
+                if response
+                    .content_length()
+                    .map_or(false, |size| size > max_size)
+                {
+                    return Err(too_large());
+                }
+
+                let mut bytes = Vec::new();
+                while let Some(chunk) = response
+                    .chunk()
+                    .await
+                    .map_err(|error| AppError::for_storage_download(format!("{error}")))?
+                {
+                    if (bytes.len() + chunk.len()) as u64 > max_size {
+                        return Err(too_large());
+                    }
+                    bytes.extend_from_slice(&chunk);
+                }
+
+                Ok((content_type, bytes))
+            }
+        }
+    }
+}
+
+/// Only `espeak` hands out `file://` URLs: read from any other provider, they would let it have any file of
+/// this server copied and served.
+fn audio_source(url: &str, local_dir: Option<&Path>) -> AppResult<AudioSource> {
+    let url =
+        Url::parse(url).map_err(|error| AppError::for_storage_download(format!("{error}")))?;
+
+    match (url.scheme(), local_dir) {
+        ("http" | "https", _) => Ok(AudioSource::Http(url)),
+        ("file", Some(dir)) => url
+            .to_file_path()
+            .map(|path| AudioSource::File {
+                path,
+                dir: dir.to_owned(),
+            })
+            .map_err(|_| AppError::for_storage_download(format!("{url} is not a local path"))),
+        _ => Err(AppError::for_storage_download(format!(
+            "{url} is not downloaded from this TTS provider"
+        ))),
+    }
+}
+
+/// `path` once links and `..` are resolved, refused unless it stays under `dir`.
+fn confined(path: &Path, dir: &Path) -> std::io::Result<PathBuf> {
+    let path = path.canonicalize()?;
+    if path.starts_with(dir.canonicalize()?) {
+        Ok(path)
+    } else {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            format!("{} is outside of {}", path.display(), dir.display()),
+        ))
+    }
+}
+
+async fn blocking<T, F>(job: F) -> AppResult<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> std::io::Result<T> + Send + 'static,
+{
+    spawn_blocking(job)
+        .await
+        .unwrap_or_else(|error| {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("{error}"),
+            ))
+        })
+        .map_err(AppError::for_storage_file_access)
+}
+
+/// Spreads files over two levels of directories, by the FNV-1a hash of their key, so that none grows too
+/// large. The same key always lands on the same path.
+fn shard_path(key: &AudioKey) -> PathBuf {
+    let name = format!("{}-{}", key.phrase, key.voice);
+    let hash = format!(
+        "{:016x}",
+        name.bytes().fold(0xcbf29ce484222325_u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        })
+    );
+
+    [&hash[0..2], &hash[2..4], &name].iter().collect()
+}
+
+fn content_type_of(path: &Path) -> &'static str {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("wav") => "audio/wav",
+        Some("mp3") => "audio/mpeg",
+        Some("ogg") => "audio/ogg",
+        Some("flac") => "audio/flac",
+        _ => "application/octet-stream",
+    }
+}
@@ -0,0 +1,50 @@
+#[cfg(test)]
+#[path = "./unit_tests/range.rs"]
+mod tests;
+
+/// A single `Range: bytes=...` request, not yet checked against the audio size.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ByteRange {
+    /// `bytes=start-` or `bytes=start-end`, both inclusive.
+    From(u64, Option<u64>),
+    /// `bytes=-length`: the last `length` bytes.
+    Suffix(u64),
+}
+
+impl ByteRange {
+    /// Multiple ranges are not supported: like unparsable headers, they mean the whole content.
+    pub fn parse(header: &str) -> Option<Self> {
+        let spec = header.trim().strip_prefix("bytes=")?;
+        if spec.contains(',') {
+            return None;
+        }
+        let (start, end) = spec.split_once('-')?;
+        let (start, end) = (start.trim(), end.trim());
+
+        if start.is_empty() {
+            end.parse().ok().map(Self::Suffix)
+        } else {
+            let start = start.parse().ok()?;
+            let end = if end.is_empty() {
+                None
+            } else {
+                Some(end.parse().ok()?)
+            };
+            match end {
+                Some(end) if end < start => None,
+                _ => Some(Self::From(start, end)),
+            }
+        }
+    }
+
+    /// First and last byte of the range within `size` bytes, none when it cannot be satisfied.
+    pub fn resolve(&self, size: u64) -> Option<(u64, u64)> {
+        match *self {
+            _ if size == 0 => None,
+            Self::From(start, _) if start >= size => None,
+            Self::From(start, end) => Some((start, end.unwrap_or(size - 1).min(size - 1))),
+            Self::Suffix(0) => None,
+            Self::Suffix(length) => Some((size - length.min(size), size - 1)),
+        }
+    }
+}
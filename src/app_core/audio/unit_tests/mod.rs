@@ -0,0 +1,73 @@
+use std::path::{Path, PathBuf};
+
+use sqlx::types::Uuid;
+
+use crate::app_core::audio::{
+    audio_source, confined, content_type_of, shard_path, AudioKey, AudioSource,
+};
+
+#[test]
+fn audio_is_sharded_by_its_key() {
+    let key = AudioKey {
+        phrase: Uuid::parse_str("6f1d7c1a-57a1-4d3b-9c57-38a7b0a4d1e2").unwrap(),
+        voice: 3,
+    };
+    let path = shard_path(&key);
+    let other = shard_path(&AudioKey {
+        voice: 4,
+        ..key.clone()
+    });
+
+    assert_eq!(path, shard_path(&key));
+    assert_ne!(path, other);
+    assert_eq!(path.components().count(), 3);
+    assert_eq!(
+        path.file_name().unwrap(),
+        "6f1d7c1a-57a1-4d3b-9c57-38a7b0a4d1e2-3"
+    );
+}
+
+#[test]
+fn content_types_follow_extensions() {
+    assert_eq!(content_type_of(Path::new("/tmp/a.wav")), "audio/wav");
+    assert_eq!(content_type_of(Path::new("speech.mp3")), "audio/mpeg");
+    assert_eq!(
+        content_type_of(&PathBuf::from("speech")),
+        "application/octet-stream"
+    );
+}
+
+#[test]
+fn only_espeak_file_urls_are_read() {
+    let dir = Path::new("/tmp/espeak");
+
+    assert_eq!(
+        audio_source("file:///tmp/espeak/a.wav", Some(dir)).unwrap(),
+        AudioSource::File {
+            path: PathBuf::from("/tmp/espeak/a.wav"),
+            dir: dir.to_owned(),
+        }
+    );
+    assert!(audio_source("file:///etc/shadow", None).is_err());
+    assert!(audio_source("ftp://tts/a.wav", Some(dir)).is_err());
+    assert!(matches!(
+        audio_source("https://tts/a.wav", None),
+        Ok(AudioSource::Http(_))
+    ));
+}
+
+#[test]
+fn file_urls_outside_of_the_output_dir_are_refused() {
+    let root = std::env::temp_dir().join(format!("phrasegen-audio-{}", std::process::id()));
+    let dir = root.join("espeak");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("a.wav"), b"RIFF").unwrap();
+    std::fs::write(root.join("secret"), b"secret").unwrap();
+
+    assert!(confined(&dir.join("a.wav"), &dir).is_ok());
+    assert!(confined(&root.join("secret"), &dir).is_err());
+    assert!(confined(&dir.join("../secret"), &dir).is_err());
+    assert!(confined(Path::new("/etc/hostname"), &dir).is_err());
+
+    std::fs::remove_dir_all(root).unwrap();
+}
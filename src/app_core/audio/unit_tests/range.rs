@@ -0,0 +1,31 @@
+use crate::app_core::audio::range::ByteRange;
+
+#[test]
+fn single_ranges_are_parsed() {
+    assert_eq!(
+        ByteRange::parse("bytes=0-499"),
+        Some(ByteRange::From(0, Some(499)))
+    );
+    assert_eq!(
+        ByteRange::parse("bytes=500-"),
+        Some(ByteRange::From(500, None))
+    );
+    assert_eq!(ByteRange::parse("bytes=-200"), Some(ByteRange::Suffix(200)));
+    assert_eq!(ByteRange::parse("bytes=0-1,4-5"), None);
+    assert_eq!(ByteRange::parse("bytes=9-3"), None);
+    assert_eq!(ByteRange::parse("items=0-1"), None);
+}
+
+#[test]
+fn ranges_are_clamped_to_the_content() {
+    assert_eq!(ByteRange::From(0, Some(499)).resolve(1000), Some((0, 499)));
+    assert_eq!(
+        ByteRange::From(500, Some(5000)).resolve(1000),
+        Some((500, 999))
+    );
+    assert_eq!(ByteRange::From(500, None).resolve(1000), Some((500, 999)));
+    assert_eq!(ByteRange::Suffix(200).resolve(1000), Some((800, 999)));
+    assert_eq!(ByteRange::Suffix(2000).resolve(1000), Some((0, 999)));
+    assert_eq!(ByteRange::From(1000, None).resolve(1000), None);
+    assert_eq!(ByteRange::Suffix(0).resolve(1000), None);
+}
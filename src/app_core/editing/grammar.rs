@@ -0,0 +1,302 @@
+use std::collections::HashSet;
+
+use sqlx::{FromRow, Pool, Postgres, Transaction};
+
+use super::{
+    begin, commit, fetch_page, language_id, unique_violation_or_sql, validate_length,
+    validate_name, validate_weight, Page, PageRequest, LANGUAGE,
+};
+use crate::app_core::engine::validation::check_production;
+use crate::app_core::errors::AppError;
+use crate::app_core::AppResult;
+
+/// Column sizes from the `bnf_basics` migration.
+pub const MAX_NON_TERMINAL_SYMBOL_LENGTH: usize = 8;
+pub const MAX_PRODUCTION_LENGTH: usize = 1024;
+/// Column sizes from the `languages` migration.
+pub const MAX_LANGUAGE_CODE_LENGTH: usize = 8;
+pub const MAX_LANGUAGE_NAME_LENGTH: usize = 32;
+pub const MAX_LOCALE_LENGTH: usize = 16;
+
+const NON_TERMINAL_SYMBOL: &str = "non-terminal symbol";
+const PRODUCTION: &str = "production";
+
+/// Languages are told apart by their code, like `ita`; TTS providers get their locale, like `it`.
+#[derive(FromRow, Clone, Debug, PartialEq)]
+pub struct GrammarLanguage {
+    pub code: String,
+    pub name: String,
+    pub locale: String,
+}
+
+/// Names are unique within a language.
+#[derive(FromRow, Clone, Debug, PartialEq)]
+pub struct GrammarNonTerminalSymbol {
+    pub id: i32,
+    pub name: String,
+    pub language: String,
+}
+
+/// Productions are in the language of their non-terminal symbol.
+#[derive(FromRow, Clone, Debug, PartialEq)]
+pub struct GrammarProduction {
+    pub id: i32,
+    pub non_terminal_symbol: String,
+    pub language: String,
+    pub production: String,
+    pub weight: i32,
+}
+
+/// Lists and edits languages, non-terminal symbols and their productions; every edit runs in its own
+/// transaction. Productions are parsed and checked before being stored, see [`check_production`].
+pub struct Grammar<'p> {
+    pool: &'p Pool<Postgres>,
+}
+
+impl<'p> Grammar<'p> {
+    pub fn new(pool: &'p Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    pub async fn languages(&self) -> AppResult<Vec<GrammarLanguage>> {
+        sqlx::query_as::<Postgres, GrammarLanguage>(
+            "SELECT code, name, locale FROM language ORDER BY id",
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(AppError::for_browsing_in_sql)
+    }
+
+    /// A new language starts with no symbols and no words.
+    pub async fn create_language(
+        &self,
+        code: &str,
+        name: &str,
+        locale: &str,
+    ) -> AppResult<GrammarLanguage> {
+        validate_name("language code", code, MAX_LANGUAGE_CODE_LENGTH)?;
+        validate_length("language name", name, MAX_LANGUAGE_NAME_LENGTH)?;
+        validate_length("locale", locale, MAX_LOCALE_LENGTH)?;
+
+        let mut transaction = begin(self.pool).await?;
+        let language = sqlx::query_as::<Postgres, GrammarLanguage>(
+            "INSERT INTO language (code, name, locale) VALUES ($1, $2, $3) RETURNING code, name, locale",
+        )
+        .bind(code)
+        .bind(name)
+        .bind(locale)
+        .fetch_one(&mut transaction)
+        .await
+        .map_err(|error| unique_violation_or_sql(error, LANGUAGE, code))?;
+
+        commit(transaction).await?;
+        Ok(language)
+    }
+
+    pub async fn non_terminal_symbols(
+        &self,
+        page: PageRequest,
+    ) -> AppResult<Page<GrammarNonTerminalSymbol>> {
+        fetch_page(
+            self.pool,
+            "SELECT COUNT(*) FROM non_terminal_symbol",
+            "SELECT nts.id, nts.name, l.code AS language FROM non_terminal_symbol nts INNER JOIN language l ON l.id = nts.language ORDER BY nts.id LIMIT $1 OFFSET $2",
+            page,
+        )
+        .await
+    }
+
+    pub async fn productions_of(
+        &self,
+        non_terminal_symbol: i32,
+    ) -> AppResult<Vec<GrammarProduction>> {
+        sqlx::query_as::<Postgres, GrammarProduction>(
+            "SELECT p.id, nts.name AS non_terminal_symbol, l.code AS language, p.production, p.weight FROM production p INNER JOIN non_terminal_symbol nts ON nts.id = p.non_terminal_symbol INNER JOIN language l ON l.id = nts.language WHERE nts.id = $1 ORDER BY p.id",
+        )
+        .bind(non_terminal_symbol)
+        .fetch_all(self.pool)
+        .await
+        .map_err(AppError::for_browsing_in_sql)
+    }
+
+    pub async fn create_non_terminal_symbol(
+        &self,
+        name: &str,
+        language: &str,
+    ) -> AppResult<GrammarNonTerminalSymbol> {
+        validate_name(NON_TERMINAL_SYMBOL, name, MAX_NON_TERMINAL_SYMBOL_LENGTH)?;
+
+        let mut transaction = begin(self.pool).await?;
+        let language_id = language_id(&mut transaction, language).await?;
+        let id = sqlx::query_scalar::<Postgres, i32>(
+            "INSERT INTO non_terminal_symbol (name, language) VALUES ($1, $2) RETURNING id",
+        )
+        .bind(name)
+        .bind(language_id)
+        .fetch_one(&mut transaction)
+        .await
+        .map_err(|error| unique_violation_or_sql(error, NON_TERMINAL_SYMBOL, name))?;
+
+        commit(transaction).await?;
+        Ok(GrammarNonTerminalSymbol {
+            id,
+            name: name.to_owned(),
+            language: language.to_owned(),
+        })
+    }
+
+    pub async fn add_production(
+        &self,
+        non_terminal_symbol: &str,
+        language: &str,
+        production: &str,
+        weight: i32,
+    ) -> AppResult<GrammarProduction> {
+        validate_weight(weight)?;
+
+        let mut transaction = begin(self.pool).await?;
+        let language_id = language_id(&mut transaction, language).await?;
+        let non_terminal_symbol_id = sqlx::query_scalar::<Postgres, i32>(
+            "SELECT id FROM non_terminal_symbol WHERE name = $1 AND language = $2",
+        )
+        .bind(non_terminal_symbol)
+        .bind(language_id)
+        .fetch_optional(&mut transaction)
+        .await
+        .map_err(AppError::for_edit_in_sql)?
+        .ok_or_else(|| {
+            AppError::for_edit_unknown_name(NON_TERMINAL_SYMBOL, non_terminal_symbol.to_owned())
+        })?;
+        check(&mut transaction, non_terminal_symbol_id, None, production).await?;
+
+        let id = sqlx::query_scalar::<Postgres, i32>(
+            "INSERT INTO production (non_terminal_symbol, production, weight) VALUES ($1, $2, $3) RETURNING id",
+        )
+        .bind(non_terminal_symbol_id)
+        .bind(production)
+        .bind(weight)
+        .fetch_one(&mut transaction)
+        .await
+        .map_err(AppError::for_edit_in_sql)?;
+
+        let added = select_production(&mut transaction, id).await?;
+        commit(transaction).await?;
+        Ok(added)
+    }
+
+    /// Fields left to `None` keep their current value.
+    pub async fn update_production(
+        &self,
+        id: i32,
+        production: Option<&str>,
+        weight: Option<i32>,
+    ) -> AppResult<GrammarProduction> {
+        if let Some(weight) = weight {
+            validate_weight(weight)?;
+        }
+
+        let mut transaction = begin(self.pool).await?;
+        let non_terminal_symbol_id = sqlx::query_scalar::<Postgres, i32>(
+            "SELECT non_terminal_symbol FROM production WHERE id = $1",
+        )
+        .bind(id)
+        .fetch_optional(&mut transaction)
+        .await
+        .map_err(AppError::for_edit_in_sql)?
+        .ok_or_else(|| AppError::for_edit_not_found(PRODUCTION, id))?;
+        if let Some(production) = production {
+            check(
+                &mut transaction,
+                non_terminal_symbol_id,
+                Some(id),
+                production,
+            )
+            .await?;
+        }
+
+        sqlx::query(
+            "UPDATE production SET production = COALESCE($2, production), weight = COALESCE($3, weight) WHERE id = $1",
+        )
+        .bind(id)
+        .bind(production)
+        .bind(weight)
+        .execute(&mut transaction)
+        .await
+        .map_err(AppError::for_edit_in_sql)?;
+
+        let updated = select_production(&mut transaction, id).await?;
+        commit(transaction).await?;
+        Ok(updated)
+    }
+
+    /// The deleted production is returned.
+    pub async fn delete_production(&self, id: i32) -> AppResult<GrammarProduction> {
+        let mut transaction = begin(self.pool).await?;
+        let production = select_production(&mut transaction, id).await?;
+
+        sqlx::query("DELETE FROM production WHERE id = $1")
+            .bind(id)
+            .execute(&mut transaction)
+            .await
+            .map_err(AppError::for_edit_in_sql)?;
+
+        commit(transaction).await?;
+        Ok(production)
+    }
+}
+
+/// Besides the production checks, the same text cannot appear twice among the branches of a symbol:
+/// generation tells discarded branches apart by their text. Productions only mention symbols of their own
+/// language.
+async fn check(
+    transaction: &mut Transaction<'_, Postgres>,
+    non_terminal_symbol_id: i32,
+    production_id: Option<i32>,
+    production: &str,
+) -> AppResult<()> {
+    validate_length(PRODUCTION, production, MAX_PRODUCTION_LENGTH)?;
+
+    let names = sqlx::query_scalar::<Postgres, String>(
+        "SELECT name FROM non_terminal_symbol WHERE language = (SELECT language FROM non_terminal_symbol WHERE id = $1)",
+    )
+    .bind(non_terminal_symbol_id)
+    .fetch_all(&mut *transaction)
+    .await
+    .map_err(AppError::for_edit_in_sql)?;
+    check_production(
+        production,
+        &names.iter().map(String::as_str).collect::<HashSet<_>>(),
+    )?;
+
+    let is_duplicate = sqlx::query_scalar::<Postgres, bool>(
+        "SELECT EXISTS (SELECT 1 FROM production WHERE non_terminal_symbol = $1 AND production = $2 AND id IS DISTINCT FROM $3)",
+    )
+    .bind(non_terminal_symbol_id)
+    .bind(production)
+    .bind(production_id)
+    .fetch_one(&mut *transaction)
+    .await
+    .map_err(AppError::for_edit_in_sql)?;
+    if is_duplicate {
+        return Err(AppError::for_edit_already_existing(
+            PRODUCTION,
+            production.to_owned(),
+        ));
+    }
+
+    Ok(())
+}
+
+async fn select_production(
+    transaction: &mut Transaction<'_, Postgres>,
+    id: i32,
+) -> AppResult<GrammarProduction> {
+    sqlx::query_as::<Postgres, GrammarProduction>(
+        "SELECT p.id, nts.name AS non_terminal_symbol, l.code AS language, p.production, p.weight FROM production p INNER JOIN non_terminal_symbol nts ON nts.id = p.non_terminal_symbol INNER JOIN language l ON l.id = nts.language WHERE p.id = $1",
+    )
+    .bind(id)
+    .fetch_optional(&mut *transaction)
+    .await
+    .map_err(AppError::for_edit_in_sql)?
+    .ok_or_else(|| AppError::for_edit_not_found(PRODUCTION, id))
+}
@@ -0,0 +1,472 @@
+use sqlx::{FromRow, Pool, Postgres, Transaction};
+
+use super::{
+    begin, commit, execute, fetch_page, language_id, unique_violation_or_sql, validate_length,
+    validate_name, validate_weight, Page, PageRequest,
+};
+use crate::app_core::errors::AppError;
+use crate::app_core::AppResult;
+
+/// Column sizes from the `bnf_basics` migration.
+pub const MAX_WORD_LENGTH: usize = 64;
+pub const MAX_TAG_NAME_LENGTH: usize = 32;
+
+const WORD: &str = "word";
+const SEMANTIC_TAG: &str = "semantic tag";
+const GRAMMAR_TAG: &str = "grammar tag";
+
+#[derive(FromRow, Clone, Debug, PartialEq)]
+pub struct LexiconWord {
+    pub id: i32,
+    pub content: String,
+    /// Code of the language of the word.
+    pub language: String,
+    pub non_repeatable: bool,
+    pub weight: i32,
+    pub semantic_tags: Vec<String>,
+    pub grammar_compatibilities: Vec<String>,
+    pub grammar_requirements: Vec<String>,
+}
+
+#[derive(FromRow, Clone, Debug, PartialEq)]
+pub struct LexiconSemanticTag {
+    pub id: i32,
+    pub name: String,
+    pub sticky: bool,
+}
+
+#[derive(FromRow, Clone, Debug, PartialEq)]
+pub struct LexiconGrammarTag {
+    pub id: i32,
+    pub name: String,
+}
+
+/// A word seen from one of its tags.
+#[derive(FromRow, Clone, Debug, PartialEq)]
+pub struct TaggedWord {
+    pub id: i32,
+    pub content: String,
+}
+
+/// How a grammar tag relates to a word: the word either accepts it from its context or imposes it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GrammarRelation {
+    Compatibility,
+    Requirement,
+}
+
+impl GrammarRelation {
+    fn table(&self) -> &'static str {
+        match self {
+            GrammarRelation::Compatibility => "word_grammar_compatibility",
+            GrammarRelation::Requirement => "word_grammar_requirements",
+        }
+    }
+}
+
+pub struct NewWord {
+    pub content: String,
+    /// Code of the language of the word.
+    pub language: String,
+    pub non_repeatable: bool,
+    pub weight: i32,
+}
+
+/// Fields left to `None` keep their current value.
+#[derive(Default)]
+pub struct WordChanges {
+    pub content: Option<String>,
+    pub non_repeatable: Option<bool>,
+    pub weight: Option<i32>,
+}
+
+/// Lists and edits words and tags; every edit runs in its own transaction.
+/// Words belong to a language, tags are shared by all of them.
+/// Renaming or deleting a semantic tag does not touch the productions mentioning it,
+/// the grammar validation reports them.
+pub struct Lexicon<'p> {
+    pool: &'p Pool<Postgres>,
+}
+
+impl<'p> Lexicon<'p> {
+    pub fn new(pool: &'p Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    pub async fn words(&self, page: PageRequest) -> AppResult<Page<LexiconWord>> {
+        fetch_page(
+            self.pool,
+            "SELECT COUNT(*) FROM word",
+            include_str!("../../../draft_ideas/select_words_page.sql"),
+            page,
+        )
+        .await
+    }
+
+    pub async fn semantic_tags(&self, page: PageRequest) -> AppResult<Page<LexiconSemanticTag>> {
+        fetch_page(
+            self.pool,
+            "SELECT COUNT(*) FROM semantic_tag",
+            "SELECT id, name, sticky FROM semantic_tag ORDER BY id LIMIT $1 OFFSET $2",
+            page,
+        )
+        .await
+    }
+
+    pub async fn grammar_tags(&self, page: PageRequest) -> AppResult<Page<LexiconGrammarTag>> {
+        fetch_page(
+            self.pool,
+            "SELECT COUNT(*) FROM grammar_tag",
+            "SELECT id, name FROM grammar_tag ORDER BY id LIMIT $1 OFFSET $2",
+            page,
+        )
+        .await
+    }
+
+    pub async fn words_with_semantic_tag(&self, tag: i32) -> AppResult<Vec<TaggedWord>> {
+        self.tagged_words("word_semantic", "semantic_tag", tag)
+            .await
+    }
+
+    pub async fn words_with_grammar_tag(
+        &self,
+        tag: i32,
+        relation: GrammarRelation,
+    ) -> AppResult<Vec<TaggedWord>> {
+        self.tagged_words(relation.table(), "grammar_tag", tag)
+            .await
+    }
+
+    pub async fn create_word(&self, word: NewWord) -> AppResult<LexiconWord> {
+        validate_word_content(&word.content)?;
+        validate_weight(word.weight)?;
+
+        let mut transaction = begin(self.pool).await?;
+        let language_id = language_id(&mut transaction, &word.language).await?;
+        let id = sqlx::query_scalar::<Postgres, i32>(
+            "INSERT INTO word (content, language, non_repeatable, weight) VALUES ($1, $2, $3, $4) RETURNING id",
+        )
+        .bind(&word.content)
+        .bind(language_id)
+        .bind(word.non_repeatable)
+        .bind(word.weight)
+        .fetch_one(&mut transaction)
+        .await
+        .map_err(|error| unique_violation_or_sql(error, WORD, &word.content))?;
+
+        let created = select_word(&mut transaction, id).await?;
+        commit(transaction).await?;
+        Ok(created)
+    }
+
+    pub async fn update_word(&self, id: i32, changes: WordChanges) -> AppResult<LexiconWord> {
+        if let Some(content) = &changes.content {
+            validate_word_content(content)?;
+        }
+        if let Some(weight) = changes.weight {
+            validate_weight(weight)?;
+        }
+
+        let mut transaction = begin(self.pool).await?;
+        let updated = sqlx::query(
+            "UPDATE word SET content = COALESCE($2, content), non_repeatable = COALESCE($3, non_repeatable), weight = COALESCE($4, weight) WHERE id = $1",
+        )
+        .bind(id)
+        .bind(&changes.content)
+        .bind(changes.non_repeatable)
+        .bind(changes.weight)
+        .execute(&mut transaction)
+        .await
+        .map_err(|error| {
+            unique_violation_or_sql(error, WORD, changes.content.as_deref().unwrap_or_default())
+        })?;
+        if updated.rows_affected() == 0 {
+            return Err(AppError::for_edit_not_found(WORD, id));
+        }
+
+        let word = select_word(&mut transaction, id).await?;
+        commit(transaction).await?;
+        Ok(word)
+    }
+
+    /// Tags attached to the word are detached first; the deleted word is returned.
+    pub async fn delete_word(&self, id: i32) -> AppResult<LexiconWord> {
+        let mut transaction = begin(self.pool).await?;
+        let word = select_word(&mut transaction, id).await?;
+
+        for table in [
+            "word_semantic",
+            "word_grammar_compatibility",
+            "word_grammar_requirements",
+        ] {
+            execute(
+                &mut transaction,
+                sqlx::query(&format!("DELETE FROM {} WHERE word = $1", table)).bind(id),
+            )
+            .await?;
+        }
+        execute(
+            &mut transaction,
+            sqlx::query("DELETE FROM word WHERE id = $1").bind(id),
+        )
+        .await?;
+
+        commit(transaction).await?;
+        Ok(word)
+    }
+
+    pub async fn create_semantic_tag(
+        &self,
+        name: &str,
+        sticky: bool,
+    ) -> AppResult<LexiconSemanticTag> {
+        validate_tag_name(SEMANTIC_TAG, name)?;
+
+        let mut transaction = begin(self.pool).await?;
+        let tag = sqlx::query_as::<Postgres, LexiconSemanticTag>(
+            "INSERT INTO semantic_tag (name, sticky) VALUES ($1, $2) RETURNING id, name, sticky",
+        )
+        .bind(name)
+        .bind(sticky)
+        .fetch_one(&mut transaction)
+        .await
+        .map_err(|error| unique_violation_or_sql(error, SEMANTIC_TAG, name))?;
+
+        commit(transaction).await?;
+        Ok(tag)
+    }
+
+    pub async fn update_semantic_tag(
+        &self,
+        id: i32,
+        name: Option<&str>,
+        sticky: Option<bool>,
+    ) -> AppResult<LexiconSemanticTag> {
+        if let Some(name) = name {
+            validate_tag_name(SEMANTIC_TAG, name)?;
+        }
+
+        let mut transaction = begin(self.pool).await?;
+        let tag = sqlx::query_as::<Postgres, LexiconSemanticTag>(
+            "UPDATE semantic_tag SET name = COALESCE($2, name), sticky = COALESCE($3, sticky) WHERE id = $1 RETURNING id, name, sticky",
+        )
+        .bind(id)
+        .bind(name)
+        .bind(sticky)
+        .fetch_optional(&mut transaction)
+        .await
+        .map_err(|error| unique_violation_or_sql(error, SEMANTIC_TAG, name.unwrap_or_default()))?
+        .ok_or_else(|| AppError::for_edit_not_found(SEMANTIC_TAG, id))?;
+
+        commit(transaction).await?;
+        Ok(tag)
+    }
+
+    /// The tag is detached from words and categories first; the deleted tag is returned.
+    pub async fn delete_semantic_tag(&self, id: i32) -> AppResult<LexiconSemanticTag> {
+        let mut transaction = begin(self.pool).await?;
+
+        for table in ["word_semantic", "category_semantic"] {
+            execute(
+                &mut transaction,
+                sqlx::query(&format!("DELETE FROM {} WHERE semantic_tag = $1", table)).bind(id),
+            )
+            .await?;
+        }
+        let tag = sqlx::query_as::<Postgres, LexiconSemanticTag>(
+            "DELETE FROM semantic_tag WHERE id = $1 RETURNING id, name, sticky",
+        )
+        .bind(id)
+        .fetch_optional(&mut transaction)
+        .await
+        .map_err(AppError::for_edit_in_sql)?
+        .ok_or_else(|| AppError::for_edit_not_found(SEMANTIC_TAG, id))?;
+
+        commit(transaction).await?;
+        Ok(tag)
+    }
+
+    pub async fn create_grammar_tag(&self, name: &str) -> AppResult<LexiconGrammarTag> {
+        validate_tag_name(GRAMMAR_TAG, name)?;
+
+        let mut transaction = begin(self.pool).await?;
+        let tag = sqlx::query_as::<Postgres, LexiconGrammarTag>(
+            "INSERT INTO grammar_tag (name) VALUES ($1) RETURNING id, name",
+        )
+        .bind(name)
+        .fetch_one(&mut transaction)
+        .await
+        .map_err(|error| unique_violation_or_sql(error, GRAMMAR_TAG, name))?;
+
+        commit(transaction).await?;
+        Ok(tag)
+    }
+
+    pub async fn update_grammar_tag(&self, id: i32, name: &str) -> AppResult<LexiconGrammarTag> {
+        validate_tag_name(GRAMMAR_TAG, name)?;
+
+        let mut transaction = begin(self.pool).await?;
+        let tag = sqlx::query_as::<Postgres, LexiconGrammarTag>(
+            "UPDATE grammar_tag SET name = $2 WHERE id = $1 RETURNING id, name",
+        )
+        .bind(id)
+        .bind(name)
+        .fetch_optional(&mut transaction)
+        .await
+        .map_err(|error| unique_violation_or_sql(error, GRAMMAR_TAG, name))?
+        .ok_or_else(|| AppError::for_edit_not_found(GRAMMAR_TAG, id))?;
+
+        commit(transaction).await?;
+        Ok(tag)
+    }
+
+    /// The tag is detached from words first; the deleted tag is returned.
+    pub async fn delete_grammar_tag(&self, id: i32) -> AppResult<LexiconGrammarTag> {
+        let mut transaction = begin(self.pool).await?;
+
+        for relation in [GrammarRelation::Compatibility, GrammarRelation::Requirement] {
+            execute(
+                &mut transaction,
+                sqlx::query(&format!(
+                    "DELETE FROM {} WHERE grammar_tag = $1",
+                    relation.table()
+                ))
+                .bind(id),
+            )
+            .await?;
+        }
+        let tag = sqlx::query_as::<Postgres, LexiconGrammarTag>(
+            "DELETE FROM grammar_tag WHERE id = $1 RETURNING id, name",
+        )
+        .bind(id)
+        .fetch_optional(&mut transaction)
+        .await
+        .map_err(AppError::for_edit_in_sql)?
+        .ok_or_else(|| AppError::for_edit_not_found(GRAMMAR_TAG, id))?;
+
+        commit(transaction).await?;
+        Ok(tag)
+    }
+
+    /// Attaching an already attached tag changes nothing.
+    pub async fn attach_semantic_tag(&self, word: i32, tag: i32) -> AppResult<LexiconWord> {
+        self.change_attachment(
+            word,
+            (SEMANTIC_TAG, "semantic_tag", tag),
+            "INSERT INTO word_semantic (word, semantic_tag) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+        )
+        .await
+    }
+
+    /// Detaching a tag that is not attached changes nothing.
+    pub async fn detach_semantic_tag(&self, word: i32, tag: i32) -> AppResult<LexiconWord> {
+        self.change_attachment(
+            word,
+            (SEMANTIC_TAG, "semantic_tag", tag),
+            "DELETE FROM word_semantic WHERE word = $1 AND semantic_tag = $2",
+        )
+        .await
+    }
+
+    pub async fn attach_grammar_tag(
+        &self,
+        word: i32,
+        tag: i32,
+        relation: GrammarRelation,
+    ) -> AppResult<LexiconWord> {
+        self.change_attachment(
+            word,
+            (GRAMMAR_TAG, "grammar_tag", tag),
+            &format!(
+                "INSERT INTO {} (word, grammar_tag) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+                relation.table()
+            ),
+        )
+        .await
+    }
+
+    pub async fn detach_grammar_tag(
+        &self,
+        word: i32,
+        tag: i32,
+        relation: GrammarRelation,
+    ) -> AppResult<LexiconWord> {
+        self.change_attachment(
+            word,
+            (GRAMMAR_TAG, "grammar_tag", tag),
+            &format!(
+                "DELETE FROM {} WHERE word = $1 AND grammar_tag = $2",
+                relation.table()
+            ),
+        )
+        .await
+    }
+
+    async fn tagged_words(
+        &self,
+        table: &'static str,
+        column: &'static str,
+        tag: i32,
+    ) -> AppResult<Vec<TaggedWord>> {
+        sqlx::query_as::<Postgres, TaggedWord>(&format!(
+            "SELECT w.id, w.content FROM word w INNER JOIN {table} t ON t.word = w.id WHERE t.{column} = $1 ORDER BY w.id"
+        ))
+        .bind(tag)
+        .fetch_all(self.pool)
+        .await
+        .map_err(AppError::for_browsing_in_sql)
+    }
+
+    /// Runs `statement` with the word and tag ids once both are known to exist.
+    async fn change_attachment(
+        &self,
+        word: i32,
+        (entity, table, tag): (&'static str, &'static str, i32),
+        statement: &str,
+    ) -> AppResult<LexiconWord> {
+        let mut transaction = begin(self.pool).await?;
+        select_word(&mut transaction, word).await?;
+
+        let tag_exists = sqlx::query_scalar::<Postgres, bool>(&format!(
+            "SELECT EXISTS (SELECT 1 FROM {} WHERE id = $1)",
+            table
+        ))
+        .bind(tag)
+        .fetch_one(&mut transaction)
+        .await
+        .map_err(AppError::for_edit_in_sql)?;
+        if !tag_exists {
+            return Err(AppError::for_edit_not_found(entity, tag));
+        }
+
+        execute(
+            &mut transaction,
+            sqlx::query(statement).bind(word).bind(tag),
+        )
+        .await?;
+
+        let changed = select_word(&mut transaction, word).await?;
+        commit(transaction).await?;
+        Ok(changed)
+    }
+}
+
+async fn select_word(
+    transaction: &mut Transaction<'_, Postgres>,
+    id: i32,
+) -> AppResult<LexiconWord> {
+    sqlx::query_as::<Postgres, LexiconWord>(include_str!("../../../draft_ideas/select_word.sql"))
+        .bind(id)
+        .fetch_optional(&mut *transaction)
+        .await
+        .map_err(AppError::for_edit_in_sql)?
+        .ok_or_else(|| AppError::for_edit_not_found(WORD, id))
+}
+
+fn validate_word_content(content: &str) -> AppResult<()> {
+    validate_length("word content", content, MAX_WORD_LENGTH)
+}
+
+fn validate_tag_name(field: &'static str, name: &str) -> AppResult<()> {
+    validate_name(field, name, MAX_TAG_NAME_LENGTH)
+}
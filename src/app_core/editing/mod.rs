@@ -0,0 +1,177 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use sqlx::postgres::PgRow;
+use sqlx::{FromRow, Pool, Postgres, Transaction};
+
+use crate::app_core::errors::AppError;
+use crate::app_core::AppResult;
+
+pub mod grammar;
+pub mod lexicon;
+pub mod voices;
+
+#[cfg(test)]
+#[path = "./unit_tests/mod.rs"]
+mod tests;
+
+const UNIQUE_VIOLATION: &str = "23505";
+
+const LANGUAGE: &str = "language";
+
+/// Most items a single page of a listing can hold.
+pub const MAX_PAGE_SIZE: i64 = 100;
+
+lazy_static! {
+    // Tag and non-terminal symbol names end up inside production selectors, which only accept word characters
+    static ref NAME: Regex = Regex::new(r"^\w+$").unwrap();
+}
+
+/// Slice of a listing ordered by id, along with the size of the whole listing.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Page<T> {
+    pub total: i64,
+    pub offset: i64,
+    pub items: Vec<T>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PageRequest {
+    offset: i64,
+    limit: i64,
+}
+
+impl PageRequest {
+    /// Negative offsets start from the beginning, limits are kept between 1 and [`MAX_PAGE_SIZE`].
+    pub fn new(offset: i64, limit: i64) -> Self {
+        Self {
+            offset: offset.max(0),
+            limit: limit.clamp(1, MAX_PAGE_SIZE),
+        }
+    }
+
+    pub fn offset(&self) -> i64 {
+        self.offset
+    }
+
+    pub fn limit(&self) -> i64 {
+        self.limit
+    }
+}
+
+/// `select` gets the limit as `$1` and the offset as `$2`.
+async fn fetch_page<T>(
+    pool: &Pool<Postgres>,
+    count: &str,
+    select: &str,
+    page: PageRequest,
+) -> AppResult<Page<T>>
+where
+    T: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+{
+    let total = sqlx::query_scalar::<Postgres, i64>(count)
+        .fetch_one(pool)
+        .await
+        .map_err(AppError::for_browsing_in_sql)?;
+    let items = sqlx::query_as::<Postgres, T>(select)
+        .bind(page.limit)
+        .bind(page.offset)
+        .fetch_all(pool)
+        .await
+        .map_err(AppError::for_browsing_in_sql)?;
+
+    Ok(Page {
+        total,
+        offset: page.offset,
+        items,
+    })
+}
+
+async fn begin(pool: &Pool<Postgres>) -> AppResult<Transaction<'static, Postgres>> {
+    pool.begin().await.map_err(AppError::for_edit_in_sql)
+}
+
+async fn execute<'q>(
+    transaction: &mut Transaction<'_, Postgres>,
+    query: sqlx::query::Query<'q, Postgres, sqlx::postgres::PgArguments>,
+) -> AppResult<()> {
+    query
+        .execute(&mut *transaction)
+        .await
+        .map(|_| ())
+        .map_err(AppError::for_edit_in_sql)
+}
+
+async fn commit(transaction: Transaction<'_, Postgres>) -> AppResult<()> {
+    transaction
+        .commit()
+        .await
+        .map_err(AppError::for_edit_in_sql)
+}
+
+/// Id of the language with the given code.
+async fn language_id(transaction: &mut Transaction<'_, Postgres>, code: &str) -> AppResult<i32> {
+    sqlx::query_scalar::<Postgres, i32>("SELECT id FROM language WHERE code = $1")
+        .bind(code)
+        .fetch_optional(&mut *transaction)
+        .await
+        .map_err(AppError::for_edit_in_sql)?
+        .ok_or_else(|| AppError::for_edit_unknown_name(LANGUAGE, code.to_owned()))
+}
+
+fn unique_violation_or_sql(error: sqlx::Error, entity: &'static str, value: &str) -> AppError {
+    let is_unique_violation = error
+        .as_database_error()
+        .and_then(|database_error| database_error.code())
+        .map_or(false, |code| code == UNIQUE_VIOLATION);
+
+    if is_unique_violation {
+        AppError::for_edit_already_existing(entity, value.to_owned())
+    } else {
+        AppError::for_edit_in_sql(error)
+    }
+}
+
+pub(crate) fn validate_name(field: &'static str, name: &str, max_length: usize) -> AppResult<()> {
+    validate_length(field, name, max_length)?;
+    if NAME.is_match(name) {
+        Ok(())
+    } else {
+        Err(AppError::for_edit_invalid_name(field, name.to_owned()))
+    }
+}
+
+/// Lengths are counted in characters, like `varchar` does.
+pub(crate) fn validate_length(
+    field: &'static str,
+    value: &str,
+    max_length: usize,
+) -> AppResult<()> {
+    if value.trim().is_empty() {
+        Err(AppError::for_edit_empty_value(field))
+    } else if value.chars().count() > max_length {
+        Err(AppError::for_edit_value_too_long(
+            field,
+            max_length,
+            value.to_owned(),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+fn validate_weight(weight: i32) -> AppResult<()> {
+    if weight > 0 {
+        Ok(())
+    } else {
+        Err(AppError::for_edit_non_positive_weight(weight))
+    }
+}
+
+/// Bounds are included; NaN is never in range.
+fn validate_range(field: &'static str, value: f32, min: f32, max: f32) -> AppResult<()> {
+    if (min..=max).contains(&value) {
+        Ok(())
+    } else {
+        Err(AppError::for_edit_out_of_range(field, min, max, value))
+    }
+}
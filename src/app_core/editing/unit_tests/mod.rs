@@ -0,0 +1,88 @@
+use crate::app_core::editing::lexicon::{MAX_TAG_NAME_LENGTH, MAX_WORD_LENGTH};
+use crate::app_core::editing::voices::{MAX_VOICE_FACTOR, MIN_VOICE_FACTOR};
+use crate::app_core::editing::{
+    validate_length, validate_name, validate_range, validate_weight, PageRequest, MAX_PAGE_SIZE,
+};
+use crate::app_core::errors::{AppError, EditError};
+
+#[test]
+fn lengths_are_counted_in_characters() {
+    assert!(validate_length(
+        "word content",
+        &"è".repeat(MAX_WORD_LENGTH),
+        MAX_WORD_LENGTH
+    )
+    .is_ok());
+    assert!(matches!(
+        validate_length(
+            "word content",
+            &"è".repeat(MAX_WORD_LENGTH + 1),
+            MAX_WORD_LENGTH
+        ),
+        Err(AppError::Edit(EditError::ValueTooLong(
+            _,
+            MAX_WORD_LENGTH,
+            _
+        )))
+    ));
+}
+
+#[test]
+fn blank_values_are_rejected() {
+    assert!(matches!(
+        validate_length("word content", "  ", MAX_WORD_LENGTH),
+        Err(AppError::Edit(EditError::EmptyValue(_)))
+    ));
+    assert!(matches!(
+        validate_name("semantic tag", "", MAX_TAG_NAME_LENGTH),
+        Err(AppError::Edit(EditError::EmptyValue(_)))
+    ));
+}
+
+#[test]
+fn names_must_fit_production_selectors() {
+    assert!(validate_name("semantic tag", "animal_2", MAX_TAG_NAME_LENGTH).is_ok());
+    assert!(matches!(
+        validate_name("semantic tag", "animal,verb", MAX_TAG_NAME_LENGTH),
+        Err(AppError::Edit(EditError::InvalidName(_, _)))
+    ));
+    assert!(matches!(
+        validate_name(
+            "grammar tag",
+            &"a".repeat(MAX_TAG_NAME_LENGTH + 1),
+            MAX_TAG_NAME_LENGTH
+        ),
+        Err(AppError::Edit(EditError::ValueTooLong(_, _, _)))
+    ));
+}
+
+#[test]
+fn weights_must_be_positive() {
+    assert!(validate_weight(1).is_ok());
+    assert!(matches!(
+        validate_weight(0),
+        Err(AppError::Edit(EditError::NonPositiveWeight(0)))
+    ));
+}
+
+#[test]
+fn voice_factors_must_be_within_bounds() {
+    let validate = |value| validate_range("rate", value, MIN_VOICE_FACTOR, MAX_VOICE_FACTOR);
+
+    assert!(validate(MIN_VOICE_FACTOR).is_ok());
+    assert!(validate(MAX_VOICE_FACTOR).is_ok());
+    assert!(matches!(
+        validate(0.0),
+        Err(AppError::Edit(EditError::OutOfRange("rate", _, _, _)))
+    ));
+    assert!(validate(f32::NAN).is_err());
+}
+
+#[test]
+fn page_requests_are_kept_within_bounds() {
+    assert_eq!(PageRequest::new(-3, 0), PageRequest::new(0, 1));
+    assert_eq!(
+        PageRequest::new(40, MAX_PAGE_SIZE + 1),
+        PageRequest::new(40, MAX_PAGE_SIZE)
+    );
+}
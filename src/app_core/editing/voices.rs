@@ -0,0 +1,153 @@
+use sqlx::{FromRow, Pool, Postgres};
+
+use super::{
+    begin, commit, language_id, unique_violation_or_sql, validate_length, validate_name,
+    validate_range,
+};
+use crate::app_core::errors::AppError;
+use crate::app_core::AppResult;
+
+/// Column sizes from the `voices` migration.
+pub const MAX_PROVIDER_LENGTH: usize = 16;
+pub const MAX_VOICE_NAME_LENGTH: usize = 32;
+/// Bounds of speaking rates and pitches, relative to the provider defaults.
+pub const MIN_VOICE_FACTOR: f32 = 0.25;
+pub const MAX_VOICE_FACTOR: f32 = 4.0;
+
+const VOICE: &str = "voice";
+
+/// A voice of the catalogue. Its `name` is the one its provider knows it by, like `m3` for espeak-ng;
+/// `rate` and `pitch` scale the provider defaults, 1 keeping them.
+#[derive(FromRow, Clone, Debug, PartialEq)]
+pub struct CatalogueVoice {
+    pub id: i32,
+    /// Code of the language the voice speaks.
+    pub language: String,
+    /// Locale of that language, handed to the provider.
+    pub locale: String,
+    pub gender: String,
+    pub provider: String,
+    pub name: String,
+    pub rate: f32,
+    pub pitch: f32,
+}
+
+pub struct NewVoice {
+    /// Code of the language the voice speaks.
+    pub language: String,
+    pub gender: String,
+    pub provider: String,
+    pub name: String,
+    pub rate: f32,
+    pub pitch: f32,
+}
+
+/// How a speech picks its voice.
+#[derive(Clone, Debug, PartialEq)]
+pub enum VoiceChoice {
+    /// A voice of the catalogue, whatever its provider.
+    Id(i32),
+    /// The first voice, by id, of the provider in use speaking `language`, in `gender` when given.
+    Matching {
+        language: String,
+        gender: Option<String>,
+    },
+}
+
+const SELECT_VOICES: &str = "SELECT v.id, l.code AS language, l.locale, v.gender::text AS gender, v.provider, v.name, v.rate, v.pitch FROM voice v INNER JOIN language l ON l.id = v.language";
+
+/// Lists, picks and adds the voices speeches are synthesized with.
+pub struct Voices<'p> {
+    pool: &'p Pool<Postgres>,
+}
+
+impl<'p> Voices<'p> {
+    pub fn new(pool: &'p Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    /// Voices of `provider`, of every language unless one is given.
+    pub async fn voices(
+        &self,
+        provider: &str,
+        language: Option<&str>,
+    ) -> AppResult<Vec<CatalogueVoice>> {
+        sqlx::query_as::<Postgres, CatalogueVoice>(&format!(
+            "{SELECT_VOICES} WHERE v.provider = $1 AND ($2::text IS NULL OR l.code = $2) ORDER BY v.id"
+        ))
+        .bind(provider)
+        .bind(language)
+        .fetch_all(self.pool)
+        .await
+        .map_err(AppError::for_browsing_in_sql)
+    }
+
+    /// `provider` is the one in use, only matching choices depend on it.
+    pub async fn choose(&self, choice: &VoiceChoice, provider: &str) -> AppResult<CatalogueVoice> {
+        match choice {
+            VoiceChoice::Id(id) => {
+                sqlx::query_as::<Postgres, CatalogueVoice>(&format!(
+                    "{SELECT_VOICES} WHERE v.id = $1"
+                ))
+                .bind(id)
+                .fetch_optional(self.pool)
+                .await
+                .map_err(AppError::for_browsing_in_sql)?
+                .ok_or_else(|| AppError::for_unknown_voice(format!("with id {id}")))
+            }
+            VoiceChoice::Matching { language, gender } => {
+                sqlx::query_as::<Postgres, CatalogueVoice>(&format!(
+                    "{SELECT_VOICES} WHERE v.provider = $1 AND l.code = $2 AND ($3::text IS NULL OR v.gender::text = $3) ORDER BY v.id LIMIT 1"
+                ))
+                .bind(provider)
+                .bind(language)
+                .bind(gender)
+                .fetch_optional(self.pool)
+                .await
+                .map_err(AppError::for_browsing_in_sql)?
+                .ok_or_else(|| {
+                    AppError::for_unknown_voice(format!(
+                        "of provider '{provider}' speaking '{language}'{}",
+                        gender
+                            .as_ref()
+                            .map(|gender| format!(" as {gender}"))
+                            .unwrap_or_default()
+                    ))
+                })
+            }
+        }
+    }
+
+    /// Names are unique among the voices of a provider speaking the same language.
+    pub async fn create_voice(&self, voice: NewVoice) -> AppResult<CatalogueVoice> {
+        validate_name("provider", &voice.provider, MAX_PROVIDER_LENGTH)?;
+        validate_length("voice name", &voice.name, MAX_VOICE_NAME_LENGTH)?;
+        validate_range("rate", voice.rate, MIN_VOICE_FACTOR, MAX_VOICE_FACTOR)?;
+        validate_range("pitch", voice.pitch, MIN_VOICE_FACTOR, MAX_VOICE_FACTOR)?;
+
+        let mut transaction = begin(self.pool).await?;
+        let language_id = language_id(&mut transaction, &voice.language).await?;
+        let id = sqlx::query_scalar::<Postgres, i32>(
+            "INSERT INTO voice (language, gender, provider, name, rate, pitch) VALUES ($1, $2::gender, $3, $4, $5, $6) RETURNING id",
+        )
+        .bind(language_id)
+        .bind(&voice.gender)
+        .bind(&voice.provider)
+        .bind(&voice.name)
+        .bind(voice.rate)
+        .bind(voice.pitch)
+        .fetch_one(&mut transaction)
+        .await
+        .map_err(|error| unique_violation_or_sql(error, VOICE, &voice.name))?;
+
+        let created =
+            sqlx::query_as::<Postgres, CatalogueVoice>(&format!("{SELECT_VOICES} WHERE v.id = $1"))
+                .bind(id)
+                .fetch_one(&mut transaction)
+                .await
+                .map_err(AppError::for_edit_in_sql)?;
+
+        commit(transaction).await?;
+        Ok(created)
+    }
+}
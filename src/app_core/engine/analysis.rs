@@ -0,0 +1,141 @@
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+use itertools::Itertools;
+
+use crate::app_core::engine::sources::GrammarDefinition;
+use crate::app_core::engine::types::{PlaceholderReference, ProductionBranch};
+
+#[cfg(test)]
+#[path = "./unit_tests/analysis.rs"]
+mod tests;
+
+/// What can be told about a non-terminal symbol by just looking at the productions.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SymbolAnalysis {
+    pub name: String,
+    /// Whether any start symbol can eventually expand into it.
+    pub reachable: bool,
+    /// Whether at least one of its derivations terminates: unproductive symbols always hit the depth limit.
+    pub productive: bool,
+    /// Height of the lowest complete derivation tree, the symbol itself included.
+    pub min_depth: Option<u32>,
+    /// Non-terminal symbols expanded by the smallest complete derivation, the symbol itself included.
+    pub min_non_terminal_symbols: Option<u32>,
+}
+
+/// Productions that cannot be parsed are ignored, the validator reports them on their own.
+pub fn analyse_definition(
+    definition: &GrammarDefinition,
+    start_symbols: &[&str],
+) -> Vec<SymbolAnalysis> {
+    let rules = expansion_rules(definition);
+    let reachable = reachable_symbols(&rules, start_symbols);
+    let min_depth = least_fixpoint(&rules, |children| {
+        children
+            .iter()
+            .copied()
+            .max()
+            .unwrap_or(0)
+            .saturating_add(1)
+    });
+    let min_non_terminal_symbols = least_fixpoint(&rules, |children| {
+        children
+            .iter()
+            .fold(1u32, |total, &child| total.saturating_add(child))
+    });
+
+    definition
+        .non_terminal_symbols
+        .iter()
+        .map(|name| SymbolAnalysis {
+            name: name.clone(),
+            reachable: reachable.contains(name.as_str()),
+            productive: min_depth.contains_key(name.as_str()),
+            min_depth: min_depth.get(name.as_str()).copied(),
+            min_non_terminal_symbols: min_non_terminal_symbols.get(name.as_str()).copied(),
+        })
+        .collect()
+}
+
+/// For every symbol, the non-terminal symbols referenced by each of its productions.
+fn expansion_rules(definition: &GrammarDefinition) -> HashMap<&str, Vec<Vec<String>>> {
+    let mut rules: HashMap<&str, Vec<Vec<String>>> = HashMap::new();
+
+    for production in definition.productions.iter() {
+        if let Ok(branch) = ProductionBranch::from_str(&production.production) {
+            rules
+                .entry(production.non_terminal_symbol.as_str())
+                .or_default()
+                .push(
+                    branch
+                        .placeholder_references()
+                        .iter()
+                        .filter_map(|placeholder| match placeholder {
+                            PlaceholderReference::NonTerminalSymbol(token) => {
+                                Some(token.reference().to_owned())
+                            }
+                            PlaceholderReference::WordSelector(_) => None,
+                        })
+                        .collect_vec(),
+                );
+        }
+    }
+
+    rules
+}
+
+fn reachable_symbols<'a>(
+    rules: &'a HashMap<&str, Vec<Vec<String>>>,
+    start_symbols: &[&'a str],
+) -> HashSet<&'a str> {
+    let mut reached: HashSet<&str> = HashSet::new();
+    let mut to_visit = start_symbols.to_vec();
+
+    while let Some(symbol) = to_visit.pop() {
+        if !reached.insert(symbol) {
+            continue;
+        }
+        for child in rules.get(symbol).into_iter().flatten().flatten() {
+            to_visit.push(child.as_str());
+        }
+    }
+
+    reached
+}
+
+/// Least value of `combine` over the productions of every symbol, where `combine` receives the values of
+/// the referenced symbols; symbols whose productions all reference a valueless symbol get no value.
+fn least_fixpoint<'a>(
+    rules: &HashMap<&'a str, Vec<Vec<String>>>,
+    combine: impl Fn(&[u32]) -> u32,
+) -> HashMap<&'a str, u32> {
+    let mut best: HashMap<&str, u32> = HashMap::new();
+
+    loop {
+        let mut changed = false;
+
+        for (&symbol, productions) in rules.iter() {
+            for children in productions {
+                let values = children
+                    .iter()
+                    .map(|child| best.get(child.as_str()).copied())
+                    .collect::<Option<Vec<_>>>();
+
+                if let Some(candidate) = values.as_deref().map(&combine) {
+                    if best
+                        .get(symbol)
+                        .map_or(true, |&current| candidate < current)
+                    {
+                        best.insert(symbol, candidate);
+                        changed = true;
+                    }
+                }
+            }
+        }
+
+        if !changed {
+            return best;
+        }
+    }
+}
@@ -0,0 +1,279 @@
+use sqlx::{FromRow, Postgres, Transaction};
+
+use super::{
+    CategoryEntry, GrammarFile, NonTerminalSymbolEntry, ProductionEntry, SemanticTagEntry,
+    WordEntry,
+};
+use crate::app_core::errors::AppError;
+use crate::app_core::AppResult;
+
+#[derive(FromRow)]
+struct CategoryRow {
+    name: String,
+    start_symbol: Option<String>,
+    semantic_tags: Vec<String>,
+}
+
+#[derive(FromRow)]
+struct ProductionRow {
+    non_terminal_symbol: String,
+    production: Option<String>,
+    weight: Option<i32>,
+}
+
+#[derive(FromRow)]
+struct WordRow {
+    content: String,
+    non_repeatable: bool,
+    weight: i32,
+    semantic_tags: Vec<String>,
+    grammar_compatibilities: Vec<String>,
+    grammar_requirements: Vec<String>,
+}
+
+/// Symbols and words of the language with the given code, along with the tags and categories shared by all
+/// the languages. Entries keep the order in which they have been stored.
+pub async fn export_grammar(
+    transaction: &mut Transaction<'_, Postgres>,
+    language: &str,
+) -> AppResult<GrammarFile> {
+    check_language(transaction, language).await?;
+
+    let semantic_tags = sqlx::query_as::<Postgres, (String, bool)>(
+        "SELECT name, sticky FROM semantic_tag ORDER BY id",
+    )
+    .fetch_all(&mut *transaction)
+    .await
+    .map_err(AppError::for_grammar_storage_in_sql)?
+    .into_iter()
+    .map(|(name, sticky)| SemanticTagEntry { name, sticky })
+    .collect();
+
+    let grammar_tags =
+        sqlx::query_scalar::<Postgres, String>("SELECT name FROM grammar_tag ORDER BY id")
+            .fetch_all(&mut *transaction)
+            .await
+            .map_err(AppError::for_grammar_storage_in_sql)?;
+
+    let categories = sqlx::query_as::<Postgres, CategoryRow>(include_str!(
+        "../../../../draft_ideas/export_categories.sql"
+    ))
+    .fetch_all(&mut *transaction)
+    .await
+    .map_err(AppError::for_grammar_storage_in_sql)?
+    .into_iter()
+    .map(|row| CategoryEntry {
+        name: row.name,
+        start_symbol: row.start_symbol,
+        semantic_tags: row.semantic_tags,
+    })
+    .collect();
+
+    let mut non_terminal_symbols: Vec<NonTerminalSymbolEntry> = Vec::new();
+    for row in sqlx::query_as::<Postgres, ProductionRow>(include_str!(
+        "../../../../draft_ideas/export_productions.sql"
+    ))
+    .bind(language)
+    .fetch_all(&mut *transaction)
+    .await
+    .map_err(AppError::for_grammar_storage_in_sql)?
+    {
+        // Rows come grouped by non-terminal symbol, the ones without productions come with nulls
+        if non_terminal_symbols.last().map(|last| &last.name) != Some(&row.non_terminal_symbol) {
+            non_terminal_symbols.push(NonTerminalSymbolEntry {
+                name: row.non_terminal_symbol,
+                productions: vec![],
+            });
+        }
+        if let (Some(production), Some(weight), Some(last)) =
+            (row.production, row.weight, non_terminal_symbols.last_mut())
+        {
+            last.productions
+                .push(ProductionEntry { production, weight });
+        }
+    }
+
+    let words = sqlx::query_as::<Postgres, WordRow>(include_str!(
+        "../../../../draft_ideas/export_words.sql"
+    ))
+    .bind(language)
+    .fetch_all(&mut *transaction)
+    .await
+    .map_err(AppError::for_grammar_storage_in_sql)?
+    .into_iter()
+    .map(|row| WordEntry {
+        content: row.content,
+        non_repeatable: row.non_repeatable,
+        weight: row.weight,
+        semantic_tags: row.semantic_tags,
+        grammar_compatibilities: row.grammar_compatibilities,
+        grammar_requirements: row.grammar_requirements,
+    })
+    .collect();
+
+    Ok(GrammarFile {
+        semantic_tags,
+        grammar_tags,
+        categories,
+        non_terminal_symbols,
+        words,
+    })
+}
+
+/// Merges the file into the stored grammar, its symbols and words going to the language with the given code:
+/// entries are matched by name (by content for words, by text for productions) and updated, nothing gets
+/// deleted.
+pub async fn import_grammar(
+    transaction: &mut Transaction<'_, Postgres>,
+    grammar: &GrammarFile,
+    language: &str,
+) -> AppResult<()> {
+    check_language(transaction, language).await?;
+
+    for tag in grammar.semantic_tags.iter() {
+        execute(
+            transaction,
+            sqlx::query("INSERT INTO semantic_tag (name, sticky) VALUES ($1, $2) ON CONFLICT (name) DO UPDATE SET sticky = EXCLUDED.sticky")
+                .bind(&tag.name)
+                .bind(tag.sticky),
+        )
+        .await?;
+    }
+
+    for tag in grammar.grammar_tags.iter() {
+        execute(
+            transaction,
+            sqlx::query("INSERT INTO grammar_tag (name) VALUES ($1) ON CONFLICT (name) DO NOTHING")
+                .bind(tag),
+        )
+        .await?;
+    }
+
+    for non_terminal_symbol in grammar.non_terminal_symbols.iter() {
+        execute(
+            transaction,
+            sqlx::query(
+                "INSERT INTO non_terminal_symbol (name, language) VALUES ($1, (SELECT id FROM language WHERE code = $2)) ON CONFLICT (language, name) DO NOTHING",
+            )
+            .bind(&non_terminal_symbol.name)
+            .bind(language),
+        )
+        .await?;
+    }
+
+    for non_terminal_symbol in grammar.non_terminal_symbols.iter() {
+        for production in non_terminal_symbol.productions.iter() {
+            execute(
+                transaction,
+                sqlx::query("UPDATE production p SET weight = $3 FROM non_terminal_symbol nts WHERE nts.id = p.non_terminal_symbol AND nts.name = $1 AND p.production = $2 AND nts.language = (SELECT id FROM language WHERE code = $4)")
+                    .bind(&non_terminal_symbol.name)
+                    .bind(&production.production)
+                    .bind(production.weight)
+                    .bind(language),
+            )
+            .await?;
+            execute(
+                transaction,
+                sqlx::query("INSERT INTO production (non_terminal_symbol, production, weight) SELECT nts.id, $2, $3 FROM non_terminal_symbol nts WHERE nts.name = $1 AND nts.language = (SELECT id FROM language WHERE code = $4) AND NOT EXISTS (SELECT 1 FROM production p WHERE p.non_terminal_symbol = nts.id AND p.production = $2)")
+                    .bind(&non_terminal_symbol.name)
+                    .bind(&production.production)
+                    .bind(production.weight)
+                    .bind(language),
+            )
+            .await?;
+        }
+    }
+
+    for category in grammar.categories.iter() {
+        execute(
+            transaction,
+            sqlx::query("INSERT INTO category (name, start_symbol) VALUES ($1, $2) ON CONFLICT (name) DO UPDATE SET start_symbol = EXCLUDED.start_symbol")
+                .bind(&category.name)
+                .bind(&category.start_symbol),
+        )
+        .await?;
+        for tag in category.semantic_tags.iter() {
+            execute(
+                transaction,
+                sqlx::query("INSERT INTO category_semantic (category, semantic_tag) SELECT c.id, s.id FROM category c, semantic_tag s WHERE c.name = $1 AND s.name = $2 ON CONFLICT DO NOTHING")
+                    .bind(&category.name)
+                    .bind(tag),
+            )
+            .await?;
+        }
+    }
+
+    for word in grammar.words.iter() {
+        execute(
+            transaction,
+            sqlx::query("INSERT INTO word (content, language, non_repeatable, weight) VALUES ($1, (SELECT id FROM language WHERE code = $4), $2, $3) ON CONFLICT (language, content) DO UPDATE SET non_repeatable = EXCLUDED.non_repeatable, weight = EXCLUDED.weight")
+                .bind(&word.content)
+                .bind(word.non_repeatable)
+                .bind(word.weight)
+                .bind(language),
+        )
+        .await?;
+        for tag in word.semantic_tags.iter() {
+            execute(
+                transaction,
+                sqlx::query("INSERT INTO word_semantic (word, semantic_tag) SELECT w.id, s.id FROM word w, semantic_tag s WHERE w.content = $1 AND s.name = $2 AND w.language = (SELECT id FROM language WHERE code = $3) ON CONFLICT DO NOTHING")
+                    .bind(&word.content)
+                    .bind(tag)
+                    .bind(language),
+            )
+            .await?;
+        }
+        for tag in word.grammar_compatibilities.iter() {
+            execute(
+                transaction,
+                sqlx::query("INSERT INTO word_grammar_compatibility (word, grammar_tag) SELECT w.id, g.id FROM word w, grammar_tag g WHERE w.content = $1 AND g.name = $2 AND w.language = (SELECT id FROM language WHERE code = $3) ON CONFLICT DO NOTHING")
+                    .bind(&word.content)
+                    .bind(tag)
+                    .bind(language),
+            )
+            .await?;
+        }
+        for tag in word.grammar_requirements.iter() {
+            execute(
+                transaction,
+                sqlx::query("INSERT INTO word_grammar_requirements (word, grammar_tag) SELECT w.id, g.id FROM word w, grammar_tag g WHERE w.content = $1 AND g.name = $2 AND w.language = (SELECT id FROM language WHERE code = $3) ON CONFLICT DO NOTHING")
+                    .bind(&word.content)
+                    .bind(tag)
+                    .bind(language),
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn check_language(
+    transaction: &mut Transaction<'_, Postgres>,
+    language: &str,
+) -> AppResult<()> {
+    let exists = sqlx::query_scalar::<Postgres, bool>(
+        "SELECT EXISTS (SELECT 1 FROM language WHERE code = $1)",
+    )
+    .bind(language)
+    .fetch_one(&mut *transaction)
+    .await
+    .map_err(AppError::for_grammar_storage_in_sql)?;
+
+    if exists {
+        Ok(())
+    } else {
+        Err(AppError::for_unknown_language(language.to_owned()))
+    }
+}
+
+async fn execute<'q>(
+    transaction: &mut Transaction<'_, Postgres>,
+    query: sqlx::query::Query<'q, Postgres, sqlx::postgres::PgArguments>,
+) -> AppResult<()> {
+    query
+        .execute(&mut *transaction)
+        .await
+        .map(|_| ())
+        .map_err(AppError::for_grammar_storage_in_sql)
+}
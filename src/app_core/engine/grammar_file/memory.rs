@@ -0,0 +1,70 @@
+use super::GrammarFile;
+use crate::app_core::engine::sources::{
+    Category, InMemoryGrammarSource, InMemorySemanticTag, InMemoryWord, DEFAULT_START_SYMBOL,
+};
+
+/// Turns the file into a grammar source, tags and words get ids following their order in the file.
+pub fn load_grammar(grammar: &GrammarFile) -> InMemoryGrammarSource {
+    let mut source = InMemoryGrammarSource::new();
+    let semantic_id = |name: &String| position_id(&grammar.semantic_tags, |tag| &tag.name == name);
+    let grammar_id = |name: &String| position_id(&grammar.grammar_tags, |tag| tag == name);
+
+    for (index, tag) in grammar.semantic_tags.iter().enumerate() {
+        source.add_semantic_tag(InMemorySemanticTag {
+            id: index as i32 + 1,
+            name: tag.name.clone(),
+            sticky: tag.sticky,
+        });
+    }
+
+    for non_terminal_symbol in grammar.non_terminal_symbols.iter() {
+        source.add_non_terminal_symbol(&non_terminal_symbol.name);
+        for production in non_terminal_symbol.productions.iter() {
+            source.add_weighted_production(
+                &non_terminal_symbol.name,
+                &production.production,
+                production.weight,
+            );
+        }
+    }
+
+    for category in grammar.categories.iter() {
+        source.add_category(Category {
+            name: category.name.clone(),
+            start_symbol: category
+                .start_symbol
+                .clone()
+                .unwrap_or_else(|| DEFAULT_START_SYMBOL.to_owned()),
+            semantic_tags: category.semantic_tags.clone(),
+        });
+    }
+
+    for (index, word) in grammar.words.iter().enumerate() {
+        source.add_word(InMemoryWord {
+            id: index as i32 + 1,
+            content: word.content.clone(),
+            non_repeatable: word.non_repeatable,
+            weight: word.weight,
+            semantic_tags: word.semantic_tags.iter().filter_map(semantic_id).collect(),
+            grammar_compatibilities: word
+                .grammar_compatibilities
+                .iter()
+                .filter_map(grammar_id)
+                .collect(),
+            grammar_requirements: word
+                .grammar_requirements
+                .iter()
+                .filter_map(grammar_id)
+                .collect(),
+        });
+    }
+
+    source
+}
+
+fn position_id<T>(entries: &[T], matches: impl Fn(&T) -> bool) -> Option<i32> {
+    entries
+        .iter()
+        .position(matches)
+        .map(|index| index as i32 + 1)
+}
@@ -0,0 +1,527 @@
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use itertools::Itertools;
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::app_core::editing::grammar::{MAX_NON_TERMINAL_SYMBOL_LENGTH, MAX_PRODUCTION_LENGTH};
+use crate::app_core::editing::lexicon::{MAX_TAG_NAME_LENGTH, MAX_WORD_LENGTH};
+use crate::app_core::editing::{validate_length, validate_name};
+use crate::app_core::engine::validation::check_production;
+use crate::app_core::errors::AppError;
+use crate::app_core::AppResult;
+
+pub mod database;
+pub mod memory;
+
+#[cfg(test)]
+#[path = "./unit_tests/mod.rs"]
+mod tests;
+
+lazy_static! {
+    static ref NTS_DEFINITION: Regex =
+        Regex::new(r"^(?P<name>\w+)\s*->\s*(?P<branch>.*)$").unwrap();
+    static ref BRANCH: Regex =
+        Regex::new(r"^(?:weight\((?P<weight>\d+)\)\s+)?(?P<production>\S.*)$").unwrap();
+    static ref CATEGORY: Regex = Regex::new(
+        r#"^category\s+(?:"(?P<quoted>(?:[^"\\]|\\.)*)"|(?P<name>\S+))(?P<attributes>.*)$"#
+    )
+    .unwrap();
+    static ref WORD: Regex =
+        Regex::new(r#"^word\s+"(?P<content>(?:[^"\\]|\\.)*)"(?P<attributes>.*)$"#).unwrap();
+    static ref ATTRIBUTE: Regex =
+        Regex::new(r"^(?P<key>[a-z-]+)(?:\((?P<value>[^()]*)\))?").unwrap();
+}
+
+/// Categories are never referenced from productions, so their names may hold any character.
+const MAX_CATEGORY_NAME_LENGTH: usize = 32;
+
+/// Grammar and lexicon in a human-writable text format, see the readme for its syntax.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GrammarFile {
+    pub semantic_tags: Vec<SemanticTagEntry>,
+    pub grammar_tags: Vec<String>,
+    pub categories: Vec<CategoryEntry>,
+    pub non_terminal_symbols: Vec<NonTerminalSymbolEntry>,
+    pub words: Vec<WordEntry>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SemanticTagEntry {
+    pub name: String,
+    pub sticky: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CategoryEntry {
+    pub name: String,
+    pub start_symbol: Option<String>,
+    pub semantic_tags: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct NonTerminalSymbolEntry {
+    pub name: String,
+    pub productions: Vec<ProductionEntry>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProductionEntry {
+    pub production: String,
+    pub weight: i32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct WordEntry {
+    pub content: String,
+    pub non_repeatable: bool,
+    pub weight: i32,
+    pub semantic_tags: Vec<String>,
+    pub grammar_compatibilities: Vec<String>,
+    pub grammar_requirements: Vec<String>,
+}
+
+impl FromStr for GrammarFile {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = GrammarFileParser::default();
+
+        for (index, line) in s.lines().enumerate() {
+            parser.parse_line(index + 1, line.trim())?;
+        }
+
+        parser.finish()
+    }
+}
+
+/// Entries that reference others keep their line, references are checked once the whole file is read.
+#[derive(Default)]
+struct GrammarFileParser {
+    grammar: GrammarFile,
+    declared: HashSet<(&'static str, String)>,
+    category_lines: Vec<usize>,
+    word_lines: Vec<usize>,
+    /// In the order of the productions of every non-terminal symbol, one after the other.
+    production_lines: Vec<usize>,
+}
+
+impl GrammarFileParser {
+    fn parse_line(&mut self, line: usize, text: &str) -> AppResult<()> {
+        if text.is_empty() || text.starts_with('#') {
+            return Ok(());
+        }
+
+        if let Some(branch) = text.strip_prefix('|') {
+            let non_terminal_symbol =
+                self.grammar
+                    .non_terminal_symbols
+                    .last_mut()
+                    .ok_or_else(|| {
+                        AppError::for_grammar_file_line(
+                            line,
+                            "production branch outside of a non-terminal symbol".to_owned(),
+                        )
+                    })?;
+            non_terminal_symbol
+                .productions
+                .push(parse_branch(line, branch.trim())?);
+            self.production_lines.push(line);
+            return Ok(());
+        }
+
+        if let Some(captures) = NTS_DEFINITION.captures(text) {
+            let name = captures["name"].to_owned();
+            self.declare_name(
+                line,
+                "non-terminal symbol",
+                &name,
+                MAX_NON_TERMINAL_SYMBOL_LENGTH,
+            )?;
+            let branch = captures["branch"].trim();
+            self.grammar
+                .non_terminal_symbols
+                .push(NonTerminalSymbolEntry {
+                    name,
+                    productions: if branch.is_empty() {
+                        vec![]
+                    } else {
+                        self.production_lines.push(line);
+                        vec![parse_branch(line, branch)?]
+                    },
+                });
+            return Ok(());
+        }
+
+        if let Some(captures) = CATEGORY.captures(text) {
+            let name = match captures.name("quoted") {
+                Some(quoted) => unescape(quoted.as_str()),
+                None => captures["name"].to_owned(),
+            };
+            let category = parse_category(line, name, &captures["attributes"])?;
+            in_line(
+                line,
+                validate_length("category", &category.name, MAX_CATEGORY_NAME_LENGTH),
+            )?;
+            self.declare(line, "category", &category.name)?;
+            self.grammar.categories.push(category);
+            self.category_lines.push(line);
+            return Ok(());
+        }
+
+        if let Some(captures) = WORD.captures(text) {
+            let word = parse_word(
+                line,
+                &unescape(&captures["content"]),
+                &captures["attributes"],
+            )?;
+            in_line(
+                line,
+                validate_length("word content", &word.content, MAX_WORD_LENGTH),
+            )?;
+            self.declare(line, "word", &word.content)?;
+            self.grammar.words.push(word);
+            self.word_lines.push(line);
+            return Ok(());
+        }
+
+        match text.split_whitespace().collect_vec().as_slice() {
+            ["semantic", name, flags @ ..] => {
+                let sticky = match flags {
+                    [] => true,
+                    ["non-sticky"] => false,
+                    _ => {
+                        return Err(AppError::for_grammar_file_line(
+                            line,
+                            format!("unknown semantic tag flags {:?}", flags),
+                        ))
+                    }
+                };
+                self.declare_name(line, "semantic tag", name, MAX_TAG_NAME_LENGTH)?;
+                self.grammar.semantic_tags.push(SemanticTagEntry {
+                    name: name.to_string(),
+                    sticky,
+                });
+            }
+            ["grammar", name] => {
+                self.declare_name(line, "grammar tag", name, MAX_TAG_NAME_LENGTH)?;
+                self.grammar.grammar_tags.push(name.to_string());
+            }
+            _ => {
+                return Err(AppError::for_grammar_file_line(
+                    line,
+                    format!("cannot recognize '{}'", text),
+                ))
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Tags and non-terminal symbols are referenced by name from productions, so names must be plain words
+    /// that fit their column, like when editing them.
+    fn declare_name(
+        &mut self,
+        line: usize,
+        kind: &'static str,
+        name: &str,
+        max_length: usize,
+    ) -> AppResult<()> {
+        in_line(line, validate_name(kind, name, max_length))?;
+
+        self.declare(line, kind, name)
+    }
+
+    fn declare(&mut self, line: usize, kind: &'static str, name: &str) -> AppResult<()> {
+        if !self.declared.insert((kind, name.to_owned())) {
+            return Err(AppError::for_grammar_file_line(
+                line,
+                format!("{} '{}' is declared twice", kind, name),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn check_reference(&self, line: usize, kind: &'static str, name: &str) -> AppResult<()> {
+        if self.declared.contains(&(kind, name.to_owned())) {
+            Ok(())
+        } else {
+            Err(AppError::for_grammar_file_line(
+                line,
+                format!("{} '{}' is not declared", kind, name),
+            ))
+        }
+    }
+
+    /// Productions are checked like when editing them, so that importing a file never stores one that
+    /// fails at generation time.
+    fn finish(self) -> AppResult<GrammarFile> {
+        let non_terminal_symbols = self
+            .grammar
+            .non_terminal_symbols
+            .iter()
+            .map(|non_terminal_symbol| non_terminal_symbol.name.as_str())
+            .collect::<HashSet<_>>();
+        let productions = self
+            .grammar
+            .non_terminal_symbols
+            .iter()
+            .flat_map(|non_terminal_symbol| non_terminal_symbol.productions.iter());
+        for (production, &line) in productions.zip(&self.production_lines) {
+            in_line(
+                line,
+                check_production(&production.production, &non_terminal_symbols),
+            )?;
+        }
+
+        for (category, &line) in self.grammar.categories.iter().zip(&self.category_lines) {
+            if let Some(start_symbol) = category.start_symbol.as_deref() {
+                self.check_reference(line, "non-terminal symbol", start_symbol)?;
+            }
+            for tag in category.semantic_tags.iter() {
+                self.check_reference(line, "semantic tag", tag)?;
+            }
+        }
+
+        for (word, &line) in self.grammar.words.iter().zip(&self.word_lines) {
+            for tag in word.semantic_tags.iter() {
+                self.check_reference(line, "semantic tag", tag)?;
+            }
+            for tag in word
+                .grammar_compatibilities
+                .iter()
+                .chain(word.grammar_requirements.iter())
+            {
+                self.check_reference(line, "grammar tag", tag)?;
+            }
+        }
+
+        Ok(self.grammar)
+    }
+}
+
+fn parse_branch(line: usize, branch: &str) -> AppResult<ProductionEntry> {
+    let captures = BRANCH.captures(branch).ok_or_else(|| {
+        AppError::for_grammar_file_line(line, format!("cannot recognize branch '{}'", branch))
+    })?;
+
+    let production = captures["production"].trim_end();
+    in_line(
+        line,
+        validate_length("production", production, MAX_PRODUCTION_LENGTH),
+    )?;
+
+    Ok(ProductionEntry {
+        production: production.to_owned(),
+        weight: captures
+            .name("weight")
+            .map_or(Ok(1), |weight| parse_weight(line, weight.as_str()))?,
+    })
+}
+
+fn parse_category(line: usize, name: String, attributes: &str) -> AppResult<CategoryEntry> {
+    let mut category = CategoryEntry {
+        name,
+        start_symbol: None,
+        semantic_tags: vec![],
+    };
+
+    for (key, value) in parse_attributes(line, attributes)? {
+        match (key.as_str(), value) {
+            ("start", Some(start_symbol)) => {
+                category.start_symbol = Some(start_symbol.trim().to_owned())
+            }
+            ("semantic", Some(tags)) => category.semantic_tags = parse_list(&tags),
+            (key, _) => return Err(unknown_attribute(line, key)),
+        }
+    }
+
+    Ok(category)
+}
+
+fn parse_word(line: usize, content: &str, attributes: &str) -> AppResult<WordEntry> {
+    let mut word = WordEntry {
+        content: content.to_owned(),
+        non_repeatable: true,
+        weight: 1,
+        semantic_tags: vec![],
+        grammar_compatibilities: vec![],
+        grammar_requirements: vec![],
+    };
+
+    for (key, value) in parse_attributes(line, attributes)? {
+        match (key.as_str(), value) {
+            ("semantic", Some(tags)) => word.semantic_tags = parse_list(&tags),
+            ("compatible", Some(tags)) => word.grammar_compatibilities = parse_list(&tags),
+            ("requires", Some(tags)) => word.grammar_requirements = parse_list(&tags),
+            ("weight", Some(weight)) => word.weight = parse_weight(line, weight.trim())?,
+            ("repeatable", None) => word.non_repeatable = false,
+            (key, _) => return Err(unknown_attribute(line, key)),
+        }
+    }
+
+    Ok(word)
+}
+
+/// Splits `key(value) flag key(value)` into pairs, flags have no value.
+fn parse_attributes(line: usize, attributes: &str) -> AppResult<Vec<(String, Option<String>)>> {
+    let mut parsed = Vec::new();
+    let mut rest = attributes.trim_start();
+
+    while !rest.is_empty() {
+        let captures = ATTRIBUTE.captures(rest).ok_or_else(|| {
+            AppError::for_grammar_file_line(line, format!("cannot recognize attribute '{}'", rest))
+        })?;
+        parsed.push((
+            captures["key"].to_owned(),
+            captures
+                .name("value")
+                .map(|value| value.as_str().to_owned()),
+        ));
+        rest = rest[captures[0].len()..].trim_start();
+    }
+
+    Ok(parsed)
+}
+
+fn parse_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+fn parse_weight(line: usize, weight: &str) -> AppResult<i32> {
+    match i32::from_str(weight) {
+        Ok(weight) if weight > 0 => Ok(weight),
+        _ => Err(AppError::for_grammar_file_line(
+            line,
+            format!("'{}' is not a positive weight", weight),
+        )),
+    }
+}
+
+/// Checks shared with editing report their problem at the line of the file.
+fn in_line(line: usize, result: AppResult<()>) -> AppResult<()> {
+    result.map_err(|error| AppError::for_grammar_file_line(line, error.to_string()))
+}
+
+fn unknown_attribute(line: usize, key: &str) -> AppError {
+    AppError::for_grammar_file_line(line, format!("unknown or malformed attribute '{}'", key))
+}
+
+fn escape(content: &str) -> String {
+    content.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn unescape(content: &str) -> String {
+    let mut unescaped = String::with_capacity(content.len());
+    let mut chars = content.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next()),
+            c => unescaped.push(c),
+        }
+    }
+
+    unescaped
+}
+
+impl Display for GrammarFile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut sections: Vec<Vec<String>> = vec![
+            self.semantic_tags
+                .iter()
+                .map(|tag| {
+                    if tag.sticky {
+                        format!("semantic {}", tag.name)
+                    } else {
+                        format!("semantic {} non-sticky", tag.name)
+                    }
+                })
+                .collect(),
+            self.grammar_tags
+                .iter()
+                .map(|tag| format!("grammar {}", tag))
+                .collect(),
+            self.categories.iter().map(format_category).collect(),
+        ];
+        sections.extend(
+            self.non_terminal_symbols
+                .iter()
+                .map(|non_terminal_symbol| vec![format_non_terminal_symbol(non_terminal_symbol)]),
+        );
+        sections.push(self.words.iter().map(format_word).collect());
+
+        let text = sections
+            .into_iter()
+            .filter(|section| !section.is_empty())
+            .map(|section| section.join("\n"))
+            .join("\n\n");
+
+        writeln!(f, "{}", text)
+    }
+}
+
+fn format_category(category: &CategoryEntry) -> String {
+    let name = &category.name;
+    let mut line = if name.is_empty() || name.starts_with('"') || name.contains(char::is_whitespace)
+    {
+        format!("category \"{}\"", escape(name))
+    } else {
+        format!("category {}", name)
+    };
+    if let Some(start_symbol) = category.start_symbol.as_deref() {
+        line.push_str(&format!(" start({})", start_symbol));
+    }
+    if !category.semantic_tags.is_empty() {
+        line.push_str(&format!(" semantic({})", category.semantic_tags.join(", ")));
+    }
+
+    line
+}
+
+fn format_branch(production: &ProductionEntry) -> String {
+    if production.weight == 1 {
+        production.production.clone()
+    } else {
+        format!("weight({}) {}", production.weight, production.production)
+    }
+}
+
+fn format_non_terminal_symbol(non_terminal_symbol: &NonTerminalSymbolEntry) -> String {
+    let mut branches = non_terminal_symbol.productions.iter().map(format_branch);
+
+    match branches.next() {
+        Some(first) => std::iter::once(format!("{} -> {}", non_terminal_symbol.name, first))
+            .chain(branches.map(|branch| format!("  | {}", branch)))
+            .join("\n"),
+        None => format!("{} ->", non_terminal_symbol.name),
+    }
+}
+
+fn format_word(word: &WordEntry) -> String {
+    let mut line = format!("word \"{}\"", escape(&word.content));
+    for (key, tags) in [
+        ("semantic", &word.semantic_tags),
+        ("compatible", &word.grammar_compatibilities),
+        ("requires", &word.grammar_requirements),
+    ] {
+        if !tags.is_empty() {
+            line.push_str(&format!(" {}({})", key, tags.join(", ")));
+        }
+    }
+    if word.weight != 1 {
+        line.push_str(&format!(" weight({})", word.weight));
+    }
+    if !word.non_repeatable {
+        line.push_str(" repeatable");
+    }
+
+    line
+}
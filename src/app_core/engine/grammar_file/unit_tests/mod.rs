@@ -0,0 +1,162 @@
+use std::str::FromStr;
+
+use futures::executor::block_on;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::app_core::engine::grammar_file::memory::load_grammar;
+use crate::app_core::engine::grammar_file::{GrammarFile, ProductionEntry};
+use crate::app_core::engine::validation::validate_grammar;
+use crate::app_core::engine::{generate_phrase, resolve_category, GenerationLimits};
+use crate::app_core::errors::{AppError, DataError};
+
+const ANIMALS: &str = r#"
+# Tags come first, but references may point anywhere in the file
+semantic noun non-sticky
+semantic animal
+grammar singular
+grammar plural
+
+category barking start(Barking) semantic(animal)
+
+Start -> {0:N:F:N:F:Subject} <1:O(0):F:O(0):F:verb>
+  | weight(3) <0:N:F:N:F:noun>
+Subject -> <0:N:F:N:F:noun>
+Barking -> <0:N:F:C:F:verb>
+Empty ->
+
+word "cane" semantic(noun, animal) requires(singular)
+word "abbaia" semantic(verb, animal) compatible(singular) weight(5) repeatable
+word "il \"cane\"" semantic(noun)
+
+semantic verb non-sticky
+"#;
+
+fn assert_line_error(text: &str, expected_line: usize) {
+    match GrammarFile::from_str(text) {
+        Err(AppError::Data(DataError::GrammarFile(line, _))) => assert_eq!(line, expected_line),
+        other => panic!(
+            "Expected an error at line {}, got {:?}",
+            expected_line, other
+        ),
+    }
+}
+
+#[test]
+fn grammar_file_is_parsed() {
+    let grammar = GrammarFile::from_str(ANIMALS).unwrap();
+
+    assert_eq!(grammar.semantic_tags.len(), 3);
+    assert!(!grammar.semantic_tags[0].sticky);
+    assert!(grammar.semantic_tags[1].sticky);
+    assert_eq!(grammar.grammar_tags, ["singular", "plural"]);
+    assert_eq!(
+        grammar.categories[0].start_symbol.as_deref(),
+        Some("Barking")
+    );
+    assert_eq!(grammar.non_terminal_symbols.len(), 4);
+    assert_eq!(
+        grammar.non_terminal_symbols[0].productions[1],
+        ProductionEntry {
+            production: "<0:N:F:N:F:noun>".to_owned(),
+            weight: 3
+        }
+    );
+    assert!(grammar.non_terminal_symbols[3].productions.is_empty());
+    assert_eq!(grammar.words[1].grammar_compatibilities, ["singular"]);
+    assert_eq!(grammar.words[1].weight, 5);
+    assert!(!grammar.words[1].non_repeatable);
+    assert_eq!(grammar.words[2].content, "il \"cane\"");
+}
+
+#[test]
+fn serialized_grammar_file_parses_back_to_itself() {
+    let grammar = GrammarFile::from_str(ANIMALS).unwrap();
+    let serialized = grammar.to_string();
+
+    assert_eq!(GrammarFile::from_str(&serialized).unwrap(), grammar);
+    assert_eq!(
+        GrammarFile::from_str(&serialized).unwrap().to_string(),
+        serialized
+    );
+}
+
+#[test]
+fn undeclared_references_are_reported_with_their_line() {
+    assert_line_error("semantic noun\n\nword \"cane\" semantic(noun, animal)", 3);
+    assert_line_error("category barking start(Barking)", 1);
+}
+
+#[test]
+fn productions_are_checked_like_when_editing_them() {
+    // Dangling non-terminal symbol
+    assert_line_error(
+        "Start -> <0:N:F:N:F:noun>\n| {0:N:F:N:F:Subject} <1:O(0):F:O(0):F:verb>",
+        2,
+    );
+    // Placeholder id clash
+    assert_line_error("Start -> <0:N:F:N:F:noun> <0:N:F:N:F:verb>", 1);
+    // Dependency cycle
+    assert_line_error(
+        "Start -> <0:N:F:N:F:noun>\nOther -> <0:O(1):F:N:F:noun> <1:O(0):F:N:F:verb>",
+        2,
+    );
+    assert_line_error("Start -> <0:N:F:N:F:noun>\n| <1", 2);
+}
+
+#[test]
+fn duplicate_declarations_are_reported() {
+    assert_line_error("Start -> <0:N:F:N:F:noun>\nStart -> <0:N:F:N:F:verb>", 2);
+    assert_line_error("word \"cane\"\nword \"cane\" repeatable", 2);
+}
+
+#[test]
+fn malformed_lines_are_reported() {
+    assert_line_error("  | <0:N:F:N:F:noun>", 1);
+    assert_line_error("Start -> weight(0) <0:N:F:N:F:noun>", 1);
+    assert_line_error("word \"cane\" loud", 1);
+    assert_line_error("whatever", 1);
+}
+
+#[test]
+fn names_beyond_their_column_length_are_reported() {
+    assert_line_error(
+        "Start -> <0:N:F:N:F:noun>\nSubject_s -> <0:N:F:N:F:noun>",
+        2,
+    );
+    assert_line_error(&format!("semantic noun\nsemantic {}", "à".repeat(33)), 2);
+    // Limits count characters, not bytes
+    assert!(GrammarFile::from_str(&format!("Soggettò -> \nsemantic {}", "à".repeat(32))).is_ok());
+}
+
+#[test]
+fn category_names_with_any_character_round_trip() {
+    let text = "Start -> <0:N:F:N:F:noun>\ncategory \"mad dogs\" start(Start)\ncategory l'ultimo";
+    let grammar = GrammarFile::from_str(text).unwrap();
+
+    assert_eq!(grammar.categories[0].name, "mad dogs");
+    assert_eq!(grammar.categories[1].name, "l'ultimo");
+    assert_eq!(
+        GrammarFile::from_str(&grammar.to_string()).unwrap(),
+        grammar
+    );
+}
+
+#[test]
+fn loaded_grammar_file_generates_and_validates() {
+    let mut source = load_grammar(&GrammarFile::from_str(ANIMALS).unwrap());
+
+    let category = block_on(resolve_category(Some("barking"), &mut source)).unwrap();
+    let phrase = block_on(generate_phrase(
+        &category,
+        &GenerationLimits::default(),
+        &mut source,
+        &mut StdRng::seed_from_u64(1),
+    ))
+    .unwrap();
+    assert_eq!(phrase.text, "abbaia");
+
+    let report = block_on(validate_grammar(&mut source)).unwrap();
+    assert!(report.problems.is_empty());
+    assert_eq!(report.unreachable_symbols(), ["Empty"]);
+}
@@ -1 +1,708 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use async_recursion::async_recursion;
+use itertools::Itertools;
+use rand::rngs::StdRng;
+
+use self::sources::{
+    Category, GrammarSource, ProductionChoice, ProductionConstraints, SelectedWord, WordConstraints,
+};
+use self::types::derivation::Derivation;
+use self::types::parsing::TokenReference;
+use self::types::{PlaceholderReference, ProductionBranch};
+use crate::app_core::errors::{AppError, GenerationError};
+use crate::app_core::AppResult;
+use crate::settings::GenerationSection;
+
+pub mod analysis;
+pub mod grammar_file;
+pub mod sources;
 pub mod types;
+pub mod validation;
+
+#[cfg(test)]
+#[path = "./unit_tests/mod.rs"]
+mod tests;
+
+#[derive(Clone, Debug)]
+pub struct GenerationLimits {
+    pub max_depth: u16,
+    pub max_length: i32,
+    /// How many failed production branches can be discarded and retried during a single generation.
+    pub max_backtracks: u32,
+}
+
+impl Default for GenerationLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: 100,
+            max_length: 500,
+            max_backtracks: 32,
+        }
+    }
+}
+
+impl From<&GenerationSection> for GenerationLimits {
+    fn from(settings: &GenerationSection) -> Self {
+        Self {
+            max_depth: settings.max_depth,
+            max_length: settings.max_length,
+            max_backtracks: settings.max_backtracks,
+        }
+    }
+}
+
+pub struct GeneratedPhrase {
+    pub text: String,
+    pub backtracks: u32,
+    pub derivation: Derivation,
+}
+
+type TrivialGenerationSubStep = GenerationSubStep<i32, i32, i32>;
+type TrivialGenerationState = dyn GenerationState<i32, i32, i32, i32>;
+
+#[derive(Clone)]
+pub struct GenerationSubStep<
+    Placeholder: Sized + Hash + Send + Clone + Eq,
+    Semantics: Sized + Hash + Send + Clone + Eq,
+    Grammar: Sized + Hash + Send + Clone + Eq,
+> {
+    grammar_context: HashSet<Grammar>,
+    semantic_context: HashSet<Semantics>,
+
+    grammar_lookup: HashMap<Placeholder, HashSet<Grammar>>,
+    semantics_lookup: HashMap<Placeholder, HashSet<Semantics>>,
+}
+
+impl<
+        Placeholder: Sized + Hash + Send + Clone + Eq,
+        Semantics: Sized + Hash + Send + Clone + Eq,
+        Grammar: Sized + Hash + Send + Clone + Eq,
+    > Default for GenerationSubStep<Placeholder, Semantics, Grammar>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<
+        Placeholder: Sized + Hash + Send + Clone + Eq,
+        Semantics: Sized + Hash + Send + Clone + Eq,
+        Grammar: Sized + Hash + Send + Clone + Eq,
+    > GenerationSubStep<Placeholder, Semantics, Grammar>
+{
+    pub fn new() -> Self {
+        Self {
+            grammar_context: HashSet::new(),
+            semantic_context: HashSet::new(),
+            grammar_lookup: HashMap::new(),
+            semantics_lookup: HashMap::new(),
+        }
+    }
+
+    pub fn propagate_grammar_tags(&mut self, tags: Vec<Grammar>) {
+        for tag in tags.into_iter() {
+            self.grammar_context.insert(tag);
+        }
+    }
+
+    pub fn propagate_semantic_tags(&mut self, tags: Vec<Semantics>) {
+        for tag in tags.into_iter() {
+            self.semantic_context.insert(tag);
+        }
+    }
+
+    pub fn register_grammar_tags(&mut self, placeholder: Placeholder, tags: Vec<Grammar>) -> bool {
+        self.grammar_lookup
+            .insert(placeholder, tags.into_iter().collect())
+            == None
+    }
+
+    pub fn register_semantic_tags(
+        &mut self,
+        placeholder: Placeholder,
+        tags: Vec<Semantics>,
+    ) -> bool {
+        self.semantics_lookup
+            .insert(placeholder, tags.into_iter().collect())
+            == None
+    }
+
+    pub fn get_grammar_of(&self, placeholder: &Placeholder) -> Option<Vec<&Grammar>> {
+        self.grammar_lookup
+            .get(placeholder)
+            .map(|grammar| grammar.iter().collect_vec())
+    }
+
+    pub fn get_semantics_of(&self, placeholder: &Placeholder) -> Option<Vec<&Semantics>> {
+        self.semantics_lookup
+            .get(placeholder)
+            .map(|grammar| grammar.iter().collect_vec())
+    }
+
+    pub fn deconstruct_context(&self) -> (Vec<&Semantics>, Vec<&Grammar>) {
+        (
+            self.semantic_context.iter().collect_vec(),
+            self.grammar_context.iter().collect_vec(),
+        )
+    }
+}
+
+trait GenerationState<
+    Word: Sized + Hash + Send + Clone + Eq,
+    Placeholder: Sized + Hash + Send + Clone + Eq,
+    Semantics: Sized + Hash + Send + Clone + Eq,
+    Grammar: Sized + Hash + Send + Clone + Eq,
+>: Send
+{
+    fn begin_generation_sub_step(&mut self);
+    fn end_generation_sub_step(
+        &mut self,
+    ) -> Option<GenerationSubStep<Placeholder, Semantics, Grammar>>;
+
+    fn current_depth(&self) -> u16;
+    fn is_too_deep(&self) -> bool;
+
+    fn propagate_semantics(&mut self, semantics: Vec<Semantics>);
+    fn propagate_grammar(&mut self, grammar: Vec<Grammar>);
+    fn current_context(&self) -> (Vec<&Semantics>, Vec<&Grammar>);
+
+    fn register_semantics(&mut self, placeholder: Placeholder, semantics: Vec<Semantics>) -> bool;
+    fn register_grammar(&mut self, placeholder: Placeholder, grammar: Vec<Grammar>) -> bool;
+    fn extract_placeholder_semantics(&self, placeholder: &Placeholder) -> Option<Vec<&Semantics>>;
+    fn extract_placeholder_grammar(&self, placeholder: &Placeholder) -> Option<Vec<&Grammar>>;
+
+    fn alter_length(&mut self, amount: i32);
+    fn current_length(&self) -> i32;
+    fn is_too_long(&self) -> bool;
+
+    fn try_backtrack(&mut self) -> bool;
+    fn backtracks(&self) -> u32;
+
+    fn register_word(&mut self, word: Word);
+    fn unregister_word(&mut self, word: &Word);
+    fn used_words(&self) -> Vec<&Word>;
+    fn has_used_word(&self, word: &Word) -> bool;
+}
+
+pub struct InMemoryGenerationState {
+    max_depth: u16,
+
+    length: i32,
+    max_length: i32,
+
+    backtracks: u32,
+    max_backtracks: u32,
+
+    used_words: HashSet<i32>,
+    sub_steps: Vec<TrivialGenerationSubStep>,
+
+    current_sub_step: TrivialGenerationSubStep,
+}
+
+impl InMemoryGenerationState {
+    #[allow(unused)]
+    pub fn new(max_depth: u16, max_length: i32, max_backtracks: u32) -> Self {
+        Self {
+            max_depth,
+            length: 0i32,
+            max_length,
+            backtracks: 0u32,
+            max_backtracks,
+            used_words: HashSet::new(),
+            sub_steps: Vec::new(),
+            current_sub_step: GenerationSubStep::new(),
+        }
+    }
+}
+
+impl GenerationState<i32, i32, i32, i32> for InMemoryGenerationState {
+    fn begin_generation_sub_step(&mut self) {
+        self.sub_steps.push(self.current_sub_step.clone());
+        self.current_sub_step = GenerationSubStep::new()
+    }
+
+    fn end_generation_sub_step(&mut self) -> Option<TrivialGenerationSubStep> {
+        if let Some(sub_step) = self.sub_steps.pop() {
+            let current_sub_step = self.current_sub_step.clone();
+            self.current_sub_step = sub_step;
+            return Some(current_sub_step);
+        }
+
+        None
+    }
+
+    fn current_depth(&self) -> u16 {
+        self.sub_steps.len() as u16
+    }
+
+    fn is_too_deep(&self) -> bool {
+        self.current_depth() > self.max_depth
+    }
+
+    fn propagate_semantics(&mut self, semantics: Vec<i32>) {
+        self.current_sub_step.propagate_semantic_tags(semantics)
+    }
+
+    fn propagate_grammar(&mut self, grammar: Vec<i32>) {
+        self.current_sub_step.propagate_grammar_tags(grammar)
+    }
+
+    fn current_context(&self) -> (Vec<&i32>, Vec<&i32>) {
+        self.current_sub_step.deconstruct_context()
+    }
+
+    fn register_semantics(&mut self, placeholder: i32, semantics: Vec<i32>) -> bool {
+        self.current_sub_step
+            .register_semantic_tags(placeholder, semantics)
+    }
+
+    fn register_grammar(&mut self, placeholder: i32, grammar: Vec<i32>) -> bool {
+        self.current_sub_step
+            .register_grammar_tags(placeholder, grammar)
+    }
+
+    fn extract_placeholder_semantics(&self, placeholder: &i32) -> Option<Vec<&i32>> {
+        self.current_sub_step.get_semantics_of(placeholder)
+    }
+
+    fn extract_placeholder_grammar(&self, placeholder: &i32) -> Option<Vec<&i32>> {
+        self.current_sub_step.get_grammar_of(placeholder)
+    }
+
+    fn alter_length(&mut self, amount: i32) {
+        self.length += amount
+    }
+
+    fn current_length(&self) -> i32 {
+        self.length
+    }
+
+    fn is_too_long(&self) -> bool {
+        self.length > self.max_length
+    }
+
+    fn try_backtrack(&mut self) -> bool {
+        if self.backtracks < self.max_backtracks {
+            self.backtracks += 1;
+            return true;
+        }
+
+        false
+    }
+
+    fn backtracks(&self) -> u32 {
+        self.backtracks
+    }
+
+    fn register_word(&mut self, word: i32) {
+        self.used_words.insert(word);
+    }
+
+    fn unregister_word(&mut self, word: &i32) {
+        self.used_words.remove(word);
+    }
+
+    fn used_words(&self) -> Vec<&i32> {
+        self.used_words.iter().collect_vec()
+    }
+
+    fn has_used_word(&self, word: &i32) -> bool {
+        self.used_words.contains(word)
+    }
+}
+
+/// No name means no category at all: generation starts from the default start symbol.
+pub async fn resolve_category(
+    name: Option<&str>,
+    source: &mut dyn GrammarSource,
+) -> AppResult<Category> {
+    match name {
+        Some(name) => source.retrieve_category(name).await,
+        None => Ok(Category::default()),
+    }
+}
+
+/// Tags are looked up one by one only to tell which ones are missing, generating without them would
+/// ignore the semantics of the category.
+async fn unknown_category_tags(
+    category: &Category,
+    tag_names: &[&str],
+    source: &mut dyn GrammarSource,
+) -> AppError {
+    let mut unknown = vec![];
+    for &name in tag_names {
+        match source.retrieve_tag_ids(&[name]).await {
+            Ok(ids) if ids.is_empty() => unknown.push(name.to_owned()),
+            Ok(_) => {}
+            Err(error) => return error,
+        }
+    }
+
+    AppError::for_generation_unknown_category_tags(category.name.clone(), unknown)
+}
+
+pub async fn generate_phrase(
+    category: &Category,
+    limits: &GenerationLimits,
+    source: &mut dyn GrammarSource,
+    rng: &mut StdRng,
+) -> AppResult<GeneratedPhrase> {
+    let mut state =
+        InMemoryGenerationState::new(limits.max_depth, limits.max_length, limits.max_backtracks);
+
+    if !category.semantic_tags.is_empty() {
+        let tag_names = category
+            .semantic_tags
+            .iter()
+            .map(String::as_str)
+            .unique()
+            .collect_vec();
+        let tags = source.retrieve_tag_ids(&tag_names).await?;
+        if tags.len() < tag_names.len() {
+            return Err(unknown_category_tags(category, &tag_names, source).await);
+        }
+        tracing::info!("Category {} seeds semantics {:?}", category.name, tags);
+        state.propagate_semantics(tags);
+    }
+
+    let derivation = generate_from_non_terminal_symbol(
+        &TokenReference::new_contextual_reference(category.start_symbol.clone()),
+        &mut state,
+        source,
+        rng,
+    )
+    .await?;
+    let text = derivation
+        .text
+        .replace("-", "")
+        .replace("_", "")
+        .replace("  ", " ")
+        .trim()
+        .to_owned();
+
+    Ok(GeneratedPhrase {
+        text,
+        backtracks: state.backtracks(),
+        derivation,
+    })
+}
+
+#[async_recursion]
+async fn generate_from_placeholder(
+    placeholder: &PlaceholderReference,
+    state: &mut TrivialGenerationState,
+    source: &mut dyn GrammarSource,
+    rng: &mut StdRng,
+) -> AppResult<Derivation> {
+    match placeholder {
+        PlaceholderReference::NonTerminalSymbol(nts) => {
+            generate_from_non_terminal_symbol(nts, state, source, rng).await
+        }
+        PlaceholderReference::WordSelector(word) => {
+            generate_from_word_selector(word, state, source, rng).await
+        }
+    }
+}
+
+#[async_recursion]
+async fn generate_from_non_terminal_symbol(
+    token: &TokenReference,
+    state: &mut TrivialGenerationState,
+    source: &mut dyn GrammarSource,
+    rng: &mut StdRng,
+) -> AppResult<Derivation> {
+    if state.is_too_deep() {
+        return Err(GenerationError::ExcessiveDepth(state.current_depth()).into());
+    }
+
+    let mut discarded_branches: Vec<String> = Vec::new();
+    let mut last_failure: Option<AppError> = None;
+
+    loop {
+        let branch = match pick_production(token, state, source, rng, &discarded_branches).await {
+            Ok(branch) => branch,
+            Err(error) => return Err(last_failure.unwrap_or(error)),
+        };
+        let used_words = state.used_words().into_iter().copied().collect_vec();
+        let length = state.current_length();
+
+        match generate_from_production_branch(token, &branch, state, source, rng).await {
+            Err(error) if is_recoverable(&error) && state.try_backtrack() => {
+                tracing::info!(
+                    "Backtracking from '{}' of NTS {}: {}",
+                    branch.text(),
+                    token.reference(),
+                    error
+                );
+                let words_to_release = state
+                    .used_words()
+                    .into_iter()
+                    .filter(|word| !used_words.contains(word))
+                    .copied()
+                    .collect_vec();
+                for word in words_to_release.iter() {
+                    state.unregister_word(word);
+                }
+                state.alter_length(length - state.current_length());
+
+                discarded_branches.push(branch.text().to_owned());
+                last_failure = Some(error);
+            }
+            result => {
+                return result.map(|derivation| Derivation {
+                    discarded_productions: discarded_branches,
+                    ..derivation
+                })
+            }
+        }
+    }
+}
+
+fn is_recoverable(error: &AppError) -> bool {
+    matches!(
+        error,
+        AppError::Generation(
+            GenerationError::NoWordsFound
+                | GenerationError::NoProductionBranchesFound(_)
+                | GenerationError::ExcessiveDepth(_)
+        )
+    )
+}
+
+async fn generate_from_production_branch(
+    token: &TokenReference,
+    branch: &ProductionBranch,
+    state: &mut TrivialGenerationState,
+    source: &mut dyn GrammarSource,
+    rng: &mut StdRng,
+) -> AppResult<Derivation> {
+    let (semantics, grammar) = compute_semantic_and_grammar_dependencies(token, state)?;
+
+    tracing::info!("Dependency on grammar: {:?}", grammar);
+    tracing::info!("Dependency on semantics: {:?}", semantics);
+
+    state.begin_generation_sub_step();
+    state.propagate_grammar(grammar.clone());
+    state.propagate_semantics(semantics.clone());
+    let mut generation_lookup = match generate_placeholders(branch, state, source, rng).await {
+        Ok(lookup) => lookup,
+        Err(error) => {
+            // The failed branch must not leak its own sub-step into the enclosing one
+            state.end_generation_sub_step();
+            return Err(error);
+        }
+    };
+    let (released_semantics, released_grammar) = if let Some((semantics, grammar)) = state
+        .end_generation_sub_step()
+        .as_ref()
+        .map(GenerationSubStep::deconstruct_context)
+    {
+        tracing::info!(
+            "Token {} has released grammar {:?} and semantics {:?}",
+            token.id(),
+            grammar,
+            semantics,
+        );
+        (
+            semantics.into_iter().copied().sorted().collect_vec(),
+            grammar.into_iter().copied().sorted().collect_vec(),
+        )
+    } else {
+        return Err(AppError::for_generation_non_existent_sub_step());
+    };
+    state.register_grammar(token.id(), released_grammar.clone());
+    state.register_semantics(token.id(), released_semantics.clone());
+
+    let children = branch
+        .placeholder_appearance_order_in_production()
+        .iter()
+        .filter_map(|i| generation_lookup.remove(i))
+        .collect_vec();
+    let result = children.iter().map(|child| child.text.as_str()).join(" ");
+
+    state.alter_length(result.len() as i32);
+
+    Ok(Derivation {
+        production: Some(branch.text().to_owned()),
+        inherited_semantics: semantics,
+        inherited_grammar: grammar,
+        registered_semantics: released_semantics,
+        registered_grammar: released_grammar,
+        children,
+        ..Derivation::new(token.id(), token.reference().to_owned(), result)
+    })
+}
+
+async fn generate_placeholders(
+    branch: &ProductionBranch,
+    state: &mut TrivialGenerationState,
+    source: &mut dyn GrammarSource,
+    rng: &mut StdRng,
+) -> AppResult<HashMap<i32, Derivation>> {
+    let mut generation_lookup: HashMap<i32, Derivation> = HashMap::new();
+
+    for placeholder in branch.ordered_placeholder_references()? {
+        generation_lookup.insert(
+            placeholder.id(),
+            generate_from_placeholder(placeholder, state, source, rng).await?,
+        );
+    }
+
+    Ok(generation_lookup)
+}
+
+async fn pick_production(
+    token: &TokenReference,
+    state: &mut TrivialGenerationState,
+    source: &mut dyn GrammarSource,
+    rng: &mut StdRng,
+    discarded_branches: &[String],
+) -> AppResult<ProductionBranch> {
+    let choice = if !state.is_too_long() {
+        ProductionChoice::Random
+    } else {
+        ProductionChoice::Shortest
+    };
+
+    source
+        .pick_production(
+            &ProductionConstraints {
+                non_terminal_symbol: token.reference(),
+                choice,
+                discarded_branches,
+            },
+            rng,
+        )
+        .await
+}
+
+#[async_recursion]
+async fn generate_from_word_selector(
+    token: &TokenReference,
+    state: &mut TrivialGenerationState,
+    source: &mut dyn GrammarSource,
+    rng: &mut StdRng,
+) -> AppResult<Derivation> {
+    let (semantic_tags, grammar_tags) = compute_semantic_and_grammar_dependencies(token, state)?;
+    let selected_word = pick_word(token, &semantic_tags, &grammar_tags, state, source, rng).await?;
+
+    tracing::info!("Found word: {}", selected_word.content);
+    tracing::info!("Semantics: {:?}", selected_word.semantic_output);
+    tracing::info!("Grammar: {:?}", selected_word.grammar_output);
+
+    state.register_semantics(token.id(), selected_word.semantic_output.clone());
+    state.register_grammar(token.id(), selected_word.grammar_output.clone());
+
+    let mut derivation = Derivation {
+        word: Some(selected_word.id),
+        inherited_semantics: semantic_tags,
+        inherited_grammar: grammar_tags,
+        registered_semantics: selected_word.semantic_output.clone(),
+        registered_grammar: selected_word.grammar_output.clone(),
+        ..Derivation::new(
+            token.id(),
+            token.reference().to_owned(),
+            selected_word.content.clone(),
+        )
+    };
+
+    if token.grammar_can_propagate() {
+        derivation.propagated_grammar = selected_word.grammar_output.clone();
+        state.propagate_grammar(selected_word.grammar_output);
+    }
+
+    if token.semantic_can_propagate() {
+        derivation.propagated_semantics = selected_word.semantic_output.clone();
+        state.propagate_semantics(selected_word.semantic_output);
+    }
+
+    if selected_word.non_repeatable {
+        state.register_word(selected_word.id)
+    }
+
+    Ok(derivation)
+}
+
+async fn pick_word(
+    token: &TokenReference,
+    semantic_tags: &[i32],
+    grammar_tags: &[i32],
+    state: &mut TrivialGenerationState,
+    source: &mut dyn GrammarSource,
+    rng: &mut StdRng,
+) -> AppResult<SelectedWord> {
+    let search_tag_names = token.reference().split(',').map(|s| s.trim()).collect_vec();
+    let search_tags = source.retrieve_tag_ids(&search_tag_names).await?;
+    let used_words = state.used_words().into_iter().copied().collect_vec();
+
+    tracing::info!("search_tags: {:?}", search_tags);
+    tracing::info!("semantic_tags: {:?}", semantic_tags);
+    tracing::info!("grammar_tags: {:?}", grammar_tags);
+    tracing::info!("used_words: {:?}", used_words);
+
+    source
+        .pick_word(
+            &WordConstraints {
+                search_tags: &search_tags,
+                semantic_tags,
+                grammar_tags,
+                used_words: &used_words,
+            },
+            rng,
+        )
+        .await
+}
+
+fn compute_semantic_and_grammar_dependencies(
+    token: &TokenReference,
+    state: &TrivialGenerationState,
+) -> AppResult<(Vec<i32>, Vec<i32>)> {
+    let (context_semantics, context_grammar) = state.current_context();
+
+    let semantic_tags = match (
+        token.semantic_dependency_on_other(),
+        token.semantic_depends_on_context(),
+    ) {
+        (None, false) => vec![],
+        (None, true) => context_semantics,
+        (Some(ref id), false) => state
+            .extract_placeholder_semantics(id)
+            .ok_or_else(|| GenerationError::NonRegisteredPlaceholder(*id))?,
+        (Some(ref id), true) => state
+            .extract_placeholder_semantics(id)
+            .ok_or_else(|| GenerationError::NonRegisteredPlaceholder(*id))?
+            .into_iter()
+            .chain(context_semantics)
+            .collect(),
+    };
+
+    let grammar_tags = match (
+        token.grammar_dependency_on_other(),
+        token.grammar_depends_on_context(),
+    ) {
+        (None, false) => vec![],
+        (None, true) => context_grammar,
+        (Some(ref id), false) => state
+            .extract_placeholder_grammar(id)
+            .ok_or_else(|| GenerationError::NonRegisteredPlaceholder(*id))?,
+        (Some(ref id), true) => state
+            .extract_placeholder_grammar(id)
+            .ok_or_else(|| GenerationError::NonRegisteredPlaceholder(*id))?
+            .into_iter()
+            .chain(context_grammar)
+            .collect(),
+    };
+
+    // Sorted, so that derivations of the same seed read the same
+    Ok((
+        semantic_tags
+            .into_iter()
+            .copied()
+            .sorted()
+            .dedup()
+            .collect(),
+        grammar_tags.into_iter().copied().sorted().dedup().collect(),
+    ))
+}
@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use itertools::Itertools;
+use rand::rngs::StdRng;
+
+use super::{
+    choose_weighted, Category, GrammarDefinition, GrammarSource, ProductionChoice,
+    ProductionConstraints, SelectedWord, StoredProduction, WordConstraints,
+};
+use crate::app_core::engine::types::ProductionBranch;
+use crate::app_core::errors::AppError;
+use crate::app_core::AppResult;
+
+#[derive(Clone, Debug)]
+pub struct InMemorySemanticTag {
+    pub id: i32,
+    pub name: String,
+    pub sticky: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct InMemoryWord {
+    pub id: i32,
+    pub content: String,
+    pub non_repeatable: bool,
+    pub weight: i32,
+    pub semantic_tags: Vec<i32>,
+    pub grammar_compatibilities: Vec<i32>,
+    pub grammar_requirements: Vec<i32>,
+}
+
+struct InMemoryProduction {
+    id: i32,
+    text: String,
+    weight: i32,
+}
+
+/// Grammar and lexicon fully held in memory, it mirrors the selection rules of the SQL queries.
+#[derive(Default)]
+pub struct InMemoryGrammarSource {
+    productions: HashMap<String, Vec<InMemoryProduction>>,
+    production_count: i32,
+    semantic_tags: Vec<InMemorySemanticTag>,
+    words: Vec<InMemoryWord>,
+    categories: Vec<Category>,
+}
+
+impl InMemoryGrammarSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Symbols get declared by their productions too, this is for the ones without any.
+    pub fn add_non_terminal_symbol(&mut self, non_terminal_symbol: &str) {
+        self.productions
+            .entry(non_terminal_symbol.to_owned())
+            .or_default();
+    }
+
+    pub fn add_production(&mut self, non_terminal_symbol: &str, production: &str) {
+        self.add_weighted_production(non_terminal_symbol, production, 1);
+    }
+
+    pub fn add_weighted_production(
+        &mut self,
+        non_terminal_symbol: &str,
+        production: &str,
+        weight: i32,
+    ) {
+        self.production_count += 1;
+        self.productions
+            .entry(non_terminal_symbol.to_owned())
+            .or_default()
+            .push(InMemoryProduction {
+                id: self.production_count,
+                text: production.to_owned(),
+                weight,
+            });
+    }
+
+    pub fn add_semantic_tag(&mut self, tag: InMemorySemanticTag) {
+        self.semantic_tags.push(tag);
+    }
+
+    pub fn add_word(&mut self, word: InMemoryWord) {
+        self.words.push(word);
+    }
+
+    pub fn add_category(&mut self, category: Category) {
+        self.categories.push(category);
+    }
+
+    fn is_sticky(&self, tag: i32) -> bool {
+        self.semantic_tags
+            .iter()
+            .any(|semantic| semantic.id == tag && semantic.sticky)
+    }
+
+    fn satisfies(word: &InMemoryWord, constraints: &WordConstraints<'_>) -> bool {
+        let has_all_search_tags = constraints
+            .search_tags
+            .iter()
+            .all(|tag| word.semantic_tags.contains(tag));
+        let intersects_semantic_context = constraints.semantic_tags.is_empty()
+            || constraints
+                .semantic_tags
+                .iter()
+                .any(|tag| word.semantic_tags.contains(tag));
+        let is_grammar_compatible = constraints
+            .grammar_tags
+            .iter()
+            .all(|tag| word.grammar_compatibilities.contains(tag));
+
+        has_all_search_tags
+            && intersects_semantic_context
+            && is_grammar_compatible
+            && !constraints.used_words.contains(&word.id)
+    }
+}
+
+#[async_trait]
+impl GrammarSource for InMemoryGrammarSource {
+    async fn pick_production(
+        &mut self,
+        constraints: &ProductionConstraints<'_>,
+        rng: &mut StdRng,
+    ) -> AppResult<ProductionBranch> {
+        let branches = self
+            .productions
+            .get(constraints.non_terminal_symbol)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .filter(|production| !constraints.discarded_branches.contains(&production.text))
+            .collect_vec();
+
+        let picked = match constraints.choice {
+            ProductionChoice::Random => {
+                choose_weighted(&branches, |production| production.weight, rng)?.copied()
+            }
+            ProductionChoice::Shortest => branches
+                .into_iter()
+                .min_by_key(|production| production.text.matches('{').count()),
+        };
+
+        picked
+            .ok_or_else(|| {
+                AppError::for_generation_no_production_branches_found(
+                    constraints.non_terminal_symbol.to_string(),
+                )
+            })
+            .and_then(|production| ProductionBranch::from_str(&production.text))
+    }
+
+    async fn pick_word(
+        &mut self,
+        constraints: &WordConstraints<'_>,
+        rng: &mut StdRng,
+    ) -> AppResult<SelectedWord> {
+        let candidates = self
+            .words
+            .iter()
+            .filter(|word| Self::satisfies(word, constraints))
+            .collect_vec();
+
+        let word = choose_weighted(&candidates, |word| word.weight, rng)?
+            .ok_or_else(AppError::for_generation_no_words_found)?;
+
+        Ok(SelectedWord {
+            id: word.id,
+            content: word.content.clone(),
+            non_repeatable: word.non_repeatable,
+            weight: word.weight,
+            semantic_output: word
+                .semantic_tags
+                .iter()
+                .copied()
+                .filter(|&tag| self.is_sticky(tag))
+                .collect(),
+            grammar_output: word.grammar_requirements.clone(),
+        })
+    }
+
+    async fn retrieve_tag_ids(&mut self, tags: &[&str]) -> AppResult<Vec<i32>> {
+        Ok(self
+            .semantic_tags
+            .iter()
+            .filter(|semantic| tags.contains(&semantic.name.as_str()))
+            .map(|semantic| semantic.id)
+            .collect())
+    }
+
+    async fn retrieve_categories(&mut self) -> AppResult<Vec<Category>> {
+        Ok(self
+            .categories
+            .iter()
+            .cloned()
+            .sorted_by(|a, b| a.name.cmp(&b.name))
+            .collect())
+    }
+
+    async fn retrieve_category(&mut self, name: &str) -> AppResult<Category> {
+        self.categories
+            .iter()
+            .find(|category| category.name == name)
+            .cloned()
+            .ok_or_else(|| AppError::for_generation_unknown_category(name.to_owned()))
+    }
+
+    async fn retrieve_definition(&mut self) -> AppResult<GrammarDefinition> {
+        Ok(GrammarDefinition {
+            non_terminal_symbols: self.productions.keys().cloned().sorted().collect(),
+            semantic_tags: self
+                .semantic_tags
+                .iter()
+                .map(|semantic| semantic.name.clone())
+                .sorted()
+                .collect(),
+            productions: self
+                .productions
+                .iter()
+                .flat_map(|(non_terminal_symbol, productions)| {
+                    productions.iter().map(move |production| StoredProduction {
+                        id: production.id,
+                        non_terminal_symbol: non_terminal_symbol.clone(),
+                        production: production.text.clone(),
+                    })
+                })
+                .sorted_by_key(|production| production.id)
+                .collect(),
+        })
+    }
+}
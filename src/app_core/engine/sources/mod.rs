@@ -0,0 +1,131 @@
+use async_trait::async_trait;
+use rand::distributions::{Distribution, WeightedError, WeightedIndex};
+use rand::rngs::StdRng;
+use sqlx::FromRow;
+
+use crate::app_core::engine::types::ProductionBranch;
+use crate::app_core::errors::AppError;
+use crate::app_core::AppResult;
+
+#[allow(unused)]
+pub use in_memory::*;
+pub use postgres::*;
+
+#[allow(unused)]
+mod in_memory;
+mod postgres;
+
+pub const DEFAULT_START_SYMBOL: &str = "Start";
+/// Code of the language phrases are generated in when none is asked for.
+pub const DEFAULT_LANGUAGE: &str = "ita";
+
+/// A kind of phrase: where generation starts from and which semantic tags seed its context.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Category {
+    pub name: String,
+    pub start_symbol: String,
+    pub semantic_tags: Vec<String>,
+}
+
+impl Default for Category {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            start_symbol: DEFAULT_START_SYMBOL.to_owned(),
+            semantic_tags: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ProductionChoice {
+    Random,
+    Shortest,
+}
+
+pub struct ProductionConstraints<'a> {
+    pub non_terminal_symbol: &'a str,
+    pub choice: ProductionChoice,
+    /// Branches whose text appears here have already failed and must not be picked again.
+    pub discarded_branches: &'a [String],
+}
+
+/// Everything a word has to satisfy in order to be picked.
+/// A suitable word carries all `search_tags`, intersects `semantic_tags` (when not empty),
+/// is compatible with all `grammar_tags` and is not one of the `used_words`.
+pub struct WordConstraints<'a> {
+    pub search_tags: &'a [i32],
+    pub semantic_tags: &'a [i32],
+    pub grammar_tags: &'a [i32],
+    pub used_words: &'a [i32],
+}
+
+#[derive(FromRow, Clone, Debug)]
+pub struct SelectedWord {
+    pub id: i32,
+    pub content: String,
+    pub non_repeatable: bool,
+    pub weight: i32,
+    pub semantic_output: Vec<i32>,
+    pub grammar_output: Vec<i32>,
+}
+
+#[derive(Clone, Debug)]
+pub struct StoredProduction {
+    pub id: i32,
+    pub non_terminal_symbol: String,
+    pub production: String,
+}
+
+/// The whole grammar as stored, unparsed: the input of static analyses.
+#[derive(Clone, Debug, Default)]
+pub struct GrammarDefinition {
+    pub non_terminal_symbols: Vec<String>,
+    pub semantic_tags: Vec<String>,
+    pub productions: Vec<StoredProduction>,
+}
+
+/// Weighted pick, `None` only when there are no candidates. Weights are summed as `u64` so that many
+/// heavy candidates cannot overflow; negative or all-zero weights are bad data, not a dead end.
+fn choose_weighted<'c, T>(
+    candidates: &'c [T],
+    weight: impl Fn(&T) -> i32,
+    rng: &mut StdRng,
+) -> AppResult<Option<&'c T>> {
+    if candidates.is_empty() {
+        return Ok(None);
+    }
+
+    let index = candidates
+        .iter()
+        .map(|candidate| u64::try_from(weight(candidate)).map_err(|_| WeightedError::InvalidWeight))
+        .collect::<Result<Vec<_>, _>>()
+        .and_then(WeightedIndex::new)
+        .map_err(AppError::for_invalid_weights)?;
+
+    Ok(candidates.get(index.sample(rng)))
+}
+
+/// Every random choice is drawn from the given `rng`, so that the same seed leads to the same picks.
+#[async_trait]
+pub trait GrammarSource: Send {
+    async fn pick_production(
+        &mut self,
+        constraints: &ProductionConstraints<'_>,
+        rng: &mut StdRng,
+    ) -> AppResult<ProductionBranch>;
+
+    async fn pick_word(
+        &mut self,
+        constraints: &WordConstraints<'_>,
+        rng: &mut StdRng,
+    ) -> AppResult<SelectedWord>;
+
+    async fn retrieve_tag_ids(&mut self, tags: &[&str]) -> AppResult<Vec<i32>>;
+
+    async fn retrieve_categories(&mut self) -> AppResult<Vec<Category>>;
+
+    async fn retrieve_category(&mut self, name: &str) -> AppResult<Category>;
+
+    async fn retrieve_definition(&mut self) -> AppResult<GrammarDefinition>;
+}
@@ -0,0 +1,301 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use itertools::Itertools;
+use rand::rngs::StdRng;
+use sqlx::{FromRow, Postgres, Transaction};
+
+use super::{
+    choose_weighted, Category, GrammarDefinition, GrammarSource, ProductionChoice,
+    ProductionConstraints, SelectedWord, StoredProduction, WordConstraints, DEFAULT_START_SYMBOL,
+};
+use crate::app_core::engine::types::ProductionBranch;
+use crate::app_core::errors::AppError;
+use crate::app_core::AppResult;
+
+/// The grammar of a single language, given by its code: symbols and words of other languages are never seen.
+/// Candidate productions are loaded once per non-terminal symbol and kept for the lifetime of the source:
+/// generating several phrases with the same source reads the grammar only once.
+pub struct PostgresGrammarSource<'a, 't> {
+    transaction: &'a mut Transaction<'t, Postgres>,
+    language: String,
+    productions: HashMap<(String, ProductionChoice), Vec<(String, i32)>>,
+}
+
+impl<'a, 't> PostgresGrammarSource<'a, 't> {
+    pub fn new(transaction: &'a mut Transaction<'t, Postgres>, language: &str) -> Self {
+        Self {
+            transaction,
+            language: language.to_owned(),
+            productions: HashMap::new(),
+        }
+    }
+
+    /// The transaction the grammar is read from, for callers storing what they generated.
+    pub fn transaction(&mut self) -> &mut Transaction<'t, Postgres> {
+        self.transaction
+    }
+
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// Fails when the language of the source is not stored, rather than finding no grammar at all.
+    pub async fn check_language(&mut self) -> AppResult<()> {
+        let exists = sqlx::query_scalar::<Postgres, bool>(
+            "SELECT EXISTS (SELECT 1 FROM language WHERE code = $1)",
+        )
+        .bind(&self.language)
+        .fetch_one(&mut *self.transaction)
+        .await
+        .map_err(AppError::for_generation_in_sql)?;
+
+        if exists {
+            Ok(())
+        } else {
+            Err(AppError::for_unknown_language(self.language.clone()))
+        }
+    }
+
+    async fn candidate_productions(
+        &mut self,
+        non_terminal_symbol: &str,
+        choice: ProductionChoice,
+    ) -> AppResult<&[(String, i32)]> {
+        let key = (non_terminal_symbol.to_owned(), choice);
+        if !self.productions.contains_key(&key) {
+            let template = match choice {
+                ProductionChoice::Random => {
+                    include_str!("../../../../draft_ideas/select_candidate_productions.sql")
+                }
+                ProductionChoice::Shortest => {
+                    include_str!("../../../../draft_ideas/select_shortest_production.sql")
+                }
+            };
+            let rows = sqlx::query_as::<Postgres, (String, i32)>(template)
+                .bind(non_terminal_symbol)
+                .bind(&self.language)
+                .fetch_all(&mut *self.transaction)
+                .await
+                .map_err(AppError::for_generation_in_sql)?;
+            self.productions.insert(key.clone(), rows);
+        }
+
+        Ok(self.productions[&key].as_slice())
+    }
+
+    async fn select_categories(&mut self, name: Option<&str>) -> AppResult<Vec<Category>> {
+        sqlx::query_as::<Postgres, CategoryRow>(include_str!(
+            "../../../../draft_ideas/select_categories.sql"
+        ))
+        .bind(name)
+        .fetch_all(&mut *self.transaction)
+        .await
+        .map(|rows| rows.into_iter().map(Category::from).collect())
+        .map_err(AppError::for_generation_in_sql)
+    }
+}
+
+#[derive(FromRow)]
+struct CategoryRow {
+    name: String,
+    start_symbol: Option<String>,
+    semantic_tags: Vec<String>,
+}
+
+impl From<CategoryRow> for Category {
+    fn from(row: CategoryRow) -> Self {
+        Self {
+            name: row.name,
+            start_symbol: row
+                .start_symbol
+                .unwrap_or_else(|| DEFAULT_START_SYMBOL.to_owned()),
+            semantic_tags: row.semantic_tags,
+        }
+    }
+}
+
+#[async_trait]
+impl<'a, 't> GrammarSource for PostgresGrammarSource<'a, 't> {
+    async fn pick_production(
+        &mut self,
+        constraints: &ProductionConstraints<'_>,
+        rng: &mut StdRng,
+    ) -> AppResult<ProductionBranch> {
+        let non_terminal_symbol = constraints.non_terminal_symbol;
+        let rows = self
+            .candidate_productions(non_terminal_symbol, constraints.choice)
+            .await?
+            .iter()
+            .filter(|(production, _)| !constraints.discarded_branches.contains(production))
+            .collect_vec();
+
+        let picked = match constraints.choice {
+            ProductionChoice::Random => choose_weighted(&rows, |(_, weight)| *weight, rng)?,
+            ProductionChoice::Shortest => rows.first(),
+        };
+
+        let (production, _) = picked.ok_or_else(|| {
+            AppError::for_generation_no_production_branches_found(non_terminal_symbol.to_string())
+        })?;
+
+        ProductionBranch::from_str(production)
+    }
+
+    async fn pick_word(
+        &mut self,
+        constraints: &WordConstraints<'_>,
+        rng: &mut StdRng,
+    ) -> AppResult<SelectedWord> {
+        let search_tags = constraints.search_tags;
+        let semantic_tags = constraints.semantic_tags;
+        let grammar_tags = constraints.grammar_tags;
+        let used_words = constraints
+            .used_words
+            .iter()
+            .chain(std::iter::once(&i32::MIN))
+            .collect_vec();
+
+        let template = include_str!("../../../../draft_ideas/select_candidate_words.sql")
+            .replace(
+                "<SELECTED_SEMANTIC_TAGS_PLACEHOLDERS>",
+                (0..search_tags.len())
+                    .map(|i| format!("${}", i + 1))
+                    .join(",")
+                    .as_str(),
+            )
+            .replace(
+                "<CONTEXTUAL_SEMANTIC_TAGS_PLACEHOLDERS>",
+                (0..semantic_tags.len())
+                    .map(|i| format!("${}", i + search_tags.len() + 1))
+                    .join(",")
+                    .as_str(),
+            )
+            .replace(
+                "<CONTEXTUAL_GRAMMAR_TAGS_PLACEHOLDERS>",
+                (0..grammar_tags.len())
+                    .map(|i| format!("${}", i + search_tags.len() + semantic_tags.len() + 1))
+                    .join(",")
+                    .as_str(),
+            )
+            .replace(
+                "<LANGUAGE>",
+                format!(
+                    "${}",
+                    search_tags.len()
+                        + semantic_tags.len()
+                        + grammar_tags.len()
+                        + used_words.len()
+                        + 1
+                )
+                .as_str(),
+            )
+            .replace(
+                "<USED_WORDS>",
+                (0..used_words.len())
+                    .map(|i| {
+                        format!(
+                            "${}",
+                            i + search_tags.len() + semantic_tags.len() + grammar_tags.len() + 1
+                        )
+                    })
+                    .join(",")
+                    .as_str(),
+            );
+
+        let mut query = sqlx::query_as::<sqlx::Postgres, SelectedWord>(&template);
+
+        for &tag in search_tags
+            .iter()
+            .chain(semantic_tags.iter())
+            .chain(grammar_tags.iter())
+            .chain(used_words)
+        {
+            tracing::info!("Binding parameter to value {}", tag);
+            query = query.bind(tag);
+        }
+        query = query.bind(&self.language);
+
+        let candidates = query
+            .fetch_all(&mut *self.transaction)
+            .await
+            .map_err(AppError::for_generation_in_sql)?;
+
+        choose_weighted(&candidates, |word| word.weight, rng)?
+            .cloned()
+            .ok_or_else(AppError::for_generation_no_words_found)
+    }
+
+    async fn retrieve_tag_ids(&mut self, tags: &[&str]) -> AppResult<Vec<i32>> {
+        let template = include_str!("../../../../draft_ideas/select_ids_of_tags.sql").replace(
+            "<SEMANTIC_TAGS_PLACEHOLDERS>",
+            (0..tags.len())
+                .map(|i| format!("${}", i + 1))
+                .join(",")
+                .as_str(),
+        );
+
+        let mut query = sqlx::query_as::<sqlx::Postgres, (i32,)>(&template);
+
+        for &tag in tags {
+            query = query.bind(tag);
+        }
+
+        let ids = query
+            .fetch_all(&mut *self.transaction)
+            .await
+            .map_err(AppError::for_generation_in_sql)?;
+
+        Ok(ids.into_iter().map(|(i,)| i).collect_vec())
+    }
+
+    async fn retrieve_categories(&mut self) -> AppResult<Vec<Category>> {
+        self.select_categories(None).await
+    }
+
+    async fn retrieve_category(&mut self, name: &str) -> AppResult<Category> {
+        self.select_categories(Some(name))
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| AppError::for_generation_unknown_category(name.to_owned()))
+    }
+
+    async fn retrieve_definition(&mut self) -> AppResult<GrammarDefinition> {
+        let non_terminal_symbols = sqlx::query_scalar::<Postgres, String>(
+            "SELECT nts.name FROM non_terminal_symbol nts INNER JOIN language l ON l.id = nts.language WHERE l.code = $1 ORDER BY nts.name",
+        )
+        .bind(&self.language)
+        .fetch_all(&mut *self.transaction)
+        .await
+        .map_err(AppError::for_generation_in_sql)?;
+
+        let semantic_tags =
+            sqlx::query_scalar::<Postgres, String>("SELECT name FROM semantic_tag ORDER BY name")
+                .fetch_all(&mut *self.transaction)
+                .await
+                .map_err(AppError::for_generation_in_sql)?;
+
+        let productions = sqlx::query_as::<Postgres, (i32, String, String)>(include_str!(
+            "../../../../draft_ideas/select_all_productions.sql"
+        ))
+        .bind(&self.language)
+        .fetch_all(&mut *self.transaction)
+        .await
+        .map_err(AppError::for_generation_in_sql)?
+        .into_iter()
+        .map(|(id, non_terminal_symbol, production)| StoredProduction {
+            id,
+            non_terminal_symbol,
+            production,
+        })
+        .collect();
+
+        Ok(GrammarDefinition {
+            non_terminal_symbols,
+            semantic_tags,
+            productions,
+        })
+    }
+}
@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+
+/// How a piece of phrase came out: one node per expanded placeholder, children in appearance order.
+/// Tags are reported through their ids.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Derivation {
+    pub placeholder: i32,
+    /// Name of the expanded non-terminal symbol, or the tags searched by a word selector.
+    pub reference: String,
+    /// Chosen production text, only for non-terminal symbols.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub production: Option<String>,
+    /// Production texts that have been tried and backtracked from before the chosen one.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub discarded_productions: Vec<String>,
+    /// Chosen word id, only for word selectors.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub word: Option<i32>,
+    pub text: String,
+    /// Tags the placeholder depended on, coming from its context or from other placeholders.
+    pub inherited_semantics: Vec<i32>,
+    pub inherited_grammar: Vec<i32>,
+    /// Tags other placeholders of the same production can depend on.
+    pub registered_semantics: Vec<i32>,
+    pub registered_grammar: Vec<i32>,
+    /// Tags pushed into the context of the enclosing production.
+    pub propagated_semantics: Vec<i32>,
+    pub propagated_grammar: Vec<i32>,
+    pub children: Vec<Derivation>,
+}
+
+impl Derivation {
+    pub fn new(placeholder: i32, reference: String, text: String) -> Self {
+        Self {
+            placeholder,
+            reference,
+            production: None,
+            discarded_productions: Vec::new(),
+            word: None,
+            text,
+            inherited_semantics: Vec::new(),
+            inherited_grammar: Vec::new(),
+            registered_semantics: Vec::new(),
+            registered_grammar: Vec::new(),
+            propagated_semantics: Vec::new(),
+            propagated_grammar: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    /// Depth-first walk over this node and all of its descendants.
+    #[cfg(test)]
+    pub fn nodes(&self) -> Vec<&Derivation> {
+        std::iter::once(self)
+            .chain(self.children.iter().flat_map(Derivation::nodes))
+            .collect()
+    }
+}
@@ -1,5 +1,5 @@
 use itertools::Itertools;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::str::FromStr;
 
 #[cfg(test)]
@@ -15,6 +15,7 @@ use crate::{
 use lazy_static::lazy_static;
 use regex::{Captures, Regex};
 
+pub mod derivation;
 pub mod parsing;
 
 lazy_static! {
@@ -23,10 +24,15 @@ lazy_static! {
 }
 
 pub struct ProductionBranch {
+    text: String,
     sequence: Vec<PlaceholderReference>,
 }
 
 impl ProductionBranch {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
     pub fn ordered_placeholder_references(&self) -> AppResult<Vec<&PlaceholderReference>> {
         let lookup: HashMap<i32, &PlaceholderReference> = self.sequence.iter().try_fold(
             HashMap::new(),
@@ -56,6 +62,10 @@ impl ProductionBranch {
             .collect::<Vec<_>>())
     }
 
+    pub fn placeholder_references(&self) -> &[PlaceholderReference] {
+        &self.sequence
+    }
+
     #[allow(unused)]
     pub fn placeholder_appearance_order_in_production(&self) -> Vec<i32> {
         self.sequence
@@ -65,8 +75,9 @@ impl ProductionBranch {
     }
 
     fn topologically_sorted(&self, graph: &HashMap<i32, Vec<i32>>) -> AppResult<Vec<i32>> {
-        let mut yet_to_finalize: HashSet<i32> =
-            graph.iter().map(|(&i, _)| i).collect::<HashSet<_>>();
+        // Ordered, so that walks always start from the same node: generation must be reproducible
+        let mut yet_to_finalize: BTreeSet<i32> =
+            graph.iter().map(|(&i, _)| i).collect::<BTreeSet<_>>();
         let mut finalized: HashSet<i32> = HashSet::new();
         let mut order: Vec<i32> = Vec::new();
 
@@ -121,7 +132,6 @@ impl FromStr for ProductionBranch {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let (successes, failures): (Vec<_>, Vec<_>) = s
             .split(' ')
-            .into_iter()
             .map(|str| str.trim())
             .map(PlaceholderReference::from_str)
             .into_iter()
@@ -129,11 +139,12 @@ impl FromStr for ProductionBranch {
 
         if failures.is_empty() {
             Ok(Self {
+                text: s.to_owned(),
                 sequence: successes.into_iter().flat_map(Result::ok).collect(),
             })
         } else {
             Err(AppError::for_multiple_errors(
-                failures.into_iter().flat_map(Result::Err).collect(),
+                failures.into_iter().flat_map(Result::err).collect(),
             ))
         }
     }
@@ -158,7 +169,7 @@ impl PlaceholderReference {
             .and_then(TokenReference::from_str)
             .map(Self::WordSelector)
     }
-    fn dependencies(&self) -> Vec<i32> {
+    pub fn dependencies(&self) -> Vec<i32> {
         match self {
             PlaceholderReference::NonTerminalSymbol(reference)
             | PlaceholderReference::WordSelector(reference) => vec![
@@ -31,6 +31,17 @@ impl TokenReference {
         }
     }
 
+    /// A trivial reference that inherits the semantic context in which it gets expanded.
+    pub fn new_contextual_reference(reference: String) -> Self {
+        Self {
+            semantic_properties: PropagationProperties {
+                can_propagate: false,
+                dependency: Dependency::OnContext,
+            },
+            ..Self::new_trivial_reference(reference)
+        }
+    }
+
     pub fn id(&self) -> i32 {
         self.id
     }
@@ -38,6 +49,9 @@ impl TokenReference {
         &self.reference
     }
 
+    pub fn grammar_dependency(&self) -> &Dependency {
+        &self.grammar_properties.dependency
+    }
     pub fn grammar_dependency_on_other(&self) -> Option<i32> {
         self.grammar_properties
             .dependency_to_other()
@@ -50,6 +64,9 @@ impl TokenReference {
         self.grammar_properties.can_propagate()
     }
 
+    pub fn semantic_dependency(&self) -> &Dependency {
+        &self.semantic_properties.dependency
+    }
     pub fn semantic_dependency_on_other(&self) -> Option<i32> {
         self.semantic_properties
             .dependency_to_other()
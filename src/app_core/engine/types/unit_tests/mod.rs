@@ -102,3 +102,11 @@ fn parsed_production_branch_returns_detected_cycle_error_of_expected_length() {
         }
     }
 }
+
+#[test]
+fn weight_prefix_is_not_a_placeholder() {
+    let str = "*3 {0:N:F:N:F:some}";
+    let result = ProductionBranch::from_str(str);
+
+    assert!(matches!(result, Err(AppError::Multiple(_))));
+}
@@ -0,0 +1,105 @@
+use crate::app_core::engine::analysis::{analyse_definition, SymbolAnalysis};
+use crate::app_core::engine::sources::{GrammarDefinition, StoredProduction};
+
+fn definition(productions: &[(&str, &str)]) -> GrammarDefinition {
+    GrammarDefinition {
+        non_terminal_symbols: productions
+            .iter()
+            .map(|(symbol, _)| symbol.to_string())
+            .sorted_unique(),
+        semantic_tags: vec![],
+        productions: productions
+            .iter()
+            .enumerate()
+            .map(|(id, (symbol, production))| StoredProduction {
+                id: id as i32 + 1,
+                non_terminal_symbol: symbol.to_string(),
+                production: production.to_string(),
+            })
+            .collect(),
+    }
+}
+
+trait SortedUnique {
+    fn sorted_unique(self) -> Vec<String>;
+}
+
+impl<I: Iterator<Item = String>> SortedUnique for I {
+    fn sorted_unique(self) -> Vec<String> {
+        use itertools::Itertools;
+
+        self.sorted().dedup().collect()
+    }
+}
+
+fn analysis_of<'a>(symbols: &'a [SymbolAnalysis], name: &str) -> &'a SymbolAnalysis {
+    symbols
+        .iter()
+        .find(|symbol| symbol.name == name)
+        .unwrap_or_else(|| panic!("No analysis for {}", name))
+}
+
+#[test]
+fn symbols_not_referenced_from_start_are_unreachable() {
+    let symbols = analyse_definition(
+        &definition(&[
+            ("Start", "{0:N:F:N:F:Subject}"),
+            ("Subject", "<0:N:F:N:F:noun>"),
+            ("Orphan", "<0:N:F:N:F:noun>"),
+        ]),
+        &["Start"],
+    );
+
+    assert!(analysis_of(&symbols, "Start").reachable);
+    assert!(analysis_of(&symbols, "Subject").reachable);
+    assert!(!analysis_of(&symbols, "Orphan").reachable);
+}
+
+#[test]
+fn symbols_whose_branches_all_recurse_are_unproductive() {
+    let symbols = analyse_definition(
+        &definition(&[
+            ("Start", "{0:N:F:N:F:Loop}"),
+            ("Start", "<0:N:F:N:F:noun>"),
+            ("Loop", "<0:N:F:N:F:noun> {1:N:F:N:F:Loop}"),
+            ("Loop", "{0:N:F:N:F:Start}"),
+            ("Loop", "{0:N:F:N:F:Start} {1:N:F:N:F:Forever}"),
+            ("Forever", "{0:N:F:N:F:Forever}"),
+        ]),
+        &["Start"],
+    );
+
+    assert!(analysis_of(&symbols, "Start").productive);
+    assert!(analysis_of(&symbols, "Loop").productive);
+    assert!(!analysis_of(&symbols, "Forever").productive);
+    assert_eq!(analysis_of(&symbols, "Forever").min_depth, None);
+    assert_eq!(
+        analysis_of(&symbols, "Forever").min_non_terminal_symbols,
+        None
+    );
+}
+
+#[test]
+fn minimum_depth_and_size_may_come_from_different_branches() {
+    let symbols = analyse_definition(
+        &definition(&[
+            ("Start", "{0:N:F:N:F:Deep}"),
+            (
+                "Start",
+                "{0:N:F:N:F:Leaf} {1:N:F:N:F:Leaf} {2:N:F:N:F:Leaf}",
+            ),
+            ("Deep", "{0:N:F:N:F:Leaf}"),
+            ("Leaf", "<0:N:F:N:F:noun>"),
+        ]),
+        &["Start"],
+    );
+
+    let start = analysis_of(&symbols, "Start");
+    assert_eq!(start.min_depth, Some(2));
+    assert_eq!(start.min_non_terminal_symbols, Some(3));
+    assert_eq!(analysis_of(&symbols, "Leaf").min_depth, Some(1));
+    assert_eq!(
+        analysis_of(&symbols, "Leaf").min_non_terminal_symbols,
+        Some(1)
+    );
+}
@@ -0,0 +1,372 @@
+use futures::executor::block_on;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::app_core::engine::sources::{
+    Category, InMemoryGrammarSource, InMemorySemanticTag, InMemoryWord,
+};
+use crate::app_core::engine::{
+    generate_phrase, resolve_category, GeneratedPhrase, GenerationLimits,
+};
+use crate::app_core::errors::{AppError, DataError, GenerationError};
+use crate::app_core::AppResult;
+
+const NOUN: i32 = 1;
+const VERB: i32 = 2;
+const ANIMAL: i32 = 3;
+const MACHINE: i32 = 4;
+
+const SINGULAR: i32 = 1;
+const PLURAL: i32 = 2;
+
+fn semantic_tag(id: i32, name: &str, sticky: bool) -> InMemorySemanticTag {
+    InMemorySemanticTag {
+        id,
+        name: name.to_owned(),
+        sticky,
+    }
+}
+
+fn word(
+    id: i32,
+    content: &str,
+    semantics: &[i32],
+    compatible: &[i32],
+    requires: &[i32],
+) -> InMemoryWord {
+    InMemoryWord {
+        id,
+        content: content.to_owned(),
+        non_repeatable: true,
+        weight: 1,
+        semantic_tags: semantics.to_vec(),
+        grammar_compatibilities: compatible.to_vec(),
+        grammar_requirements: requires.to_vec(),
+    }
+}
+
+fn generate_with_seed(source: &mut InMemoryGrammarSource, seed: u64) -> AppResult<String> {
+    generate_with_limits(source, seed, &GenerationLimits::default()).map(|phrase| phrase.text)
+}
+
+fn generate_with_limits(
+    source: &mut InMemoryGrammarSource,
+    seed: u64,
+    limits: &GenerationLimits,
+) -> AppResult<GeneratedPhrase> {
+    block_on(generate_phrase(
+        &Category::default(),
+        limits,
+        source,
+        &mut StdRng::seed_from_u64(seed),
+    ))
+}
+
+fn generate_in_category(
+    source: &mut InMemoryGrammarSource,
+    category: &str,
+    seed: u64,
+) -> AppResult<String> {
+    let category = block_on(resolve_category(Some(category), source))?;
+
+    block_on(generate_phrase(
+        &category,
+        &GenerationLimits::default(),
+        source,
+        &mut StdRng::seed_from_u64(seed),
+    ))
+    .map(|phrase| phrase.text)
+}
+
+fn category(name: &str, start_symbol: &str, semantic_tags: &[&str]) -> Category {
+    Category {
+        name: name.to_owned(),
+        start_symbol: start_symbol.to_owned(),
+        semantic_tags: semantic_tags.iter().map(|tag| tag.to_string()).collect(),
+    }
+}
+
+fn without_backtracking() -> GenerationLimits {
+    GenerationLimits {
+        max_backtracks: 0,
+        ..GenerationLimits::default()
+    }
+}
+
+fn animals_grammar() -> InMemoryGrammarSource {
+    let mut source = InMemoryGrammarSource::new();
+    source.add_semantic_tag(semantic_tag(NOUN, "noun", false));
+    source.add_semantic_tag(semantic_tag(VERB, "verb", false));
+    source.add_semantic_tag(semantic_tag(ANIMAL, "animal", true));
+    source.add_semantic_tag(semantic_tag(MACHINE, "machine", true));
+
+    source.add_word(word(1, "cane", &[NOUN, ANIMAL], &[], &[SINGULAR]));
+    source.add_word(word(2, "abbaia", &[VERB, ANIMAL], &[SINGULAR], &[]));
+    source.add_word(word(3, "miagolano", &[VERB, ANIMAL], &[PLURAL], &[]));
+    source.add_word(word(4, "gira", &[VERB, MACHINE], &[SINGULAR], &[]));
+
+    source
+}
+
+#[test]
+fn single_word_production_is_generated() {
+    let mut source = animals_grammar();
+    source.add_production("Start", "<0:N:F:N:F:noun>");
+
+    let result = generate_with_seed(&mut source, 0).unwrap();
+
+    assert_eq!(result, "cane");
+}
+
+#[test]
+fn word_choice_honours_grammar_and_semantics_of_sibling_nts() {
+    let mut source = animals_grammar();
+    source.add_production("Start", "{0:N:F:N:F:Subject} <1:O(0):F:O(0):F:verb>");
+    source.add_production("Subject", "<0:N:T:N:T:noun>");
+
+    let result = generate_with_seed(&mut source, 0).unwrap();
+
+    assert_eq!(result, "cane abbaia");
+}
+
+#[test]
+fn non_repeatable_words_are_not_picked_twice() {
+    let mut source = animals_grammar();
+    source.add_production("Start", "<0:N:F:N:F:noun> <1:N:F:N:F:noun>");
+
+    let result = generate_with_limits(&mut source, 0, &without_backtracking());
+
+    assert!(matches!(
+        result,
+        Err(AppError::Generation(GenerationError::NoWordsFound))
+    ));
+}
+
+#[test]
+fn missing_non_terminal_symbol_is_reported() {
+    let mut source = animals_grammar();
+    source.add_production("Start", "{0:N:F:N:F:Missing}");
+
+    let result = generate_with_limits(&mut source, 0, &without_backtracking());
+
+    assert!(matches!(
+        result,
+        Err(AppError::Generation(GenerationError::NoProductionBranchesFound(name))) if name.eq("Missing")
+    ));
+}
+
+#[test]
+fn same_seed_reproduces_the_same_phrase() {
+    let mut source = animals_grammar();
+    source.add_production("Start", "<0:N:F:N:F:verb> <1:N:F:N:F:verb>");
+    source.add_production("Start", "<0:N:F:N:F:verb> <1:N:F:N:F:noun>");
+    source.add_production("Start", "<0:N:F:N:F:noun> <1:N:F:N:F:verb>");
+
+    for seed in 0..32 {
+        assert_eq!(
+            generate_with_seed(&mut source, seed).unwrap(),
+            generate_with_seed(&mut source, seed).unwrap()
+        );
+    }
+}
+
+#[test]
+fn dead_end_branch_is_backtracked_and_its_words_released() {
+    let mut source = animals_grammar();
+    source.add_production("Start", "<0:N:F:N:F:noun> <1:N:F:N:F:noun>");
+    source.add_production("Start", "<0:N:F:N:F:noun> <1:O(0):F:O(0):F:verb>");
+
+    for seed in 0..16 {
+        let phrase = generate_with_limits(&mut source, seed, &GenerationLimits::default()).unwrap();
+
+        assert_eq!(phrase.text, "cane abbaia");
+        assert!(phrase.backtracks <= 1);
+    }
+}
+
+#[test]
+fn nested_dead_end_is_backtracked_by_the_enclosing_nts() {
+    let mut source = animals_grammar();
+    source.add_production("Start", "{0:N:F:N:F:Subject} <1:O(0):F:O(0):F:verb>");
+    source.add_production("Subject", "{0:N:F:N:F:Missing}");
+    source.add_production("Subject", "<0:N:T:N:T:noun>");
+
+    for seed in 0..16 {
+        let phrase = generate_with_limits(&mut source, seed, &GenerationLimits::default()).unwrap();
+
+        assert_eq!(phrase.text, "cane abbaia");
+    }
+}
+
+#[test]
+fn exhausted_alternatives_report_the_last_failure() {
+    let mut source = animals_grammar();
+    source.add_production("Start", "<0:N:F:N:F:noun> <1:N:F:N:F:noun>");
+
+    let result = generate_with_limits(&mut source, 0, &GenerationLimits::default());
+
+    assert!(matches!(
+        result,
+        Err(AppError::Generation(GenerationError::NoWordsFound))
+    ));
+}
+
+#[test]
+fn category_semantic_tags_seed_the_root_context() {
+    let mut source = animals_grammar();
+    source.add_production("Start", "<0:N:F:C:F:verb>");
+    source.add_category(category("machines", "Start", &["machine"]));
+
+    for seed in 0..16 {
+        assert_eq!(
+            generate_in_category(&mut source, "machines", seed).unwrap(),
+            "gira"
+        );
+    }
+}
+
+#[test]
+fn unknown_category_semantic_tags_are_reported() {
+    let mut source = animals_grammar();
+    source.add_production("Start", "<0:N:F:C:F:verb>");
+    source.add_category(category("machines", "Start", &["machine", "machnie"]));
+
+    let result = generate_in_category(&mut source, "machines", 0);
+
+    assert!(matches!(
+        result,
+        Err(AppError::Generation(GenerationError::UnknownCategoryTags(name, tags)))
+            if name == "machines" && tags == vec!["machnie"]
+    ));
+}
+
+#[test]
+fn category_start_symbol_replaces_the_default_one() {
+    let mut source = animals_grammar();
+    source.add_production("Start", "<0:N:F:N:F:verb>");
+    source.add_production("Barking", "<0:N:F:N:F:noun> <1:O(0):F:O(0):F:verb>");
+    source.add_category(category("barking", "Barking", &[]));
+
+    let result = generate_in_category(&mut source, "barking", 0).unwrap();
+
+    assert_eq!(result, "cane abbaia");
+}
+
+#[test]
+fn unknown_category_is_reported() {
+    let mut source = animals_grammar();
+    source.add_production("Start", "<0:N:F:N:F:verb>");
+
+    let result = generate_in_category(&mut source, "nowhere", 0);
+
+    assert!(matches!(
+        result,
+        Err(AppError::Generation(GenerationError::UnknownCategory(name))) if name.eq("nowhere")
+    ));
+}
+
+#[test]
+fn heavier_production_branch_is_picked_more_often() {
+    let mut source = animals_grammar();
+    source.add_weighted_production("Start", "<0:N:F:N:F:noun>", 1);
+    source.add_weighted_production("Start", "<0:N:F:N:F:verb>", 50);
+
+    let picked_heavier = (0..64)
+        .filter(|&seed| generate_with_seed(&mut source, seed).unwrap() != "cane")
+        .count();
+
+    assert!(picked_heavier > 48);
+}
+
+#[test]
+fn weights_too_heavy_for_i32_are_summed() {
+    let mut source = animals_grammar();
+    source.add_weighted_production("Start", "<0:N:F:N:F:noun>", i32::MAX);
+    source.add_weighted_production("Start", "<0:N:F:N:F:verb>", i32::MAX);
+
+    assert!(generate_with_seed(&mut source, 0).is_ok());
+}
+
+#[test]
+fn invalid_weights_are_reported_instead_of_backtracked() {
+    let mut source = animals_grammar();
+    source.add_weighted_production("Start", "<0:N:F:N:F:noun>", -1);
+    source.add_weighted_production("Start", "<0:N:F:N:F:verb>", 1);
+
+    let result = generate_with_seed(&mut source, 0);
+
+    assert!(matches!(result, Err(AppError::Data(DataError::Weights(_)))));
+}
+
+#[test]
+fn heavier_word_is_picked_more_often() {
+    let mut source = animals_grammar();
+    source.add_word(InMemoryWord {
+        weight: 50,
+        ..word(5, "gatto", &[NOUN, ANIMAL], &[], &[SINGULAR])
+    });
+    source.add_production("Start", "<0:N:F:N:F:noun>");
+
+    let picked_heavier = (0..64)
+        .filter(|&seed| generate_with_seed(&mut source, seed).unwrap() == "gatto")
+        .count();
+
+    assert!(picked_heavier > 48);
+}
+
+#[test]
+fn derivation_tree_follows_the_expanded_placeholders() {
+    let mut source = animals_grammar();
+    source.add_production("Start", "{0:N:F:N:F:Subject} <1:O(0):F:O(0):F:verb>");
+    source.add_production("Subject", "<0:N:T:N:T:noun>");
+
+    let derivation = generate_with_limits(&mut source, 0, &GenerationLimits::default())
+        .unwrap()
+        .derivation;
+
+    assert_eq!(derivation.reference, "Start");
+    assert_eq!(derivation.text, "cane abbaia");
+    assert_eq!(
+        derivation
+            .children
+            .iter()
+            .map(|child| child.reference.as_str())
+            .collect::<Vec<_>>(),
+        ["Subject", "verb"]
+    );
+
+    let subject = &derivation.children[0];
+    assert_eq!(subject.production.as_deref(), Some("<0:N:T:N:T:noun>"));
+    assert_eq!(subject.registered_semantics, [ANIMAL]);
+    assert_eq!(subject.registered_grammar, [SINGULAR]);
+
+    let noun = &subject.children[0];
+    assert_eq!(noun.word, Some(1));
+    assert_eq!(noun.propagated_semantics, [ANIMAL]);
+    assert_eq!(noun.propagated_grammar, [SINGULAR]);
+
+    let verb = &derivation.children[1];
+    assert_eq!(verb.word, Some(2));
+    assert_eq!(verb.inherited_semantics, [ANIMAL]);
+    assert_eq!(verb.inherited_grammar, [SINGULAR]);
+    assert!(verb.children.is_empty());
+}
+
+#[test]
+fn derivation_tree_reports_discarded_productions() {
+    let mut source = animals_grammar();
+    source.add_production("Start", "{0:N:F:N:F:Subject} <1:O(0):F:O(0):F:verb>");
+    source.add_production("Subject", "{0:N:F:N:F:Missing}");
+    source.add_production("Subject", "<0:N:T:N:T:noun>");
+
+    for seed in 0..16 {
+        let phrase = generate_with_limits(&mut source, seed, &GenerationLimits::default()).unwrap();
+        let subject = &phrase.derivation.children[0];
+
+        assert_eq!(
+            subject.discarded_productions.len() as u32,
+            phrase.backtracks
+        );
+        assert_eq!(phrase.derivation.nodes().len(), 4);
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::HashSet;
+
+use futures::executor::block_on;
+
+use crate::app_core::engine::sources::{InMemoryGrammarSource, InMemorySemanticTag};
+use crate::app_core::engine::validation::{
+    check_production, validate_grammar, GrammarProblemKind, GrammarReport,
+};
+use crate::app_core::errors::{AppError, DataError, ProductionError};
+
+fn grammar() -> InMemoryGrammarSource {
+    let mut source = InMemoryGrammarSource::new();
+    source.add_semantic_tag(InMemorySemanticTag {
+        id: 1,
+        name: "noun".to_owned(),
+        sticky: false,
+    });
+    source.add_production("Subject", "<0:N:T:N:T:noun>");
+
+    source
+}
+
+fn validate(source: &mut InMemoryGrammarSource) -> GrammarReport {
+    block_on(validate_grammar(source)).unwrap()
+}
+
+fn kinds(report: &GrammarReport) -> Vec<(i32, GrammarProblemKind)> {
+    report
+        .problems
+        .iter()
+        .map(|problem| (problem.production, problem.kind))
+        .collect()
+}
+
+#[test]
+fn well_formed_grammar_has_no_problems() {
+    let mut source = grammar();
+    source.add_production("Start", "{0:N:F:N:F:Subject} <1:O(0):F:O(0):F:noun>");
+
+    assert!(validate(&mut source).is_valid());
+}
+
+#[test]
+fn unparsable_production_is_reported_with_its_id() {
+    let mut source = grammar();
+    source.add_production("Start", "{0:N:F:N:F:Subject} <1#N:F:N:F:noun>");
+
+    let report = validate(&mut source);
+
+    assert_eq!(kinds(&report), [(2, GrammarProblemKind::Unparsable)]);
+    assert_eq!(report.problems[0].non_terminal_symbol, "Start");
+}
+
+#[test]
+fn id_clashes_and_cycles_are_reported() {
+    let mut source = grammar();
+    source.add_production("Start", "<0:N:F:N:F:noun> <0:N:F:N:F:noun>");
+    source.add_production("Start", "<0:O(1):F:N:F:noun> <1:O(0):F:N:F:noun>");
+
+    let report = validate(&mut source);
+
+    assert_eq!(
+        kinds(&report),
+        [
+            (2, GrammarProblemKind::IdClash),
+            (3, GrammarProblemKind::DependencyCycle)
+        ]
+    );
+}
+
+#[test]
+fn dependency_on_missing_placeholder_is_reported() {
+    let mut source = grammar();
+    source.add_production("Start", "<0:N:F:N:F:noun> <1:O(7):F:N:F:noun>");
+
+    let report = validate(&mut source);
+
+    assert_eq!(
+        kinds(&report),
+        [(2, GrammarProblemKind::UnknownPlaceholder)]
+    );
+}
+
+#[test]
+fn dangling_references_are_all_reported() {
+    let mut source = grammar();
+    source.add_production(
+        "Start",
+        "{0:N:F:N:F:Missing} <1:N:F:N:F:verb> <2:N:F:N:F:adverb>",
+    );
+
+    let report = validate(&mut source);
+
+    assert_eq!(
+        kinds(&report),
+        [
+            (2, GrammarProblemKind::UnknownNonTerminalSymbol),
+            (2, GrammarProblemKind::UnknownSemanticTag),
+            (2, GrammarProblemKind::UnknownSemanticTag)
+        ]
+    );
+    assert_eq!(
+        report.problems[0].to_string(),
+        "production 2 of 'Start': unknown non-terminal symbol 'Missing'"
+    );
+}
+
+#[test]
+fn report_includes_reachability_and_productivity() {
+    let mut source = grammar();
+    source.add_production("Start", "{0:N:F:N:F:Start}");
+    source.add_production("Orphan", "<0:N:F:N:F:noun>");
+
+    let report = validate(&mut source);
+
+    assert!(report.is_valid());
+    assert_eq!(report.unreachable_symbols(), ["Orphan", "Subject"]);
+    assert_eq!(report.unproductive_symbols(), ["Start"]);
+}
+
+#[test]
+fn checked_production_fails_on_its_first_problem() {
+    let known = ["Start", "Subject"].into_iter().collect::<HashSet<_>>();
+    let production_error = |production: &str| match check_production(production, &known) {
+        Err(AppError::Data(DataError::Production(error))) => Some(error),
+        _ => None,
+    };
+
+    assert!(check_production("{0:N:F:N:F:Subject} <1:O(0):F:O(0):F:verb>", &known).is_ok());
+    assert!(matches!(
+        production_error("<0:O(3):F:N:F:noun>"),
+        Some(ProductionError::UnknownPlaceholder(0, 3))
+    ));
+    assert!(matches!(
+        production_error("<0:N:F:N:F:noun> <0:N:F:N:F:verb>"),
+        Some(ProductionError::IdClash(0))
+    ));
+    assert!(matches!(
+        production_error("<0:O(1):F:N:F:noun> <1:O(0):F:N:F:verb>"),
+        Some(ProductionError::CycleDetected(_))
+    ));
+    assert!(matches!(
+        production_error("{0:N:F:N:F:Object}"),
+        Some(ProductionError::UnknownNonTerminalSymbol(name)) if name == "Object"
+    ));
+    assert!(matches!(
+        check_production("<0:X:F:N:F:noun> <1:N:F:N:F:verb> <2:N:F:N:F>", &known),
+        Err(AppError::Data(DataError::GrammarParses(errors))) if errors.len() == 2
+    ));
+}
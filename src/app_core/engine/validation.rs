@@ -0,0 +1,263 @@
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use itertools::Itertools;
+
+use crate::app_core::engine::analysis::{analyse_definition, SymbolAnalysis};
+use crate::app_core::engine::sources::{
+    GrammarDefinition, GrammarSource, StoredProduction, DEFAULT_START_SYMBOL,
+};
+use crate::app_core::engine::types::{PlaceholderReference, ProductionBranch};
+use crate::app_core::errors::{AppError, DataError, ProductionError};
+use crate::app_core::AppResult;
+
+#[cfg(test)]
+#[path = "./unit_tests/validation.rs"]
+mod tests;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GrammarProblemKind {
+    Unparsable,
+    IdClash,
+    UnknownPlaceholder,
+    DependencyCycle,
+    UnknownNonTerminalSymbol,
+    UnknownSemanticTag,
+}
+
+/// Something wrong within a stored production, the kind of failure generation would hit sooner or later.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GrammarProblem {
+    pub production: i32,
+    pub non_terminal_symbol: String,
+    pub kind: GrammarProblemKind,
+    pub description: String,
+}
+
+impl Display for GrammarProblem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "production {} of '{}': {}",
+            self.production, self.non_terminal_symbol, self.description
+        )
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct GrammarReport {
+    pub problems: Vec<GrammarProblem>,
+    pub symbols: Vec<SymbolAnalysis>,
+}
+
+impl GrammarReport {
+    pub fn is_valid(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    pub fn unreachable_symbols(&self) -> Vec<&str> {
+        self.symbols
+            .iter()
+            .filter(|symbol| !symbol.reachable)
+            .map(|symbol| symbol.name.as_str())
+            .collect()
+    }
+
+    pub fn unproductive_symbols(&self) -> Vec<&str> {
+        self.symbols
+            .iter()
+            .filter(|symbol| !symbol.productive)
+            .map(|symbol| symbol.name.as_str())
+            .collect()
+    }
+}
+
+/// Generation may start from the default start symbol or from the one of any category.
+pub async fn validate_grammar(source: &mut dyn GrammarSource) -> AppResult<GrammarReport> {
+    let definition = source.retrieve_definition().await?;
+    let categories = source.retrieve_categories().await?;
+    let start_symbols = std::iter::once(DEFAULT_START_SYMBOL)
+        .chain(
+            categories
+                .iter()
+                .map(|category| category.start_symbol.as_str()),
+        )
+        .unique()
+        .collect_vec();
+
+    Ok(validate_definition(&definition, &start_symbols))
+}
+
+/// Parses every production and checks what generation would otherwise find out only when picking it.
+pub fn validate_definition(
+    definition: &GrammarDefinition,
+    start_symbols: &[&str],
+) -> GrammarReport {
+    let non_terminal_symbols = definition
+        .non_terminal_symbols
+        .iter()
+        .map(String::as_str)
+        .collect::<HashSet<_>>();
+    let semantic_tags = definition
+        .semantic_tags
+        .iter()
+        .map(String::as_str)
+        .collect::<HashSet<_>>();
+
+    GrammarReport {
+        problems: definition
+            .productions
+            .iter()
+            .flat_map(|production| {
+                validate_production(production, &non_terminal_symbols, &semantic_tags)
+            })
+            .collect(),
+        symbols: analyse_definition(definition, start_symbols),
+    }
+}
+
+/// Checks a single production before it gets stored, failing on the first problem found.
+/// Unknown semantic tags are tolerated: words may be added later on.
+pub fn check_production(production: &str, non_terminal_symbols: &HashSet<&str>) -> AppResult<()> {
+    let branch = ProductionBranch::from_str(production).map_err(gather_parse_errors)?;
+    let placeholders = branch.placeholder_references();
+    let ids = placeholders
+        .iter()
+        .map(PlaceholderReference::id)
+        .collect::<HashSet<_>>();
+
+    // Sorting dependencies on missing placeholders is not possible
+    for placeholder in placeholders {
+        if let Some(dependency) = placeholder
+            .dependencies()
+            .into_iter()
+            .find(|dependency| !ids.contains(dependency))
+        {
+            return Err(AppError::for_production_unknown_placeholder(
+                placeholder.id(),
+                dependency,
+            ));
+        }
+    }
+    branch.ordered_placeholder_references()?;
+
+    for placeholder in placeholders {
+        if let PlaceholderReference::NonTerminalSymbol(token) = placeholder {
+            if !non_terminal_symbols.contains(token.reference()) {
+                return Err(AppError::for_production_unknown_non_terminal_symbol(
+                    token.reference().to_owned(),
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Parsing reports a failure per unrecognised token: when they are all parse errors they are kept together.
+fn gather_parse_errors(error: AppError) -> AppError {
+    match error {
+        AppError::Multiple(errors) => {
+            let parse_errors = errors
+                .iter()
+                .filter_map(|error| match error {
+                    AppError::Data(DataError::GrammarParse(parse_error)) => {
+                        Some(parse_error.clone())
+                    }
+                    _ => None,
+                })
+                .collect_vec();
+
+            if parse_errors.len() == errors.len() {
+                AppError::for_grammar_parses(parse_errors)
+            } else {
+                AppError::for_multiple_errors(errors)
+            }
+        }
+        other => other,
+    }
+}
+
+fn validate_production(
+    production: &StoredProduction,
+    non_terminal_symbols: &HashSet<&str>,
+    semantic_tags: &HashSet<&str>,
+) -> Vec<GrammarProblem> {
+    let problem = |kind: GrammarProblemKind, description: String| GrammarProblem {
+        production: production.id,
+        non_terminal_symbol: production.non_terminal_symbol.clone(),
+        kind,
+        description,
+    };
+
+    let branch = match ProductionBranch::from_str(&production.production) {
+        Ok(branch) => branch,
+        Err(error) => return vec![problem(GrammarProblemKind::Unparsable, error.to_string())],
+    };
+    let placeholders = branch.placeholder_references();
+    let ids = placeholders
+        .iter()
+        .map(PlaceholderReference::id)
+        .collect::<HashSet<_>>();
+
+    let mut problems = placeholders
+        .iter()
+        .flat_map(|placeholder| {
+            placeholder
+                .dependencies()
+                .into_iter()
+                .filter(|dependency| !ids.contains(dependency))
+                .map(move |dependency| (placeholder.id(), dependency))
+        })
+        .map(|(placeholder, dependency)| {
+            problem(
+                GrammarProblemKind::UnknownPlaceholder,
+                format!("placeholder {placeholder} depends on missing placeholder {dependency}"),
+            )
+        })
+        .collect_vec();
+
+    // Sorting dependencies on missing placeholders is not possible
+    if problems.is_empty() {
+        if let Err(error) = branch.ordered_placeholder_references() {
+            problems.push(match error {
+                AppError::Data(DataError::Production(reason @ ProductionError::IdClash(_))) => {
+                    problem(GrammarProblemKind::IdClash, reason.to_string())
+                }
+                AppError::Data(DataError::Production(
+                    reason @ ProductionError::CycleDetected(_),
+                )) => problem(GrammarProblemKind::DependencyCycle, reason.to_string()),
+                other => problem(GrammarProblemKind::Unparsable, other.to_string()),
+            });
+        }
+    }
+
+    for placeholder in placeholders {
+        match placeholder {
+            PlaceholderReference::NonTerminalSymbol(token) => {
+                if !non_terminal_symbols.contains(token.reference()) {
+                    problems.push(problem(
+                        GrammarProblemKind::UnknownNonTerminalSymbol,
+                        format!("unknown non-terminal symbol '{}'", token.reference()),
+                    ));
+                }
+            }
+            PlaceholderReference::WordSelector(token) => {
+                for tag in token
+                    .reference()
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|tag| !semantic_tags.contains(tag))
+                {
+                    problems.push(problem(
+                        GrammarProblemKind::UnknownSemanticTag,
+                        format!("unknown semantic tag '{}'", tag),
+                    ));
+                }
+            }
+        }
+    }
+
+    problems
+}
@@ -1,3 +1,4 @@
+use rand::distributions::WeightedError;
 use sqlx::Error;
 use std::num::ParseIntError;
 
@@ -13,6 +14,12 @@ pub enum AppError {
     Infrastructure(#[from] InfrastructureError),
     #[error("Some data produced an error. {0}")]
     Data(#[from] DataError),
+    #[error("Change rejected. {0}")]
+    Edit(#[from] EditError),
+    #[error("Audio storage failed. {0}")]
+    Storage(#[from] StorageError),
+    #[error("Settings are not valid. {0}")]
+    Settings(#[from] SettingsError),
     #[error("Multiple errors: {0:?}")]
     Multiple(Vec<AppError>),
 }
@@ -27,6 +34,38 @@ impl AppError {
     pub fn for_upload_in_sql_uuid(error: sqlx::types::uuid::Error) -> Self {
         UploadError::from(error).into()
     }
+    pub fn for_upload_synthesis(reason: String) -> Self {
+        UploadError::SynthesisFailed(reason).into()
+    }
+    /// Partial seconds count as whole ones.
+    pub fn for_upload_circuit_open(remaining: std::time::Duration) -> Self {
+        let seconds = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+        UploadError::CircuitOpen(seconds).into()
+    }
+    pub fn for_upload_foreign_voice(voice: i32, provider: String) -> Self {
+        UploadError::ForeignVoice(voice, provider).into()
+    }
+    pub fn for_storage_download(reason: String) -> Self {
+        StorageError::DownloadFailed(reason).into()
+    }
+    pub fn for_storage_file_access(error: std::io::Error) -> Self {
+        StorageError::FileAccess(format!("{error}")).into()
+    }
+    pub fn for_storage_in_sql(error: sqlx::Error) -> Self {
+        StorageError::DBFailed(format!("{error}")).into()
+    }
+    pub fn for_settings_file_access(path: &std::path::Path, error: std::io::Error) -> Self {
+        SettingsError::FileAccess(path.display().to_string(), format!("{error}")).into()
+    }
+    pub fn for_settings_file_format(path: &std::path::Path, error: toml::de::Error) -> Self {
+        SettingsError::FileFormat(path.display().to_string(), format!("{error}")).into()
+    }
+    pub fn for_settings_environment(name: &'static str, value: String, reason: String) -> Self {
+        SettingsError::Environment(name, value, reason).into()
+    }
+    pub fn for_settings_invalid(problems: Vec<String>) -> Self {
+        SettingsError::Invalid(problems).into()
+    }
     pub fn for_generation_in_sql(error: sqlx::Error) -> Self {
         GenerationError::from(error).into()
     }
@@ -42,12 +81,33 @@ impl AppError {
     pub fn for_generation_non_existent_sub_step() -> Self {
         GenerationError::NonExistentSubStep.into()
     }
+    pub fn for_generation_unknown_category(name: String) -> Self {
+        GenerationError::UnknownCategory(name).into()
+    }
+    pub fn for_generation_unknown_category_tags(category: String, tags: Vec<String>) -> Self {
+        GenerationError::UnknownCategoryTags(category, tags).into()
+    }
+    pub fn for_generation_seeded_pool() -> Self {
+        GenerationError::SeededPool.into()
+    }
+    pub fn for_generation_empty_pool(category: String) -> Self {
+        GenerationError::EmptyPool(category).into()
+    }
+    pub fn for_generation_no_distinct_phrase(attempts: u32) -> Self {
+        GenerationError::NoDistinctPhrase(attempts).into()
+    }
+    pub fn for_generation_too_many_streams(max: usize) -> Self {
+        GenerationError::TooManyStreams(max).into()
+    }
     pub fn for_infrastructure_http_client_failed(error: reqwest::Error) -> Self {
         InfrastructureError::from(error).into()
     }
     pub fn for_infrastructure_db_connections_unavailable(error: sqlx::Error) -> Self {
         InfrastructureError::from(error).into()
     }
+    pub fn for_infrastructure_synthesizer_unavailable(reason: String) -> Self {
+        InfrastructureError::SynthesizerUnavailable(reason).into()
+    }
     pub fn for_multiple_errors(errors: Vec<AppError>) -> Self {
         Self::Multiple(errors)
     }
@@ -63,12 +123,76 @@ impl AppError {
     pub fn for_unrecognized_dependency_marker(marker: String) -> Self {
         DataError::GrammarParse(ParseError::UnrecognizedDependencyMarker(marker)).into()
     }
+    pub fn for_grammar_parses(errors: Vec<ParseError>) -> Self {
+        DataError::GrammarParses(errors).into()
+    }
+    pub fn for_unknown_language(code: String) -> Self {
+        DataError::UnknownLanguage(code).into()
+    }
+    pub fn for_unknown_voice(description: String) -> Self {
+        DataError::UnknownVoice(description).into()
+    }
+    pub fn for_invalid_weights(error: WeightedError) -> Self {
+        DataError::Weights(error.to_string()).into()
+    }
+    pub fn for_unknown_phrase(id: String) -> Self {
+        DataError::UnknownPhrase(id).into()
+    }
+    pub fn for_grammar_file_line(line: usize, reason: String) -> Self {
+        DataError::GrammarFile(line, reason).into()
+    }
+    pub fn for_grammar_file_access(error: std::io::Error) -> Self {
+        DataError::GrammarFileAccess(format!("{error}")).into()
+    }
+    pub fn for_grammar_storage_in_sql(error: sqlx::Error) -> Self {
+        DataError::GrammarStorage(format!("{error}")).into()
+    }
+    pub fn for_browsing_in_sql(error: sqlx::Error) -> Self {
+        DataError::Browsing(format!("{error}")).into()
+    }
+    pub fn for_edit_in_sql(error: sqlx::Error) -> Self {
+        EditError::from(error).into()
+    }
+    pub fn for_edit_not_found(entity: &'static str, id: i32) -> Self {
+        EditError::NotFound(entity, id).into()
+    }
+    pub fn for_edit_unknown_name(entity: &'static str, name: String) -> Self {
+        EditError::UnknownName(entity, name).into()
+    }
+    pub fn for_edit_already_existing(entity: &'static str, value: String) -> Self {
+        EditError::AlreadyExisting(entity, value).into()
+    }
+    pub fn for_edit_empty_value(field: &'static str) -> Self {
+        EditError::EmptyValue(field).into()
+    }
+    pub fn for_edit_value_too_long(field: &'static str, max_length: usize, value: String) -> Self {
+        EditError::ValueTooLong(field, max_length, value).into()
+    }
+    pub fn for_edit_invalid_name(field: &'static str, value: String) -> Self {
+        EditError::InvalidName(field, value).into()
+    }
+    pub fn for_edit_non_positive_weight(weight: i32) -> Self {
+        EditError::NonPositiveWeight(weight).into()
+    }
+    pub fn for_edit_out_of_range(field: &'static str, min: f32, max: f32, value: f32) -> Self {
+        EditError::OutOfRange(field, min, max, value).into()
+    }
     pub fn for_production_id_clash(clashing_id: i32) -> Self {
         DataError::Production(ProductionError::IdClash(clashing_id)).into()
     }
     pub fn for_production_cycle_detected(cycle_ids: Vec<i32>) -> Self {
         DataError::Production(ProductionError::CycleDetected(cycle_ids)).into()
     }
+    pub fn for_production_unknown_placeholder(placeholder_id: i32, dependency_id: i32) -> Self {
+        DataError::Production(ProductionError::UnknownPlaceholder(
+            placeholder_id,
+            dependency_id,
+        ))
+        .into()
+    }
+    pub fn for_production_unknown_non_terminal_symbol(name: String) -> Self {
+        DataError::Production(ProductionError::UnknownNonTerminalSymbol(name)).into()
+    }
 }
 
 #[derive(Error, Debug, Clone)]
@@ -79,6 +203,34 @@ pub enum UploadError {
     DBFailed(String),
     #[error("DB UUID parsing failed, {0}")]
     UuidNotParsed(String),
+    #[error("Local synthesis failed, {0}")]
+    SynthesisFailed(String),
+    #[error("TTS provider keeps failing, requests are suspended for {0} more seconds")]
+    CircuitOpen(u64),
+    #[error("Voice {0} belongs to TTS provider '{1}', which is not the one in use")]
+    ForeignVoice(i32, String),
+}
+
+#[derive(Error, Debug, Clone)]
+pub enum StorageError {
+    #[error("Audio cannot be downloaded from the TTS provider, {0}")]
+    DownloadFailed(String),
+    #[error("Audio file cannot be accessed, {0}")]
+    FileAccess(String),
+    #[error("DB Error, {0}")]
+    DBFailed(String),
+}
+
+#[derive(Error, Debug, Clone)]
+pub enum SettingsError {
+    #[error("Settings file {0} cannot be read, {1}")]
+    FileAccess(String, String),
+    #[error("Settings file {0} is not well formed, {1}")]
+    FileFormat(String, String),
+    #[error("Environment variable {0} cannot be '{1}', {2}")]
+    Environment(&'static str, String, String),
+    #[error("{}", .0.join("; "))]
+    Invalid(Vec<String>),
 }
 
 #[derive(Error, Debug, Clone)]
@@ -95,6 +247,18 @@ pub enum GenerationError {
     NoWordsFound,
     #[error("Unable to find any suitable production branches for NTS named '{0}'")]
     NoProductionBranchesFound(String),
+    #[error("Unable to find a category named '{0}'")]
+    UnknownCategory(String),
+    #[error("Category '{0}' refers to unknown semantic tags {1:?}")]
+    UnknownCategoryTags(String, Vec<String>),
+    #[error("A seed reproduces generated phrases only, it cannot pick from the pool")]
+    SeededPool,
+    #[error("There is no stored phrase to reuse among {0}")]
+    EmptyPool(String),
+    #[error("Unable to find a phrase different from the previous ones in {0} attempts")]
+    NoDistinctPhrase(u32),
+    #[error("This server already streams {0} phrase subscriptions, try again later")]
+    TooManyStreams(usize),
 }
 
 impl From<sqlx::Error> for GenerationError {
@@ -103,6 +267,12 @@ impl From<sqlx::Error> for GenerationError {
     }
 }
 
+impl From<sqlx::Error> for EditError {
+    fn from(e: Error) -> Self {
+        Self::DBFailed(format!("{e}"))
+    }
+}
+
 impl From<sqlx::Error> for InfrastructureError {
     fn from(e: Error) -> Self {
         Self::DBConnectionsUnavailable(format!("{e}"))
@@ -133,6 +303,8 @@ pub enum InfrastructureError {
     ClientNotAvailable(#[from] HttpError),
     #[error("No DB connection seems available: {0}")]
     DBConnectionsUnavailable(String),
+    #[error("Local synthesizer cannot be run: {0}")]
+    SynthesizerUnavailable(String),
 }
 
 impl From<reqwest::Error> for InfrastructureError {
@@ -151,6 +323,44 @@ pub enum DataError {
     GrammarParses(Vec<ParseError>),
     #[error("Production is not well formed, {0:?}")]
     Production(#[from] ProductionError),
+    #[error("Grammar file is not well formed at line {0}: {1}")]
+    GrammarFile(usize, String),
+    #[error("Grammar file cannot be accessed, {0}")]
+    GrammarFileAccess(String),
+    #[error("Grammar storage failed, {0}")]
+    GrammarStorage(String),
+    #[error("Browsing failed, {0}")]
+    Browsing(String),
+    #[error("There is no language with code '{0}'")]
+    UnknownLanguage(String),
+    #[error("There is no voice {0}")]
+    UnknownVoice(String),
+    #[error("There is no phrase with id '{0}'")]
+    UnknownPhrase(String),
+    #[error("Candidates cannot be picked by weight, {0}")]
+    Weights(String),
+}
+
+#[derive(Error, Debug, Clone)]
+pub enum EditError {
+    #[error("DB Error, {0}.")]
+    DBFailed(String),
+    #[error("there is no {0} with id {1}")]
+    NotFound(&'static str, i32),
+    #[error("there is no {0} named '{1}'")]
+    UnknownName(&'static str, String),
+    #[error("{0} '{1}' already exists")]
+    AlreadyExisting(&'static str, String),
+    #[error("{0} cannot be empty")]
+    EmptyValue(&'static str),
+    #[error("{0} cannot be longer than {1} characters, '{2}' is")]
+    ValueTooLong(&'static str, usize, String),
+    #[error("{0} '{1}' can only contain letters, digits and underscores")]
+    InvalidName(&'static str, String),
+    #[error("weight must be greater than zero, got {0}")]
+    NonPositiveWeight(i32),
+    #[error("{0} must be between {1} and {2}, got {3}")]
+    OutOfRange(&'static str, f32, f32, f32),
 }
 
 #[derive(Error, Debug, Clone)]
@@ -183,4 +393,8 @@ pub enum ProductionError {
     IdClash(i32),
     #[error("a dependency cycle has been detected with a walk through the following ids: {0:?}")]
     CycleDetected(Vec<i32>),
+    #[error("placeholder {0} depends on missing placeholder {1}")]
+    UnknownPlaceholder(i32, i32),
+    #[error("unknown non-terminal symbol '{0}'")]
+    UnknownNonTerminalSymbol(String),
 }
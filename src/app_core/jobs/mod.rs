@@ -0,0 +1,250 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use actix_web::rt::time::sleep;
+use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use sqlx::postgres::PgListener;
+use sqlx::types::Uuid;
+use sqlx::{FromRow, Pool, Postgres};
+
+use crate::app_core::editing::voices::VoiceChoice;
+use crate::app_core::errors::AppError;
+use crate::app_core::{AppCore, AppResult};
+use crate::settings::JobsSection;
+
+#[cfg(test)]
+#[path = "./unit_tests/mod.rs"]
+mod tests;
+
+/// Where a job stands; running jobs are still pending.
+#[derive(sqlx::Type, Clone, Copy, Debug, PartialEq, Eq)]
+#[sqlx(type_name = "speech_job_status")]
+#[sqlx(rename_all = "lowercase")]
+pub enum JobStatus {
+    Pending,
+    Done,
+    Failed,
+}
+
+/// A request to synthesize a stored phrase in a voice of the catalogue. `url` is set once done, `error`
+/// once failed.
+#[derive(FromRow, Clone, Debug, PartialEq)]
+pub struct SpeechJob {
+    pub id: Uuid,
+    pub phrase: Uuid,
+    pub voice: i32,
+    pub status: JobStatus,
+    pub url: Option<String>,
+    pub error: Option<String>,
+}
+
+/// A job picked up by a worker, along with the text to synthesize.
+#[derive(FromRow)]
+struct ClaimedJob {
+    id: Uuid,
+    phrase: Uuid,
+    voice: i32,
+    text: String,
+    /// When the job was picked up, in microseconds: only this claim can finish the job.
+    started_at: i64,
+}
+
+const JOB_COLUMNS: &str = "id, generated_phrase AS phrase, voice, status, url, error";
+
+/// `started_at` in microseconds, compared as is to tell claims apart.
+const STARTED_AT_MICROS: &str = "(extract(epoch FROM started_at) * 1000000)::bigint";
+
+/// Postgres channel the ids of finished jobs are notified on, whichever server finished them.
+const FINISHED_CHANNEL: &str = "speech_job_finished";
+
+/// How speech jobs are processed.
+#[derive(Clone, Copy)]
+pub struct JobSettings {
+    /// Jobs processed at the same time.
+    pub workers: usize,
+    /// How often idle workers look for new jobs.
+    pub poll_interval: Duration,
+    /// Jobs picked up longer ago are given to another worker, their own having likely died with its server.
+    pub stale_after: Duration,
+}
+
+impl From<&JobsSection> for JobSettings {
+    fn from(settings: &JobsSection) -> Self {
+        Self {
+            workers: settings.workers,
+            poll_interval: Duration::from_millis(settings.poll_interval_ms),
+            stale_after: Duration::from_secs(settings.stale_after_secs),
+        }
+    }
+}
+
+/// The queue of speech jobs, kept in the database so that it survives restarts and is shared by every
+/// server.
+pub struct SpeechJobs<'p> {
+    pool: &'p Pool<Postgres>,
+}
+
+impl<'p> SpeechJobs<'p> {
+    pub fn new(pool: &'p Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    /// Queues the synthesis of `phrase` in `voice`, or returns the pending job already doing it.
+    pub async fn enqueue(&self, phrase: Uuid, voice: i32) -> AppResult<SpeechJob> {
+        sqlx::query_as::<Postgres, SpeechJob>(&format!(
+            "INSERT INTO speech_job (generated_phrase, voice) VALUES ($1, $2) ON CONFLICT (generated_phrase, voice) WHERE status = 'pending' DO UPDATE SET voice = EXCLUDED.voice RETURNING {JOB_COLUMNS}"
+        ))
+        .bind(phrase)
+        .bind(voice)
+        .fetch_one(self.pool)
+        .await
+        .map_err(AppError::for_upload_in_sql)
+    }
+
+    pub async fn job(&self, id: Uuid) -> AppResult<Option<SpeechJob>> {
+        sqlx::query_as::<Postgres, SpeechJob>(&format!(
+            "SELECT {JOB_COLUMNS} FROM speech_job WHERE id = $1"
+        ))
+        .bind(id)
+        .fetch_optional(self.pool)
+        .await
+        .map_err(AppError::for_browsing_in_sql)
+    }
+
+    /// Picks the oldest pending job nobody is working on. Concurrent workers skip each other's rows instead
+    /// of waiting for them.
+    async fn claim(&self, stale_after: Duration) -> AppResult<Option<ClaimedJob>> {
+        sqlx::query_as::<Postgres, ClaimedJob>(&format!(
+            "UPDATE speech_job j SET started_at = now() FROM generated_phrase p WHERE p.id = j.generated_phrase AND j.id = (SELECT id FROM speech_job WHERE status = 'pending' AND (started_at IS NULL OR started_at < now() - make_interval(secs => $1)) ORDER BY created_at FOR UPDATE SKIP LOCKED LIMIT 1) RETURNING j.id, j.generated_phrase AS phrase, j.voice, p.content AS text, {STARTED_AT_MICROS} AS started_at",
+        ))
+        .bind(stale_after.as_secs_f64())
+        .fetch_optional(self.pool)
+        .await
+        .map_err(AppError::for_upload_in_sql)
+    }
+
+    /// Does nothing and returns `false` when the job was handed to another worker since it was claimed,
+    /// so that a late result never overwrites the one of the new claim.
+    async fn finish(&self, job: &ClaimedJob, result: &AppResult<String>) -> AppResult<bool> {
+        let (status, url, error) = outcome(result);
+
+        sqlx::query(&format!(
+            "WITH finished AS (UPDATE speech_job SET status = $2, url = $3, error = $4, finished_at = now() WHERE id = $1 AND status = 'pending' AND {STARTED_AT_MICROS} = $5 RETURNING id) SELECT pg_notify('{FINISHED_CHANNEL}', id::text) FROM finished"
+        ))
+        .bind(job.id)
+        .bind(status)
+        .bind(url)
+        .bind(error)
+        .bind(job.started_at)
+        .execute(self.pool)
+        .await
+        .map(|done| done.rows_affected() > 0)
+        .map_err(AppError::for_upload_in_sql)
+    }
+}
+
+/// The status, url and error a job is finished with.
+fn outcome(result: &AppResult<String>) -> (JobStatus, Option<String>, Option<String>) {
+    match result {
+        Ok(url) => (JobStatus::Done, Some(url.clone()), None),
+        Err(error) => (JobStatus::Failed, None, Some(error.to_string())),
+    }
+}
+
+/// Hands the jobs finished by any server to the subscribers of this one.
+#[derive(Default)]
+pub struct FinishedJobs {
+    subscribers: Mutex<Vec<UnboundedSender<SpeechJob>>>,
+}
+
+impl FinishedJobs {
+    /// Every job finishing from now on, until the receiver is dropped.
+    pub fn subscribe(&self) -> UnboundedReceiver<SpeechJob> {
+        let (sender, receiver) = unbounded();
+        self.subscribers.lock().unwrap().push(sender);
+        receiver
+    }
+
+    fn publish(&self, job: SpeechJob) {
+        self.subscribers
+            .lock()
+            .unwrap()
+            .retain(|subscriber| subscriber.unbounded_send(job.clone()).is_ok());
+    }
+}
+
+/// Listens for finished jobs on a connection of its own, publishing them to [`AppCore::finished_jobs`].
+pub fn spawn_listener(core: Arc<AppCore>) {
+    actix_web::rt::spawn(async move {
+        loop {
+            if let Err(error) = listen(&core).await {
+                tracing::error!("Cannot listen for finished speech jobs: {error}");
+                sleep(Duration::from_secs(1)).await;
+            }
+        }
+    });
+}
+
+/// Only returns on failure; lost connections are reopened by the listener itself.
+async fn listen(core: &AppCore) -> Result<(), sqlx::Error> {
+    let mut listener = PgListener::connect_with(core.pool()).await?;
+    listener.listen(FINISHED_CHANNEL).await?;
+
+    loop {
+        let notification = listener.recv().await?;
+        let job = match Uuid::parse_str(notification.payload()) {
+            Ok(id) => core.jobs().job(id).await,
+            Err(_) => continue,
+        };
+        match job {
+            Ok(Some(job)) => core.finished_jobs().publish(job),
+            Ok(None) => {}
+            Err(error) => tracing::warn!("Cannot read finished speech job: {error}"),
+        }
+    }
+}
+
+/// Starts `settings.workers` workers on the current runtime, processing jobs until the server stops.
+pub fn spawn_workers(core: Arc<AppCore>, settings: JobSettings) {
+    for worker in 0..settings.workers {
+        actix_web::rt::spawn(work(core.clone(), settings, worker));
+    }
+}
+
+async fn work(core: Arc<AppCore>, settings: JobSettings, worker: usize) {
+    loop {
+        match core.jobs().claim(settings.stale_after).await {
+            Ok(Some(job)) => {
+                let result = process(&core, &job).await;
+                if let Err(error) = &result {
+                    tracing::warn!("Speech job {} failed: {error}", job.id);
+                }
+                match core.jobs().finish(&job, &result).await {
+                    Ok(true) => {}
+                    Ok(false) => tracing::warn!(
+                        "Worker {worker} gave speech job {} up, it was handed to another worker",
+                        job.id
+                    ),
+                    Err(error) => tracing::error!(
+                        "Worker {worker} cannot finish speech job {}: {error}",
+                        job.id
+                    ),
+                }
+            }
+            Ok(None) => sleep(settings.poll_interval).await,
+            Err(error) => {
+                tracing::error!("Worker {worker} cannot look for speech jobs: {error}");
+                sleep(settings.poll_interval).await;
+            }
+        }
+    }
+}
+
+async fn process(core: &AppCore, job: &ClaimedJob) -> AppResult<String> {
+    let voice = core
+        .voices()
+        .choose(&VoiceChoice::Id(job.voice), core.uploader().provider())
+        .await?;
+
+    core.speech_url(job.phrase, &voice, &job.text).await
+}
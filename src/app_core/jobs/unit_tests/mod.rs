@@ -0,0 +1,93 @@
+use std::time::Duration;
+
+use sqlx::types::Uuid;
+
+use crate::app_core::errors::AppError;
+use crate::app_core::jobs::{outcome, JobSettings, JobStatus, SpeechJob, JOB_COLUMNS};
+use crate::served::types::jobs::{self as served, AudioJob};
+use crate::settings::JobsSection;
+
+fn job(status: JobStatus, url: Option<&str>, error: Option<&str>) -> SpeechJob {
+    SpeechJob {
+        id: Uuid::from_u128(1),
+        phrase: Uuid::from_u128(2),
+        voice: 3,
+        status,
+        url: url.map(String::from),
+        error: error.map(String::from),
+    }
+}
+
+#[test]
+fn synthesized_jobs_are_done_with_their_url() {
+    assert_eq!(
+        outcome(&Ok("https://audio/1.mp3".to_string())),
+        (
+            JobStatus::Done,
+            Some("https://audio/1.mp3".to_string()),
+            None
+        )
+    );
+}
+
+#[test]
+fn failed_jobs_keep_the_error() {
+    let error = AppError::for_unknown_phrase("nowhere".to_string());
+    let message = error.to_string();
+
+    assert_eq!(
+        outcome(&Err(error)),
+        (JobStatus::Failed, None, Some(message))
+    );
+}
+
+#[test]
+fn statuses_are_served_as_is() {
+    for (status, served) in [
+        (JobStatus::Pending, served::JobStatus::Pending),
+        (JobStatus::Done, served::JobStatus::Done),
+        (JobStatus::Failed, served::JobStatus::Failed),
+    ] {
+        assert!(served::JobStatus::from(status) == served);
+    }
+}
+
+#[test]
+fn job_columns_are_named_after_the_fields() {
+    let names: Vec<_> = JOB_COLUMNS
+        .split(',')
+        .map(|column| column.rsplit(' ').next().unwrap())
+        .collect();
+
+    assert_eq!(names, ["id", "phrase", "voice", "status", "url", "error"]);
+}
+
+#[test]
+fn jobs_are_served_with_every_field() {
+    let served = AudioJob::from(job(JobStatus::Failed, None, Some("too long")));
+
+    assert_eq!(served.id, Uuid::from_u128(1).to_string());
+    assert_eq!(served.phrase_id, Uuid::from_u128(2).to_string());
+    assert_eq!(served.voice, 3);
+    assert!(served.status == served::JobStatus::Failed);
+    assert_eq!(served.url, None);
+    assert_eq!(served.error.as_deref(), Some("too long"));
+
+    let served = AudioJob::from(job(JobStatus::Done, Some("https://audio/1.mp3"), None));
+    assert!(served.status == served::JobStatus::Done);
+    assert_eq!(served.url.as_deref(), Some("https://audio/1.mp3"));
+    assert_eq!(served.error, None);
+}
+
+#[test]
+fn settings_are_converted_to_durations() {
+    let settings = JobSettings::from(&JobsSection {
+        workers: 4,
+        poll_interval_ms: 250,
+        stale_after_secs: 90,
+    });
+
+    assert_eq!(settings.workers, 4);
+    assert_eq!(settings.poll_interval, Duration::from_millis(250));
+    assert_eq!(settings.stale_after, Duration::from_secs(90));
+}
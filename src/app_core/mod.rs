@@ -1,31 +1,156 @@
-use crate::app_core::types::upload::UploadedSpeech;
-use std::collections::{HashMap, HashSet};
-use std::hash::Hash;
-use std::str::FromStr;
+use crate::app_core::types::upload::{SpeechVoice, UploadedSpeech};
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use crate::outgoing::tts_wrapper::TtsWrapper;
+use crate::outgoing::tts::breaker::CircuitState;
+use crate::outgoing::tts::AppTtsProvider;
 use crate::served::types::graphql::Speech;
-use async_recursion::async_recursion;
 use async_trait::async_trait;
-use itertools::Itertools;
-use rand::RngCore;
-use sqlx::{FromRow, Transaction};
-use sqlx::{Pool, Postgres};
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+use sqlx::types::Uuid;
+use sqlx::{Acquire, Pool, Postgres};
 
-use self::errors::AppError;
+use self::errors::{AppError, GenerationError};
 
+pub mod audio;
+pub mod editing;
 pub mod engine;
 pub mod errors;
+pub mod jobs;
+pub mod phrases;
 pub mod types;
-use crate::app_core::engine::types::parsing::TokenReference;
-use crate::app_core::engine::types::{PlaceholderReference, ProductionBranch};
-use crate::app_core::errors::GenerationError;
+
+#[cfg(test)]
+#[path = "./unit_tests/mod.rs"]
+mod tests;
+
+use crate::app_core::audio::{AudioArchive, AudioKey};
+use crate::app_core::editing::grammar::Grammar;
+use crate::app_core::editing::lexicon::Lexicon;
+use crate::app_core::editing::voices::{CatalogueVoice, Voices};
+use crate::app_core::engine::sources::{
+    Category, GrammarSource, PostgresGrammarSource, DEFAULT_LANGUAGE,
+};
+use crate::app_core::engine::validation::{validate_grammar, GrammarReport};
+use crate::app_core::engine::{generate_phrase, resolve_category, GenerationLimits};
+use crate::app_core::jobs::{FinishedJobs, SpeechJobs};
+use crate::app_core::phrases::Phrases;
+use crate::settings::{GenerationSection, Settings};
 use crate::utils::{LogLevel, Loggable};
 
 pub type AppResult<T> = Result<T, AppError>;
 
-pub struct SpeechGenerationOptions {}
+/// Seeds are kept within 2^53 so that JavaScript clients can round-trip them without losing digits.
+pub const MAX_GENERATION_SEED: u64 = (1 << 53) - 1;
+
+/// Most phrases a single batch can hold.
+pub const MAX_BATCH_SIZE: usize = 100;
+/// Seeds tried for each phrase of a distinct batch before giving up on it.
+pub const MAX_DISTINCT_ATTEMPTS: u32 = 10;
+
+/// Pool size past which the probabilistic strategy only reuses phrases.
+pub const DEFAULT_MAX_PHRASES: u64 = 2048;
+
+/// How a generation chooses between a brand new phrase and one already in the pool.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GenerationStrategy {
+    /// Always generates, storing the phrase in the pool unless already there.
+    Fresh,
+    /// Always picks a stored phrase, failing when there is none.
+    Pool,
+    /// Generates with probability `1 - stored / max_phrases`, reuses otherwise.
+    Probabilistic { max_phrases: u64 },
+}
+
+impl GenerationStrategy {
+    /// A seed only reproduces generated phrases: given one, generation is always fresh, and picking from the
+    /// pool is refused instead of ignoring the seed.
+    pub fn seeded(self, seed: Option<u64>) -> AppResult<Self> {
+        match (self, seed) {
+            (_, None) => Ok(self),
+            (Self::Pool, Some(_)) => Err(AppError::for_generation_seeded_pool()),
+            (_, Some(_)) => Ok(Self::Fresh),
+        }
+    }
+}
+
+impl Default for GenerationStrategy {
+    fn default() -> Self {
+        Self::Probabilistic {
+            max_phrases: DEFAULT_MAX_PHRASES,
+        }
+    }
+}
+
+/// Whether a phrase comes out of the generation or out of the pool.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PhraseOrigin {
+    Generated,
+    Reused,
+}
+
+#[derive(Clone)]
+pub struct SpeechGenerationOptions {
+    pub category: Option<String>,
+    /// Code of the language to generate in, [`DEFAULT_LANGUAGE`] when none.
+    pub language: Option<String>,
+    pub seed: Option<u64>,
+    pub strategy: GenerationStrategy,
+}
+
+pub struct BatchGenerationOptions {
+    pub category: Option<String>,
+    /// Code of the language to generate in, [`DEFAULT_LANGUAGE`] when none.
+    pub language: Option<String>,
+    pub strategy: GenerationStrategy,
+    /// Seeds every phrase of the batch, which can be reproduced as a whole.
+    pub seed: Option<u64>,
+    /// Whether a phrase already in the batch must be replaced by a different one.
+    pub distinct: bool,
+}
+
+/// A phrase of a batch, or why it could not be produced; `seed` is the last one tried.
+pub struct BatchItem {
+    pub seed: u64,
+    pub result: AppResult<Speech>,
+}
+
+/// Outcome of checking a dependency, and how long the check took.
+pub struct DependencyHealth {
+    pub result: AppResult<()>,
+    pub latency: Duration,
+}
+
+impl DependencyHealth {
+    async fn check(check: impl Future<Output = AppResult<()>>) -> Self {
+        let start = Instant::now();
+        let result = check.await;
+        Self {
+            result,
+            latency: start.elapsed(),
+        }
+    }
+}
+
+/// Health of the dependencies of the server: the database behind the generator and the TTS provider.
+pub struct HealthReport {
+    pub db: DependencyHealth,
+    pub tts: DependencyHealth,
+}
+
+impl HealthReport {
+    pub fn is_healthy(&self) -> bool {
+        self.db.result.is_ok() && self.tts.result.is_ok()
+    }
+}
+
+fn random_seed() -> u64 {
+    rand::thread_rng().gen_range(0..=MAX_GENERATION_SEED)
+}
 
 #[async_trait]
 pub trait AsyncHealth {
@@ -34,6 +159,12 @@ pub trait AsyncHealth {
 
 #[async_trait]
 pub trait AsyncUploader {
+    /// Name of the TTS provider in the voice catalogue.
+    fn provider(&self) -> &str;
+
+    /// State of the circuit breaker guarding the TTS provider, when it has one.
+    fn circuit(&self) -> Option<CircuitState>;
+
     async fn upload(
         &self,
         request: crate::app_core::types::upload::Speech,
@@ -43,6 +174,15 @@ pub trait AsyncUploader {
 #[async_trait]
 pub trait AsyncPhraseGenerator {
     async fn generate(&self, options: SpeechGenerationOptions) -> AppResult<Speech>;
+    /// Generates up to [`MAX_BATCH_SIZE`] phrases in a single transaction. Failing phrases are reported
+    /// in their item, only database failures fail the whole batch.
+    async fn generate_batch(
+        &self,
+        count: usize,
+        options: BatchGenerationOptions,
+    ) -> AppResult<Vec<BatchItem>>;
+    async fn categories(&self) -> AppResult<Vec<Category>>;
+    async fn validate_grammar(&self, language: &str) -> AppResult<GrammarReport>;
 }
 
 #[async_trait]
@@ -54,10 +194,48 @@ pub trait AsyncHealthyPhraseGenerator: AsyncPhraseGenerator + AsyncHealth {}
 type AppUploader = dyn AsyncHealthyUploader + Send + Sync;
 type AppPhraseGenerator = dyn AsyncHealthyPhraseGenerator + Send + Sync;
 
+/// Caps the `phrases` streams open at once on this server, each one storing a new phrase every interval.
+pub struct PhraseStreams {
+    open: Arc<AtomicUsize>,
+    max: usize,
+}
+
+/// One of the [`PhraseStreams`], given back once dropped along with its stream.
+pub struct PhraseStreamSlot(Arc<AtomicUsize>);
+
+impl PhraseStreams {
+    pub fn new(max: usize) -> Self {
+        Self {
+            open: Arc::default(),
+            max,
+        }
+    }
+
+    /// Fails when `max` streams are already open.
+    pub fn open(&self) -> AppResult<PhraseStreamSlot> {
+        self.open
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |open| {
+                Some(open + 1).filter(|open| *open <= self.max)
+            })
+            .map(|_| PhraseStreamSlot(self.open.clone()))
+            .map_err(|_| AppError::for_generation_too_many_streams(self.max))
+    }
+}
+
+impl Drop for PhraseStreamSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
 pub struct AppCore {
     uploader: Arc<AppUploader>,
     generator: Arc<AppPhraseGenerator>,
     pool: Arc<Pool<Postgres>>,
+    audio: AudioArchive,
+    finished_jobs: FinishedJobs,
+    phrase_streams: PhraseStreams,
+    settings: Settings,
 }
 
 impl AppCore {
@@ -65,14 +243,25 @@ impl AppCore {
         uploader: Arc<AppUploader>,
         generator: Arc<AppPhraseGenerator>,
         pool: Arc<Pool<Postgres>>,
+        audio: AudioArchive,
+        settings: Settings,
     ) -> Self {
         Self {
             uploader,
             generator,
             pool,
+            audio,
+            finished_jobs: FinishedJobs::default(),
+            phrase_streams: PhraseStreams::new(settings.generation.max_streams),
+            settings,
         }
     }
 
+    /// The settings the server was started with.
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
     pub fn uploader(&self) -> &AppUploader {
         self.uploader.as_ref()
     }
@@ -85,43 +274,228 @@ impl AppCore {
         self.pool.as_ref()
     }
 
-    pub async fn is_healthy(&self) -> AppResult<()> {
-        let (generator_res, uploader_res) =
-            futures::join!(self.generator().is_healthy(), self.uploader().is_healthy());
+    pub fn lexicon(&self) -> Lexicon<'_> {
+        Lexicon::new(self.pool())
+    }
 
-        if generator_res.is_err() {
-            generator_res
-        } else {
-            uploader_res
+    pub fn grammar(&self) -> Grammar<'_> {
+        Grammar::new(self.pool())
+    }
+
+    pub fn audio(&self) -> &AudioArchive {
+        &self.audio
+    }
+
+    pub fn phrases(&self) -> Phrases<'_> {
+        Phrases::new(self.pool(), self.audio())
+    }
+
+    pub fn voices(&self) -> Voices<'_> {
+        Voices::new(self.pool())
+    }
+
+    pub fn jobs(&self) -> SpeechJobs<'_> {
+        SpeechJobs::new(self.pool())
+    }
+
+    /// Jobs finished by any server, see [`jobs::spawn_listener`].
+    pub fn finished_jobs(&self) -> &FinishedJobs {
+        &self.finished_jobs
+    }
+
+    /// The `phrases` subscriptions of this server, see [`PhraseStreams`].
+    pub fn phrase_streams(&self) -> &PhraseStreams {
+        &self.phrase_streams
+    }
+
+    /// The URL of the speech of `phrase` spoken by `voice`, when already synthesized; nothing gets synthesized
+    /// nor stored.
+    pub async fn stored_speech_url(
+        &self,
+        phrase: Uuid,
+        voice: &CatalogueVoice,
+    ) -> AppResult<Option<String>> {
+        let key = &AudioKey {
+            phrase,
+            voice: voice.id,
+        };
+
+        Ok(self
+            .stored_speech(key)
+            .await?
+            .map(|(url, has_content_type, in_database)| {
+                if self.audio.is_archived(key, has_content_type, in_database) {
+                    self.audio.served_url(key)
+                } else {
+                    url
+                }
+            }))
+    }
+
+    /// The provider URL of a stored speech, and whether its audio is kept in a file or in the database.
+    async fn stored_speech(&self, key: &AudioKey) -> AppResult<Option<(String, bool, bool)>> {
+        sqlx::query_as::<Postgres, (String, bool, bool)>(
+            "SELECT url, content_type IS NOT NULL, audio IS NOT NULL FROM generated_phrase_speech WHERE generated_phrase = $1 AND voice = $2",
+        )
+        .bind(key.phrase)
+        .bind(key.voice)
+        .fetch_optional(self.pool())
+        .await
+        .map_err(AppError::for_upload_in_sql)
+    }
+
+    /// The URL of `text` spoken by `voice`, synthesized on the first request only. Audio kept by this server
+    /// is linked through its `/audio` route, the provider URL is used otherwise.
+    pub async fn speech_url(
+        &self,
+        phrase: Uuid,
+        voice: &CatalogueVoice,
+        text: &str,
+    ) -> AppResult<String> {
+        let key = &AudioKey {
+            phrase,
+            voice: voice.id,
+        };
+
+        let stored = self.stored_speech(key).await?;
+
+        // Synthesis may be slow, so no transaction is held meanwhile
+        let url = match stored {
+            Some((_, has_content_type, in_database))
+                if self.audio.is_archived(key, has_content_type, in_database) =>
+            {
+                return Ok(self.audio.served_url(key));
+            }
+            Some((url, _, _)) => url,
+            // Audio of other providers is still served, but only the provider in use can synthesize
+            None if voice.provider != self.uploader().provider() => {
+                return Err(AppError::for_upload_foreign_voice(
+                    voice.id,
+                    voice.provider.clone(),
+                ));
+            }
+            None => self
+                .uploader()
+                .upload(crate::app_core::types::upload::Speech {
+                    text: text.to_owned(),
+                    voice: SpeechVoice {
+                        name: voice.name.clone(),
+                        locale: voice.locale.clone(),
+                        is_male: voice.gender == "male",
+                        rate: voice.rate,
+                        pitch: voice.pitch,
+                    },
+                })
+                .await
+                .map(|res| res.url.to_string())?,
+        };
+
+        // Audio synthesized before a storage was set up is kept the first time it is asked for again.
+        // Failing to keep it is not fatal: the provider URL still works, and next time will try again.
+        // Downloading is done before the transaction, a slow provider must not hold the row lock
+        let copied = self.audio.copy(key, &url).await.unwrap_or_else(|error| {
+            tracing::warn!("Unable to store the audio of {}: {error}", key.phrase);
+            None
+        });
+
+        // When a concurrent request stored the same speech first, its row is kept and waited for
+        let mut transaction = self
+            .pool()
+            .begin()
+            .await
+            .map_err(AppError::for_upload_in_sql)?;
+        let (url, has_content_type, in_database) = sqlx::query_as::<Postgres, (String, bool, bool)>(
+            "INSERT INTO generated_phrase_speech (generated_phrase, voice, url) VALUES ($1, $2, $3) ON CONFLICT (generated_phrase, voice) DO UPDATE SET url = generated_phrase_speech.url RETURNING url, content_type IS NOT NULL, audio IS NOT NULL",
+        )
+        .bind(key.phrase)
+        .bind(key.voice)
+        .bind(&url)
+        .fetch_one(&mut transaction)
+        .await
+        .map_err(AppError::for_upload_in_sql)?;
+        if self.audio.is_archived(key, has_content_type, in_database) {
+            return Ok(self.audio.served_url(key));
         }
+
+        let is_archived = match copied {
+            Some(audio) => {
+                let mut savepoint = transaction
+                    .begin()
+                    .await
+                    .map_err(AppError::for_upload_in_sql)?;
+                match self.audio.archive(&mut savepoint, key, audio).await {
+                    Ok(()) => {
+                        savepoint
+                            .commit()
+                            .await
+                            .map_err(AppError::for_upload_in_sql)?;
+                        true
+                    }
+                    Err(error) => {
+                        tracing::warn!("Unable to store the audio of {}: {error}", key.phrase);
+                        false
+                    }
+                }
+            }
+            None => false,
+        };
+
+        transaction
+            .commit()
+            .await
+            .map_err(AppError::for_upload_in_sql)?;
+
+        Ok(if is_archived {
+            self.audio.served_url(key)
+        } else {
+            url
+        })
+    }
+
+    /// Checks the dependencies concurrently.
+    pub async fn health(&self) -> HealthReport {
+        let (db, tts) = futures::join!(
+            DependencyHealth::check(self.generator().is_healthy()),
+            DependencyHealth::check(self.uploader().is_healthy())
+        );
+
+        HealthReport { db, tts }
     }
 }
 
 #[derive(Clone)]
 pub struct Uploader {
-    wrapper: Arc<TtsWrapper>,
+    provider: Arc<AppTtsProvider>,
 }
 
 impl Uploader {
-    pub fn new(wrapper: Arc<TtsWrapper>) -> Self {
-        Self { wrapper }
+    pub fn new(provider: Arc<AppTtsProvider>) -> Self {
+        Self { provider }
     }
 }
 
 #[async_trait]
 impl AsyncUploader for Uploader {
+    fn provider(&self) -> &str {
+        self.provider.name()
+    }
+
+    fn circuit(&self) -> Option<CircuitState> {
+        self.provider.circuit()
+    }
+
     async fn upload(
         &self,
         request: crate::app_core::types::upload::Speech,
     ) -> AppResult<UploadedSpeech> {
-        self.wrapper.upload(request.into()).await.map(Into::into)
+        self.provider.upload(request.into()).await.map(Into::into)
     }
 }
 
 #[async_trait]
 impl AsyncHealth for Uploader {
     async fn is_healthy(&self) -> AppResult<()> {
-        self.wrapper.health().await
+        self.provider.health().await
     }
 }
 
@@ -130,608 +504,280 @@ impl AsyncHealthyUploader for Uploader {}
 
 pub struct PhraseGenerator {
     pool: Arc<Pool<Postgres>>,
+    limits: GenerationLimits,
 }
 
 impl PhraseGenerator {
-    pub fn new(pool: Arc<Pool<Postgres>>) -> Self {
-        Self { pool }
+    pub fn new(pool: Arc<Pool<Postgres>>, settings: &GenerationSection) -> Self {
+        Self {
+            pool,
+            limits: settings.into(),
+        }
     }
 }
 
-#[async_trait]
-impl AsyncPhraseGenerator for PhraseGenerator {
-    // WHAT IS THIS SMOKING PILE OF SPAGHETT'
-    async fn generate(&self, opts: SpeechGenerationOptions) -> AppResult<Speech> {
-        let max_phrases = 2048u64; //TODO: hardcoded
-
-        let mut transaction = self
-            .pool
-            .begin()
-            .await
-            .map_err(AppError::for_generation_in_sql)?;
+/// The share of the phrase pool a generation draws from: phrases are pooled by category and language.
+struct PoolShare<'c> {
+    category: Option<&'c str>,
+    language: String,
+    total: i64,
+}
 
-        let current_total = sqlx::query_scalar::<_, i64>("SELECT COUNT(id) FROM generated_phrase")
-            .fetch_one(&mut transaction)
-            .await
-            .map_err(AppError::for_generation_in_sql)?;
+impl<'c> PoolShare<'c> {
+    async fn count(
+        source: &mut PostgresGrammarSource<'_, '_>,
+        category: Option<&'c str>,
+    ) -> AppResult<PoolShare<'c>> {
+        let language = source.language().to_owned();
+        let total = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(id) FROM generated_phrase WHERE category IS NOT DISTINCT FROM (SELECT id FROM category WHERE name = $1) AND language = (SELECT id FROM language WHERE code = $2)",
+        )
+        .bind(category)
+        .bind(&language)
+        .fetch_one(source.transaction())
+        .await
+        .map_err(AppError::for_generation_in_sql)?;
 
-        let remaining = (max_phrases as i64 - current_total).max(0);
+        Ok(Self {
+            category,
+            language,
+            total,
+        })
+    }
+}
 
-        let result = if rand::thread_rng().next_u64() % max_phrases < (remaining as u64) {
-            tracing::info!("Generating new phrase, remaining {remaining}");
-            //TODO: add some retry, generation can fail
-            let s = generate_phrase(opts, &mut transaction).await?;
+impl PhraseGenerator {
+    /// Either generates a new phrase and stores it in the pool, or extracts one already there,
+    /// as `strategy` tells.
+    async fn produce(
+        &self,
+        source: &mut PostgresGrammarSource<'_, '_>,
+        share: &mut PoolShare<'_>,
+        category: &Category,
+        strategy: GenerationStrategy,
+        seed: u64,
+    ) -> AppResult<Speech> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let is_fresh = match strategy {
+            GenerationStrategy::Fresh => true,
+            GenerationStrategy::Pool => false,
+            // The emptier the pool, the likelier a new phrase
+            GenerationStrategy::Probabilistic { max_phrases } => {
+                let max_phrases = max_phrases.max(1);
+                let remaining = (max_phrases as i64 - share.total).max(0);
+                rng.next_u64() % max_phrases < (remaining as u64)
+            }
+        };
+
+        if is_fresh {
+            tracing::info!("Generating new phrase with seed {seed}, pool has {}", share.total);
+            // A dedicated stream: the same seed yields the same phrase regardless of the pool state
+            let generated = generate_phrase(
+                category,
+                &self.limits,
+                source,
+                &mut StdRng::seed_from_u64(seed),
+            )
+            .await?;
+            let (s, backtracks, derivation) = (
+                generated.text,
+                Some(generated.backtracks),
+                Some(generated.derivation),
+            );
             if let Some(id) = sqlx::query_scalar::<_, sqlx::types::Uuid>(
-                "SELECT id FROM generated_phrase WHERE content = $1",
+                "SELECT id FROM generated_phrase WHERE content = $1 AND language = (SELECT id FROM language WHERE code = $2)",
             )
             .bind(&s)
-            .fetch_optional(&mut transaction)
+            .bind(&share.language)
+            .fetch_optional(source.transaction())
             .await
             .map_err(AppError::for_generation_in_sql)?
             {
-                Ok((id, s))
+                Ok((id, s, backtracks, derivation))
             } else {
+                share.total += 1;
+                // Concurrent generations may store the same phrase first: theirs is kept
                 sqlx::query!(
-                    "INSERT INTO generated_phrase (content) VALUES ($1) RETURNING id",
-                    &s
+                    "INSERT INTO generated_phrase (content, category, language) VALUES ($1, (SELECT id FROM category WHERE name = $2), (SELECT id FROM language WHERE code = $3)) ON CONFLICT (language, content) DO UPDATE SET content = EXCLUDED.content RETURNING id",
+                    &s,
+                    share.category as _,
+                    &share.language
                 )
-                .fetch_one(&mut transaction)
+                .fetch_one(source.transaction())
                 .await
                 .map(|res| res.id)
                 .map_err(AppError::for_generation_in_sql)
-                .map(|uuid| (uuid, s))
+                .map(|uuid| (uuid, s, backtracks, derivation))
             }
+        } else if share.total == 0 {
+            Err(AppError::for_generation_empty_pool(share.category.map_or_else(
+                || "uncategorised phrases".to_owned(),
+                |category| format!("phrases of category '{category}'"),
+            )))
         } else {
-            tracing::info!("Extracting existing phrase, remaining {remaining}");
+            tracing::info!("Extracting existing phrase with seed {seed}, pool has {}", share.total);
 
             sqlx::query_as::<_, (sqlx::types::Uuid, String)>(
-                "SELECT id, content FROM generated_phrase ORDER BY random() limit 1",
+                "SELECT id, content FROM generated_phrase WHERE category IS NOT DISTINCT FROM (SELECT id FROM category WHERE name = $1) AND language = (SELECT id FROM language WHERE code = $2) ORDER BY id LIMIT 1 OFFSET $3",
             )
-            .fetch_one(&mut transaction)
+            .bind(share.category)
+            .bind(&share.language)
+            .bind(rng.gen_range(0..share.total))
+            .fetch_one(source.transaction())
             .await
             .map_err(AppError::for_generation_in_sql)
+            .map(|(uuid, text)| (uuid, text, None, None))
         }
-        .map(|(uuid, text)| Speech {
+        .map(|(uuid, text, backtracks, derivation)| Speech {
             id: uuid.to_string(),
             text,
-        })?;
-
-        transaction
-            .commit()
-            .await
-            .map_err(AppError::for_generation_in_sql)?;
-
-        Ok(result)
-    }
-}
-
-#[async_trait]
-impl AsyncHealth for PhraseGenerator {
-    async fn is_healthy(&self) -> AppResult<()> {
-        self.pool
-            .acquire()
-            .await
-            .map(|_| ())
-            .map_err(AppError::for_infrastructure_db_connections_unavailable)
-            .log_err("DB is not healthy", LogLevel::Warning)
+            language: share.language.clone(),
+            // Sending the seed back generates, which would not give a reused phrase again
+            seed: Some(seed).filter(|_| is_fresh),
+            origin: if is_fresh {
+                PhraseOrigin::Generated
+            } else {
+                PhraseOrigin::Reused
+            },
+            backtracks,
+            derivation,
+        })
     }
 }
 
 #[async_trait]
-impl AsyncHealthyPhraseGenerator for PhraseGenerator {}
-
-type TrivialGenerationSubStep = GenerationSubStep<i32, i32, i32>;
-type TrivialGenerationState = dyn GenerationState<i32, i32, i32, i32>;
-
-#[derive(Clone)]
-pub struct GenerationSubStep<
-    Placeholder: Sized + Hash + Send + Clone + Eq,
-    Semantics: Sized + Hash + Send + Clone + Eq,
-    Grammar: Sized + Hash + Send + Clone + Eq,
-> {
-    grammar_context: HashSet<Grammar>,
-    semantic_context: HashSet<Semantics>,
-
-    grammar_lookup: HashMap<Placeholder, HashSet<Grammar>>,
-    semantics_lookup: HashMap<Placeholder, HashSet<Semantics>>,
-}
-
-impl<
-        Placeholder: Sized + Hash + Send + Clone + Eq,
-        Semantics: Sized + Hash + Send + Clone + Eq,
-        Grammar: Sized + Hash + Send + Clone + Eq,
-    > GenerationSubStep<Placeholder, Semantics, Grammar>
-{
-    pub fn new() -> Self {
-        Self {
-            grammar_context: HashSet::new(),
-            semantic_context: HashSet::new(),
-            grammar_lookup: HashMap::new(),
-            semantics_lookup: HashMap::new(),
-        }
-    }
-
-    pub fn propagate_grammar_tags(&mut self, tags: Vec<Grammar>) {
-        for tag in tags.into_iter() {
-            self.grammar_context.insert(tag);
-        }
-    }
-
-    pub fn propagate_semantic_tags(&mut self, tags: Vec<Semantics>) {
-        for tag in tags.into_iter() {
-            self.semantic_context.insert(tag);
-        }
-    }
-
-    pub fn register_grammar_tags(&mut self, placeholder: Placeholder, tags: Vec<Grammar>) -> bool {
-        self.grammar_lookup
-            .insert(placeholder, tags.into_iter().collect())
-            == None
-    }
-
-    pub fn register_semantic_tags(
-        &mut self,
-        placeholder: Placeholder,
-        tags: Vec<Semantics>,
-    ) -> bool {
-        self.semantics_lookup
-            .insert(placeholder, tags.into_iter().collect())
-            == None
-    }
-
-    pub fn get_grammar_of(&self, placeholder: &Placeholder) -> Option<Vec<&Grammar>> {
-        self.grammar_lookup
-            .get(placeholder)
-            .map(|grammar| grammar.iter().collect_vec())
-    }
-
-    pub fn get_semantics_of(&self, placeholder: &Placeholder) -> Option<Vec<&Semantics>> {
-        self.semantics_lookup
-            .get(placeholder)
-            .map(|grammar| grammar.iter().collect_vec())
-    }
-
-    pub fn deconstruct_context(&self) -> (Vec<&Semantics>, Vec<&Grammar>) {
-        (
-            self.semantic_context.iter().collect_vec(),
-            self.grammar_context.iter().collect_vec(),
-        )
-    }
-}
-
-trait GenerationState<
-    Word: Sized + Hash + Send + Clone + Eq,
-    Placeholder: Sized + Hash + Send + Clone + Eq,
-    Semantics: Sized + Hash + Send + Clone + Eq,
-    Grammar: Sized + Hash + Send + Clone + Eq,
->: Send
-{
-    fn begin_generation_sub_step(&mut self);
-    fn end_generation_sub_step(
-        &mut self,
-    ) -> Option<GenerationSubStep<Placeholder, Semantics, Grammar>>;
-
-    fn current_depth(&self) -> u16;
-    fn is_too_deep(&self) -> bool;
-
-    fn propagate_semantics(&mut self, semantics: Vec<Semantics>);
-    fn propagate_grammar(&mut self, grammar: Vec<Grammar>);
-    fn current_context(&self) -> (Vec<&Semantics>, Vec<&Grammar>);
-
-    fn register_semantics(&mut self, placeholder: Placeholder, semantics: Vec<Semantics>) -> bool;
-    fn register_grammar(&mut self, placeholder: Placeholder, grammar: Vec<Grammar>) -> bool;
-    fn extract_placeholder_semantics(&self, placeholder: &Placeholder) -> Option<Vec<&Semantics>>;
-    fn extract_placeholder_grammar(&self, placeholder: &Placeholder) -> Option<Vec<&Grammar>>;
-
-    fn alter_length(&mut self, amount: i32);
-    fn is_too_long(&self) -> bool;
-
-    fn register_word(&mut self, word: Word);
-    fn unregister_word(&mut self, word: &Word);
-    fn used_words(&self) -> Vec<&Word>;
-    fn has_used_word(&self, word: &Word) -> bool;
-}
-
-pub struct InMemoryGenerationState {
-    max_depth: u16,
-
-    length: i32,
-    max_length: i32,
-
-    used_words: HashSet<i32>,
-    sub_steps: Vec<TrivialGenerationSubStep>,
-
-    current_sub_step: TrivialGenerationSubStep,
-}
-
-impl InMemoryGenerationState {
-    #[allow(unused)]
-    pub fn new(max_depth: u16, max_length: i32) -> Self {
-        Self {
-            max_depth,
-            length: 0i32,
-            max_length,
-            used_words: HashSet::new(),
-            sub_steps: Vec::new(),
-            current_sub_step: GenerationSubStep::new(),
-        }
-    }
-}
-
-impl GenerationState<i32, i32, i32, i32> for InMemoryGenerationState {
-    fn begin_generation_sub_step(&mut self) {
-        self.sub_steps.push(self.current_sub_step.clone());
-        self.current_sub_step = GenerationSubStep::new()
-    }
-
-    fn end_generation_sub_step(&mut self) -> Option<TrivialGenerationSubStep> {
-        if let Some(sub_step) = self.sub_steps.pop() {
-            let current_sub_step = self.current_sub_step.clone();
-            self.current_sub_step = sub_step;
-            return Some(current_sub_step);
-        }
-
-        None
-    }
-
-    fn current_depth(&self) -> u16 {
-        self.sub_steps.len() as u16
-    }
-
-    fn is_too_deep(&self) -> bool {
-        self.current_depth() > self.max_depth
-    }
-
-    fn propagate_semantics(&mut self, semantics: Vec<i32>) {
-        self.current_sub_step.propagate_semantic_tags(semantics)
-    }
-
-    fn propagate_grammar(&mut self, grammar: Vec<i32>) {
-        self.current_sub_step.propagate_grammar_tags(grammar)
-    }
-
-    fn current_context(&self) -> (Vec<&i32>, Vec<&i32>) {
-        self.current_sub_step.deconstruct_context()
-    }
-
-    fn register_semantics(&mut self, placeholder: i32, semantics: Vec<i32>) -> bool {
-        self.current_sub_step
-            .register_semantic_tags(placeholder, semantics)
-    }
-
-    fn register_grammar(&mut self, placeholder: i32, grammar: Vec<i32>) -> bool {
-        self.current_sub_step
-            .register_grammar_tags(placeholder, grammar)
-    }
-
-    fn extract_placeholder_semantics(&self, placeholder: &i32) -> Option<Vec<&i32>> {
-        self.current_sub_step.get_semantics_of(placeholder)
-    }
-
-    fn extract_placeholder_grammar(&self, placeholder: &i32) -> Option<Vec<&i32>> {
-        self.current_sub_step.get_grammar_of(placeholder)
-    }
-
-    fn alter_length(&mut self, amount: i32) {
-        self.length += amount
-    }
-
-    fn is_too_long(&self) -> bool {
-        self.length > self.max_length
-    }
+impl AsyncPhraseGenerator for PhraseGenerator {
+    async fn generate(&self, opts: SpeechGenerationOptions) -> AppResult<Speech> {
+        let strategy = opts.strategy.seeded(opts.seed)?;
+        let seed = opts.seed.unwrap_or_else(random_seed);
 
-    fn register_word(&mut self, word: i32) {
-        self.used_words.insert(word);
-    }
+        let mut transaction = self
+            .pool
+            .begin()
+            .await
+            .map_err(AppError::for_generation_in_sql)?;
+        let mut source = PostgresGrammarSource::new(
+            &mut transaction,
+            opts.language.as_deref().unwrap_or(DEFAULT_LANGUAGE),
+        );
+        source.check_language().await?;
 
-    fn unregister_word(&mut self, word: &i32) {
-        self.used_words.remove(word);
-    }
+        let category = resolve_category(opts.category.as_deref(), &mut source).await?;
+        let mut share = PoolShare::count(&mut source, opts.category.as_deref()).await?;
+        let result = self
+            .produce(&mut source, &mut share, &category, strategy, seed)
+            .await?;
 
-    fn used_words(&self) -> Vec<&i32> {
-        self.used_words.iter().collect_vec()
-    }
+        transaction
+            .commit()
+            .await
+            .map_err(AppError::for_generation_in_sql)?;
 
-    fn has_used_word(&self, word: &i32) -> bool {
-        self.used_words.contains(word)
+        Ok(result)
     }
-}
 
-async fn generate_phrase(
-    _: SpeechGenerationOptions,
-    transaction: &mut Transaction<'_, Postgres>,
-) -> AppResult<String> {
-    let mut state = InMemoryGenerationState::new(100, 500);
-
-    generate_from_non_terminal_symbol(
-        &TokenReference::new_trivial_reference("Start".to_owned()),
-        &mut state,
-        transaction,
-    )
-    .await
-    .map(|s| {
-        s.replace("-", "")
-            .replace("_", "")
-            .replace("  ", " ")
-            .trim()
-            .to_owned()
-    })
-}
+    async fn generate_batch(
+        &self,
+        count: usize,
+        opts: BatchGenerationOptions,
+    ) -> AppResult<Vec<BatchItem>> {
+        // Item seeds come from the batch seed, so that a whole batch can be reproduced
+        let strategy = opts.strategy.seeded(opts.seed)?;
+        let mut seeds = StdRng::seed_from_u64(opts.seed.unwrap_or_else(random_seed));
+        let mut texts = HashSet::new();
+        let mut items = Vec::new();
 
-#[async_recursion]
-async fn generate_from_placeholder(
-    placeholder: &PlaceholderReference,
-    state: &mut TrivialGenerationState,
-    transaction: &mut Transaction<'_, Postgres>,
-) -> AppResult<String> {
-    match placeholder {
-        PlaceholderReference::NonTerminalSymbol(nts) => {
-            generate_from_non_terminal_symbol(nts, state, transaction).await
-        }
-        PlaceholderReference::WordSelector(word) => {
-            generate_from_word_selector(word, state, transaction).await
+        let mut transaction = self
+            .pool
+            .begin()
+            .await
+            .map_err(AppError::for_generation_in_sql)?;
+        let mut source = PostgresGrammarSource::new(
+            &mut transaction,
+            opts.language.as_deref().unwrap_or(DEFAULT_LANGUAGE),
+        );
+        source.check_language().await?;
+
+        let category = resolve_category(opts.category.as_deref(), &mut source).await?;
+        let mut share = PoolShare::count(&mut source, opts.category.as_deref()).await?;
+
+        for _ in 0..count.min(MAX_BATCH_SIZE) {
+            let mut attempts = 0;
+            let item = loop {
+                attempts += 1;
+                let seed = seeds.gen_range(0..=MAX_GENERATION_SEED);
+
+                match self
+                    .produce(&mut source, &mut share, &category, strategy, seed)
+                    .await
+                {
+                    Ok(speech) if opts.distinct && texts.contains(&speech.text) => {
+                        if attempts == MAX_DISTINCT_ATTEMPTS {
+                            break BatchItem {
+                                seed,
+                                result: Err(AppError::for_generation_no_distinct_phrase(attempts)),
+                            };
+                        }
+                    }
+                    // Past a failed statement the transaction cannot be used anymore
+                    Err(error @ AppError::Generation(GenerationError::DBFailed(_))) => {
+                        return Err(error)
+                    }
+                    result => break BatchItem { seed, result },
+                }
+            };
+
+            if let Ok(speech) = &item.result {
+                texts.insert(speech.text.clone());
+            }
+            items.push(item);
         }
-    }
-}
 
-#[async_recursion]
-async fn generate_from_non_terminal_symbol(
-    token: &TokenReference,
-    state: &mut TrivialGenerationState,
-    transaction: &mut Transaction<'_, Postgres>,
-) -> AppResult<String> {
-    if state.is_too_deep() {
-        return Err(GenerationError::ExcessiveDepth(state.current_depth()).into());
-    }
-
-    let branch = pick_production(token, state, transaction).await?;
-
-    let (semantics, grammar) = compute_semantic_and_grammar_dependencies(token, state)?;
-    let mut generation_lookup: HashMap<i32, String> = HashMap::new();
-
-    tracing::info!("Dependency on grammar: {:?}", grammar);
-    tracing::info!("Dependency on semantics: {:?}", semantics);
+        transaction
+            .commit()
+            .await
+            .map_err(AppError::for_generation_in_sql)?;
 
-    state.begin_generation_sub_step();
-    state.propagate_grammar(grammar);
-    state.propagate_semantics(semantics);
-    for placeholder in branch.ordered_placeholder_references()? {
-        generation_lookup.insert(
-            placeholder.id(),
-            generate_from_placeholder(placeholder, state, transaction).await?,
-        );
+        Ok(items)
     }
-    if let Some((semantics, grammar)) = state
-        .end_generation_sub_step()
-        .as_ref()
-        .map(GenerationSubStep::deconstruct_context)
-    {
-        tracing::info!(
-            "Token {} has released grammar {:?} and semantics {:?}",
-            token.id(),
-            grammar,
-            semantics,
-        );
-        state.register_grammar(token.id(), grammar.into_iter().copied().collect());
-        state.register_semantics(token.id(), semantics.into_iter().copied().collect());
-    } else {
-        return Err(AppError::for_generation_non_existent_sub_step());
-    }
-
-    let result = branch
-        .placeholder_appearance_order_in_production()
-        .iter()
-        .filter_map(|i| generation_lookup.get(i))
-        .join(" ");
-
-    state.alter_length(result.len() as i32);
-
-    Ok(result)
-}
-
-async fn pick_production(
-    token: &TokenReference,
-    state: &mut TrivialGenerationState,
-    transaction: &mut Transaction<'_, Postgres>,
-) -> AppResult<ProductionBranch> {
-    let template = if !state.is_too_long() {
-        include_str!("../../draft_ideas/select_random_production.sql")
-    } else {
-        include_str!("../../draft_ideas/select_shortest_production.sql")
-    };
-
-    let (row,): (String,) = sqlx::query_as::<Postgres, (String,)>(template)
-        .bind(token.reference())
-        .fetch_optional(transaction)
-        .await
-        .map_err(AppError::for_generation_in_sql)?
-        .ok_or_else(|| {
-            AppError::for_generation_no_production_branches_found(token.reference().to_string())
-        })?;
-
-    ProductionBranch::from_str(&row)
-}
 
-#[derive(FromRow)]
-struct SelectedWord {
-    id: i32,
-    content: String,
-    non_repeatable: bool,
-    semantic_output: Vec<i32>,
-    grammar_output: Vec<i32>,
-}
-
-#[async_recursion]
-async fn generate_from_word_selector(
-    token: &TokenReference,
-    state: &mut TrivialGenerationState,
-    transaction: &mut Transaction<'_, Postgres>,
-) -> AppResult<String> {
-    let selected_word = pick_word(token, state, transaction).await?;
-
-    tracing::info!("Found word: {}", selected_word.content);
-    tracing::info!("Semantics: {:?}", selected_word.semantic_output);
-    tracing::info!("Grammar: {:?}", selected_word.grammar_output);
-
-    state.register_semantics(token.id(), selected_word.semantic_output.clone());
-    state.register_grammar(token.id(), selected_word.grammar_output.clone());
+    async fn categories(&self) -> AppResult<Vec<Category>> {
+        let mut transaction = self
+            .pool
+            .begin()
+            .await
+            .map_err(AppError::for_generation_in_sql)?;
 
-    if token.grammar_can_propagate() {
-        state.propagate_grammar(selected_word.grammar_output);
+        // Categories are shared by all the languages
+        PostgresGrammarSource::new(&mut transaction, DEFAULT_LANGUAGE)
+            .retrieve_categories()
+            .await
     }
 
-    if token.semantic_can_propagate() {
-        state.propagate_semantics(selected_word.semantic_output);
-    }
+    async fn validate_grammar(&self, language: &str) -> AppResult<GrammarReport> {
+        let mut transaction = self
+            .pool
+            .begin()
+            .await
+            .map_err(AppError::for_generation_in_sql)?;
+        let mut source = PostgresGrammarSource::new(&mut transaction, language);
+        source.check_language().await?;
 
-    if selected_word.non_repeatable {
-        state.register_word(selected_word.id)
+        validate_grammar(&mut source).await
     }
-
-    Ok(selected_word.content)
 }
 
-async fn pick_word(
-    token: &TokenReference,
-    state: &mut TrivialGenerationState,
-    transaction: &mut Transaction<'_, Postgres>,
-) -> AppResult<SelectedWord> {
-    let search_tag_names = token.reference().split(',').map(|s| s.trim()).collect_vec();
-    let search_tags = retrieve_tag_ids_from_strings(&(search_tag_names), transaction).await?;
-    let used_words = state
-        .used_words()
-        .into_iter()
-        .chain(vec![&i32::MIN])
-        .collect_vec();
-    let (semantic_tags, grammar_tags) = compute_semantic_and_grammar_dependencies(token, state)?;
-
-    tracing::info!("search_tags: {:?}", search_tags);
-    tracing::info!("semantic_tags: {:?}", semantic_tags);
-    tracing::info!("grammar_tags: {:?}", grammar_tags);
-    tracing::info!("used_words: {:?}", used_words);
-
-    let template = include_str!("../../draft_ideas/select_random_word.sql")
-        .replace(
-            "<SELECTED_SEMANTIC_TAGS_PLACEHOLDERS>",
-            (0..search_tags.len())
-                .map(|i| format!("${}", i + 1))
-                .join(",")
-                .as_str(),
-        )
-        .replace(
-            "<CONTEXTUAL_SEMANTIC_TAGS_PLACEHOLDERS>",
-            (0..semantic_tags.len())
-                .map(|i| format!("${}", i + search_tags.len() + 1))
-                .join(",")
-                .as_str(),
-        )
-        .replace(
-            "<CONTEXTUAL_GRAMMAR_TAGS_PLACEHOLDERS>",
-            (0..grammar_tags.len())
-                .map(|i| format!("${}", i + search_tags.len() + semantic_tags.len() + 1))
-                .join(",")
-                .as_str(),
-        )
-        .replace(
-            "<USED_WORDS>",
-            (0..used_words.len())
-                .map(|i| {
-                    format!(
-                        "${}",
-                        i + search_tags.len() + semantic_tags.len() + grammar_tags.len() + 1
-                    )
-                })
-                .join(",")
-                .as_str(),
-        );
-
-    let mut query = sqlx::query_as::<sqlx::Postgres, SelectedWord>(&template);
-
-    for &tag in search_tags
-        .iter()
-        .chain(semantic_tags.iter())
-        .chain(grammar_tags.iter())
-        .chain(used_words.into_iter())
-    {
-        tracing::info!("Binding parameter to value {}", tag);
-        query = query.bind(tag);
+#[async_trait]
+impl AsyncHealth for PhraseGenerator {
+    async fn is_healthy(&self) -> AppResult<()> {
+        self.pool
+            .acquire()
+            .await
+            .map(|_| ())
+            .map_err(AppError::for_infrastructure_db_connections_unavailable)
+            .log_err("DB is not healthy", LogLevel::Warning)
     }
-
-    query
-        .fetch_optional(transaction)
-        .await
-        .map_err(AppError::for_generation_in_sql)?
-        .ok_or_else(AppError::for_generation_no_words_found)
 }
 
-fn compute_semantic_and_grammar_dependencies(
-    token: &TokenReference,
-    state: &TrivialGenerationState,
-) -> AppResult<(Vec<i32>, Vec<i32>)> {
-    let (context_semantics, context_grammar) = state.current_context();
-
-    let semantic_tags = match (
-        token.semantic_dependency_on_other(),
-        token.semantic_depends_on_context(),
-    ) {
-        (None, false) => vec![],
-        (None, true) => context_semantics,
-        (Some(ref id), false) => state
-            .extract_placeholder_semantics(id)
-            .ok_or_else(|| GenerationError::NonRegisteredPlaceholder(*id))?,
-        (Some(ref id), true) => state
-            .extract_placeholder_semantics(id)
-            .ok_or_else(|| GenerationError::NonRegisteredPlaceholder(*id))?
-            .into_iter()
-            .chain(context_semantics)
-            .collect(),
-    };
-
-    let grammar_tags = match (
-        token.grammar_dependency_on_other(),
-        token.grammar_depends_on_context(),
-    ) {
-        (None, false) => vec![],
-        (None, true) => context_grammar,
-        (Some(ref id), false) => state
-            .extract_placeholder_grammar(id)
-            .ok_or_else(|| GenerationError::NonRegisteredPlaceholder(*id))?,
-        (Some(ref id), true) => state
-            .extract_placeholder_grammar(id)
-            .ok_or_else(|| GenerationError::NonRegisteredPlaceholder(*id))?
-            .into_iter()
-            .chain(context_grammar)
-            .collect(),
-    };
-
-    Ok((
-        semantic_tags.into_iter().copied().collect(),
-        grammar_tags.into_iter().copied().collect(),
-    ))
-}
-
-async fn retrieve_tag_ids_from_strings(
-    tags: &[&str],
-    transaction: &mut Transaction<'_, Postgres>,
-) -> AppResult<Vec<i32>> {
-    let template = include_str!("../../draft_ideas/select_ids_of_tags.sql").replace(
-        "<SEMANTIC_TAGS_PLACEHOLDERS>",
-        (0..tags.len())
-            .map(|i| format!("${}", i + 1))
-            .join(",")
-            .as_str(),
-    );
-
-    let mut query = sqlx::query_as::<sqlx::Postgres, (i32,)>(&template);
-
-    for &tag in tags {
-        query = query.bind(tag);
-    }
-
-    let ids = query
-        .fetch_all(transaction)
-        .await
-        .map_err(AppError::for_generation_in_sql)?;
-
-    Ok(ids.into_iter().map(|(i,)| i).collect_vec())
-}
+#[async_trait]
+impl AsyncHealthyPhraseGenerator for PhraseGenerator {}
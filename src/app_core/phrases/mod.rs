@@ -0,0 +1,125 @@
+use sqlx::types::Uuid;
+use sqlx::{FromRow, Pool, Postgres};
+
+use crate::app_core::audio::{AudioArchive, AudioKey};
+use crate::app_core::editing::{Page, PageRequest};
+use crate::app_core::errors::AppError;
+use crate::app_core::AppResult;
+
+#[cfg(test)]
+#[path = "./unit_tests/mod.rs"]
+mod tests;
+
+/// A phrase of the pool, as stored by a past generation.
+#[derive(FromRow, Clone, Debug, PartialEq)]
+pub struct StoredPhrase {
+    pub id: Uuid,
+    pub content: String,
+    /// Code of the language of the phrase.
+    pub language: String,
+}
+
+/// Audio already synthesized for a stored phrase, in one of the voices.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StoredSpeech {
+    pub voice: i32,
+    /// Code of the language the voice speaks.
+    pub language: String,
+    pub gender: String,
+    pub url: String,
+}
+
+/// Reads the pool of generated phrases and the audio cached for them.
+pub struct Phrases<'p> {
+    pool: &'p Pool<Postgres>,
+    audio: &'p AudioArchive,
+}
+
+impl<'p> Phrases<'p> {
+    pub fn new(pool: &'p Pool<Postgres>, audio: &'p AudioArchive) -> Self {
+        Self { pool, audio }
+    }
+
+    pub async fn phrase(&self, id: Uuid) -> AppResult<Option<StoredPhrase>> {
+        sqlx::query_as::<Postgres, StoredPhrase>(
+            "SELECT p.id, p.content, l.code AS language FROM generated_phrase p INNER JOIN language l ON l.id = p.language WHERE p.id = $1",
+        )
+        .bind(id)
+        .fetch_optional(self.pool)
+        .await
+        .map_err(AppError::for_browsing_in_sql)
+    }
+
+    /// Phrases are ordered by content; `search` keeps the ones containing it, ignoring case.
+    pub async fn phrases(
+        &self,
+        search: Option<&str>,
+        page: PageRequest,
+    ) -> AppResult<Page<StoredPhrase>> {
+        let pattern = like_pattern(search.unwrap_or_default());
+
+        let total = sqlx::query_scalar::<Postgres, i64>(
+            "SELECT COUNT(*) FROM generated_phrase WHERE content ILIKE $1",
+        )
+        .bind(&pattern)
+        .fetch_one(self.pool)
+        .await
+        .map_err(AppError::for_browsing_in_sql)?;
+        let items = sqlx::query_as::<Postgres, StoredPhrase>(
+            "SELECT p.id, p.content, l.code AS language FROM generated_phrase p INNER JOIN language l ON l.id = p.language WHERE p.content ILIKE $1 ORDER BY p.content, l.code LIMIT $2 OFFSET $3",
+        )
+        .bind(&pattern)
+        .bind(page.limit())
+        .bind(page.offset())
+        .fetch_all(self.pool)
+        .await
+        .map_err(AppError::for_browsing_in_sql)?;
+
+        Ok(Page {
+            total,
+            offset: page.offset(),
+            items,
+        })
+    }
+
+    /// Audio kept by this server is linked through its `/audio` route.
+    pub async fn speeches(&self, phrase: Uuid) -> AppResult<Vec<StoredSpeech>> {
+        let rows = sqlx::query_as::<Postgres, (i32, String, String, String, bool, bool)>(
+            "SELECT s.voice, l.code, v.gender::text, s.url, s.content_type IS NOT NULL, s.audio IS NOT NULL FROM generated_phrase_speech s INNER JOIN voice v ON v.id = s.voice INNER JOIN language l ON l.id = v.language WHERE s.generated_phrase = $1 ORDER BY s.voice",
+        )
+        .bind(phrase)
+        .fetch_all(self.pool)
+        .await
+        .map_err(AppError::for_browsing_in_sql)?;
+
+        Ok(rows
+            .into_iter()
+            .map(
+                |(voice, language, gender, url, has_content_type, in_database)| {
+                    let key = AudioKey { phrase, voice };
+                    let url = if self.audio.is_archived(&key, has_content_type, in_database) {
+                        self.audio.served_url(&key)
+                    } else {
+                        url
+                    };
+
+                    StoredSpeech {
+                        voice,
+                        language,
+                        gender,
+                        url,
+                    }
+                },
+            )
+            .collect())
+    }
+}
+
+/// `ILIKE` pattern matching any text containing `search` literally.
+fn like_pattern(search: &str) -> String {
+    let escaped = search
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{escaped}%")
+}
@@ -0,0 +1,8 @@
+use crate::app_core::phrases::like_pattern;
+
+#[test]
+fn searches_match_wildcards_literally() {
+    assert_eq!(like_pattern(""), "%%");
+    assert_eq!(like_pattern("cane"), "%cane%");
+    assert_eq!(like_pattern("50%_\\"), "%50\\%\\_\\\\%");
+}
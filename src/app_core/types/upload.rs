@@ -3,7 +3,20 @@ use reqwest::Url;
 #[derive(Clone)]
 pub struct Speech {
     pub text: String,
+    pub voice: SpeechVoice,
+}
+
+/// How the TTS provider reads a speech, as described by the voice catalogue.
+#[derive(Clone)]
+pub struct SpeechVoice {
+    /// Name the provider knows the voice by.
+    pub name: String,
+    /// Locale of the text, like `it`.
+    pub locale: String,
     pub is_male: bool,
+    /// Speaking rate and pitch, relative to the provider defaults.
+    pub rate: f32,
+    pub pitch: f32,
 }
 
 #[derive(Clone)]
@@ -11,8 +24,8 @@ pub struct UploadedSpeech {
     pub url: Url,
 }
 
-impl From<crate::outgoing::tts_wrapper::types::UploadResult> for UploadedSpeech {
-    fn from(result: crate::outgoing::tts_wrapper::types::UploadResult) -> Self {
+impl From<crate::outgoing::tts::types::UploadResult> for UploadedSpeech {
+    fn from(result: crate::outgoing::tts::types::UploadResult) -> Self {
         Self { url: result.url }
     }
 }
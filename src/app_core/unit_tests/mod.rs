@@ -0,0 +1,45 @@
+use crate::app_core::errors::{AppError, GenerationError};
+use crate::app_core::{GenerationStrategy, PhraseStreams};
+
+const PROBABILISTIC: GenerationStrategy = GenerationStrategy::Probabilistic { max_phrases: 10 };
+
+#[test]
+fn strategies_are_kept_without_a_seed() {
+    for strategy in [
+        GenerationStrategy::Fresh,
+        GenerationStrategy::Pool,
+        PROBABILISTIC,
+    ] {
+        assert_eq!(strategy.seeded(None).unwrap(), strategy);
+    }
+}
+
+#[test]
+fn a_seed_always_generates() {
+    assert_eq!(
+        PROBABILISTIC.seeded(Some(42)).unwrap(),
+        GenerationStrategy::Fresh
+    );
+    assert_eq!(
+        GenerationStrategy::Fresh.seeded(Some(42)).unwrap(),
+        GenerationStrategy::Fresh
+    );
+    assert!(matches!(
+        GenerationStrategy::Pool.seeded(Some(42)),
+        Err(AppError::Generation(GenerationError::SeededPool))
+    ));
+}
+
+#[test]
+fn phrase_streams_are_capped_until_one_ends() {
+    let streams = PhraseStreams::new(2);
+    let first = streams.open().unwrap();
+    let _second = streams.open().unwrap();
+
+    assert!(matches!(
+        streams.open(),
+        Err(AppError::Generation(GenerationError::TooManyStreams(2)))
+    ));
+    drop(first);
+    assert!(streams.open().is_ok());
+}
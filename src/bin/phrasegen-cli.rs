@@ -0,0 +1,314 @@
+use std::str::FromStr;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use sqlx::postgres::PgPoolOptions;
+use sqlx::{Pool, Postgres};
+
+use phrase_generator::app_core::engine::grammar_file::database::{export_grammar, import_grammar};
+use phrase_generator::app_core::engine::grammar_file::memory::load_grammar;
+use phrase_generator::app_core::engine::grammar_file::GrammarFile;
+use phrase_generator::app_core::engine::sources::{
+    Category, GrammarSource, PostgresGrammarSource, DEFAULT_LANGUAGE,
+};
+use phrase_generator::app_core::engine::types::derivation::Derivation;
+use phrase_generator::app_core::engine::validation::validate_grammar;
+use phrase_generator::app_core::engine::{generate_phrase, resolve_category, GenerationLimits};
+use phrase_generator::app_core::errors::AppError;
+use phrase_generator::app_core::{AppResult, MAX_GENERATION_SEED};
+use phrase_generator::settings::Settings;
+
+const USAGE: &str = "Usage: phrasegen-cli [--grammar FILE | --language CODE] COMMAND
+
+Commands:
+  generate [--count N] [--category NAME | --start SYMBOL] [--seed SEED] [--derivation]
+  validate
+  export FILE
+  import FILE
+
+Without --grammar the grammar stored at DB_CONNECTION_STRING, or database.connection_string
+of the settings file, is used in the language given by --language (ita by default). Export
+and import always work on the database.";
+
+enum Command {
+    Generate(GenerationRequest),
+    Validate,
+    Export(String),
+    Import(String),
+}
+
+struct GenerationRequest {
+    count: u32,
+    category: Option<String>,
+    start_symbol: Option<String>,
+    seed: Option<u64>,
+    derivation: bool,
+}
+
+#[actix_web::main]
+async fn main() {
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_max_level(tracing::Level::WARN)
+        .init();
+
+    let arguments = std::env::args().skip(1).collect::<Vec<_>>();
+    let (grammar_path, language, command) = match parse_arguments(&arguments) {
+        Ok(parsed) => parsed,
+        Err(reason) => {
+            eprintln!("{}\n\n{}", reason, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    let code = run(grammar_path, language, command)
+        .await
+        .unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            2
+        });
+    std::process::exit(code);
+}
+
+fn parse_arguments(
+    arguments: &[String],
+) -> Result<(Option<String>, Option<String>, Command), String> {
+    let mut arguments = arguments.iter().map(String::as_str);
+    let mut grammar_path = None;
+    let mut language = None;
+
+    let command = loop {
+        match arguments.next() {
+            Some("--grammar") => grammar_path = Some(expect_value(&mut arguments, "--grammar")?),
+            Some("--language") => language = Some(expect_value(&mut arguments, "--language")?),
+            Some(command) => break command,
+            None => return Err("No command given".to_owned()),
+        }
+    };
+
+    let command = match command {
+        "generate" => Command::Generate(parse_generation_request(&mut arguments)?),
+        "validate" => Command::Validate,
+        "export" => Command::Export(expect_value(&mut arguments, "export")?),
+        "import" => Command::Import(expect_value(&mut arguments, "import")?),
+        unknown => return Err(format!("Unknown command '{}'", unknown)),
+    };
+
+    if let Some(unexpected) = arguments.next() {
+        return Err(format!("Unexpected argument '{}'", unexpected));
+    }
+    if grammar_path.is_some() && matches!(command, Command::Export(_) | Command::Import(_)) {
+        return Err("Export and import work on the database, --grammar cannot be used".to_owned());
+    }
+    if grammar_path.is_some() && language.is_some() {
+        return Err("A grammar file holds a single language, --language cannot be used".to_owned());
+    }
+
+    Ok((grammar_path, language, command))
+}
+
+fn parse_generation_request<'a>(
+    arguments: &mut impl Iterator<Item = &'a str>,
+) -> Result<GenerationRequest, String> {
+    let mut request = GenerationRequest {
+        count: 1,
+        category: None,
+        start_symbol: None,
+        seed: None,
+        derivation: false,
+    };
+
+    while let Some(argument) = arguments.next() {
+        match argument {
+            "--count" => request.count = parse_value(arguments, "--count")?,
+            "--category" => request.category = Some(expect_value(arguments, "--category")?),
+            "--start" => request.start_symbol = Some(expect_value(arguments, "--start")?),
+            "--seed" => request.seed = Some(parse_value(arguments, "--seed")?),
+            "--derivation" => request.derivation = true,
+            unknown => return Err(format!("Unknown generation option '{}'", unknown)),
+        }
+    }
+
+    if request.category.is_some() && request.start_symbol.is_some() {
+        return Err("Either --category or --start can be given, not both".to_owned());
+    }
+
+    Ok(request)
+}
+
+fn expect_value<'a>(
+    arguments: &mut impl Iterator<Item = &'a str>,
+    option: &str,
+) -> Result<String, String> {
+    arguments
+        .next()
+        .map(str::to_owned)
+        .ok_or_else(|| format!("Missing value after '{}'", option))
+}
+
+fn parse_value<'a, T: FromStr>(
+    arguments: &mut impl Iterator<Item = &'a str>,
+    option: &str,
+) -> Result<T, String> {
+    let value = expect_value(arguments, option)?;
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value '{}' for '{}'", value, option))
+}
+
+async fn run(
+    grammar_path: Option<String>,
+    language: Option<String>,
+    command: Command,
+) -> AppResult<i32> {
+    if let Some(path) = grammar_path {
+        let text = std::fs::read_to_string(&path).map_err(AppError::for_grammar_file_access)?;
+        let mut source = load_grammar(&GrammarFile::from_str(&text)?);
+
+        return match command {
+            Command::Generate(request) => {
+                generate(&request, &file_generation_limits(), &mut source).await
+            }
+            Command::Validate => validate(&mut source).await,
+            Command::Export(_) | Command::Import(_) => unreachable!("rejected while parsing"),
+        };
+    }
+
+    let language = language.as_deref().unwrap_or(DEFAULT_LANGUAGE);
+    let settings = Settings::load()?;
+    let pool = connect(&settings).await?;
+    let mut transaction = pool
+        .begin()
+        .await
+        .map_err(AppError::for_grammar_storage_in_sql)?;
+
+    match command {
+        // Nothing is stored: the transaction is rolled back when dropped
+        Command::Generate(request) => {
+            let mut source = PostgresGrammarSource::new(&mut transaction, language);
+            source.check_language().await?;
+            let limits = GenerationLimits::from(&settings.generation);
+            generate(&request, &limits, &mut source).await
+        }
+        Command::Validate => {
+            let mut source = PostgresGrammarSource::new(&mut transaction, language);
+            source.check_language().await?;
+            validate(&mut source).await
+        }
+        Command::Export(path) => {
+            let grammar = export_grammar(&mut transaction, language).await?;
+            std::fs::write(&path, grammar.to_string())
+                .map_err(AppError::for_grammar_file_access)?;
+            println!("Exported grammar to {}", path);
+            Ok(0)
+        }
+        Command::Import(path) => {
+            let text = std::fs::read_to_string(&path).map_err(AppError::for_grammar_file_access)?;
+            import_grammar(&mut transaction, &GrammarFile::from_str(&text)?, language).await?;
+            transaction
+                .commit()
+                .await
+                .map_err(AppError::for_grammar_storage_in_sql)?;
+            println!("Imported grammar from {}", path);
+            Ok(0)
+        }
+    }
+}
+
+/// The schema is expected to be up to date: migrations are left to the server.
+async fn connect(settings: &Settings) -> AppResult<Pool<Postgres>> {
+    PgPoolOptions::new()
+        .max_connections(1)
+        .connect(settings.database.connection_string.as_str())
+        .await
+        .map_err(AppError::for_grammar_storage_in_sql)
+}
+
+/// A grammar file needs no database, so settings that cannot be loaded only cost the tuned limits.
+fn file_generation_limits() -> GenerationLimits {
+    Settings::load()
+        .map(|settings| GenerationLimits::from(&settings.generation))
+        .unwrap_or_else(|error| {
+            tracing::warn!("{}, default generation limits are used", error);
+            GenerationLimits::default()
+        })
+}
+
+/// One phrase per line, preceded by the seed that reproduces it; failures go to stderr.
+async fn generate(
+    request: &GenerationRequest,
+    limits: &GenerationLimits,
+    source: &mut dyn GrammarSource,
+) -> AppResult<i32> {
+    let category = match &request.start_symbol {
+        Some(start_symbol) => Category {
+            start_symbol: start_symbol.clone(),
+            ..Category::default()
+        },
+        None => resolve_category(request.category.as_deref(), source).await?,
+    };
+    let first_seed = request
+        .seed
+        .unwrap_or_else(|| rand::thread_rng().gen_range(0..=MAX_GENERATION_SEED));
+    let mut failures = 0;
+
+    for index in 0..request.count {
+        let seed = first_seed.wrapping_add(index as u64) & MAX_GENERATION_SEED;
+
+        match generate_phrase(&category, limits, source, &mut StdRng::seed_from_u64(seed)).await {
+            Ok(phrase) => {
+                println!("{}\t{}", seed, phrase.text);
+                if request.derivation {
+                    print_derivation(&phrase.derivation, 1);
+                }
+            }
+            Err(error) => {
+                failures += 1;
+                eprintln!("{}\t{}", seed, error);
+            }
+        }
+    }
+
+    Ok(if failures == 0 { 0 } else { 1 })
+}
+
+fn print_derivation(node: &Derivation, depth: usize) {
+    let indent = "  ".repeat(depth);
+
+    match (&node.production, node.word) {
+        (Some(production), _) => {
+            for discarded in node.discarded_productions.iter() {
+                println!(
+                    "{}{{{}:{}}} x {}",
+                    indent, node.placeholder, node.reference, discarded
+                );
+            }
+            println!(
+                "{}{{{}:{}}} -> {}",
+                indent, node.placeholder, node.reference, production
+            );
+        }
+        (None, Some(word)) => println!(
+            "{}<{}:{}> = {:?} (word {})",
+            indent, node.placeholder, node.reference, node.text, word
+        ),
+        (None, None) => println!("{}{}: {:?}", indent, node.reference, node.text),
+    }
+
+    for child in node.children.iter() {
+        print_derivation(child, depth + 1);
+    }
+}
+
+async fn validate(source: &mut dyn GrammarSource) -> AppResult<i32> {
+    let report = validate_grammar(source).await?;
+
+    for problem in report.problems.iter() {
+        println!("{}", problem);
+    }
+    println!("Unreachable symbols: {:?}", report.unreachable_symbols());
+    println!("Unproductive symbols: {:?}", report.unproductive_symbols());
+    println!("Found {} problems", report.problems.len());
+
+    Ok(if report.is_valid() { 0 } else { 1 })
+}
@@ -0,0 +1,5 @@
+pub mod app_core;
+pub mod outgoing;
+pub mod served;
+pub mod settings;
+pub mod utils;
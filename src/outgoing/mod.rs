@@ -1 +1 @@
-pub mod tts_wrapper;
+pub mod tts;
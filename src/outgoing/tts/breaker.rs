@@ -0,0 +1,124 @@
+use std::fmt::{Display, Formatter};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[cfg(test)]
+#[path = "./unit_tests/breaker.rs"]
+mod tests;
+
+#[derive(Clone, Copy)]
+pub struct BreakerSettings {
+    /// Consecutive failures opening the circuit.
+    pub failure_threshold: u32,
+    /// How long an open circuit refuses requests before letting a trial one through.
+    pub cooldown: Duration,
+}
+
+/// What the breaker currently lets through.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests go through; `failures` counts the last consecutive failed ones.
+    Closed { failures: u32 },
+    /// Requests are refused for `remaining` more.
+    Open { remaining: Duration },
+    /// A single trial request goes through, its outcome closes the circuit or opens it again.
+    HalfOpen,
+}
+
+impl Display for CircuitState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CircuitState::Closed { .. } => write!(f, "closed"),
+            CircuitState::Open { .. } => write!(f, "open"),
+            CircuitState::HalfOpen => write!(f, "half-open"),
+        }
+    }
+}
+
+enum Circuit {
+    Closed(u32),
+    Open {
+        until: Instant,
+    },
+    /// A trial started at the given instant. Trials that never report back, like dropped requests, are
+    /// given up after a cooldown.
+    HalfOpen {
+        trial: Instant,
+    },
+}
+
+/// Stops calling a dependency that keeps failing, so that callers fail fast instead of piling up on it.
+pub struct CircuitBreaker {
+    settings: BreakerSettings,
+    circuit: Mutex<Circuit>,
+}
+
+impl CircuitBreaker {
+    pub fn new(settings: BreakerSettings) -> Self {
+        Self {
+            settings,
+            circuit: Mutex::new(Circuit::Closed(0)),
+        }
+    }
+
+    pub fn state(&self) -> CircuitState {
+        self.state_at(Instant::now())
+    }
+
+    /// Asks to send a request, refused with the time left before the next trial.
+    pub fn acquire(&self) -> Result<(), Duration> {
+        self.acquire_at(Instant::now())
+    }
+
+    pub fn record_success(&self) {
+        *self.circuit.lock().unwrap() = Circuit::Closed(0);
+    }
+
+    pub fn record_failure(&self) {
+        self.record_failure_at(Instant::now())
+    }
+
+    fn state_at(&self, now: Instant) -> CircuitState {
+        match *self.circuit.lock().unwrap() {
+            Circuit::Closed(failures) => CircuitState::Closed { failures },
+            Circuit::Open { until } if now < until => CircuitState::Open {
+                remaining: until - now,
+            },
+            Circuit::Open { .. } | Circuit::HalfOpen { .. } => CircuitState::HalfOpen,
+        }
+    }
+
+    fn acquire_at(&self, now: Instant) -> Result<(), Duration> {
+        let mut circuit = self.circuit.lock().unwrap();
+        match *circuit {
+            Circuit::Closed(_) => Ok(()),
+            Circuit::Open { until } if now < until => Err(until - now),
+            Circuit::HalfOpen { trial } if now < trial + self.settings.cooldown => {
+                Err(trial + self.settings.cooldown - now)
+            }
+            Circuit::Open { .. } | Circuit::HalfOpen { .. } => {
+                *circuit = Circuit::HalfOpen { trial: now };
+                Ok(())
+            }
+        }
+    }
+
+    fn record_failure_at(&self, now: Instant) {
+        let mut circuit = self.circuit.lock().unwrap();
+        *circuit = match *circuit {
+            Circuit::Closed(failures) if failures + 1 < self.settings.failure_threshold => {
+                Circuit::Closed(failures + 1)
+            }
+            Circuit::Open { until } => Circuit::Open { until },
+            Circuit::Closed(_) | Circuit::HalfOpen { .. } => {
+                tracing::warn!(
+                    "Circuit opened, refusing requests for {:?}",
+                    self.settings.cooldown
+                );
+                Circuit::Open {
+                    until: now + self.settings.cooldown,
+                }
+            }
+        };
+    }
+}
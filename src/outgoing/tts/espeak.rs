@@ -0,0 +1,171 @@
+use std::ffi::OsString;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::time::{Duration, Instant};
+
+use actix_web::rt::task::spawn_blocking;
+use async_trait::async_trait;
+use reqwest::Url;
+
+use super::types::{Speech, UploadResult};
+use super::TtsProvider;
+use crate::app_core::errors::AppError;
+use crate::app_core::AppResult;
+use crate::settings::EspeakSection;
+use crate::utils::{LogLevel, Loggable};
+
+#[cfg(test)]
+#[path = "./unit_tests/espeak.rs"]
+mod tests;
+
+/// espeak-ng defaults, scaled by the rate and pitch of voices.
+const DEFAULT_WORDS_PER_MINUTE: f32 = 175.0;
+const DEFAULT_PITCH: f32 = 50.0;
+const MAX_PITCH: f32 = 99.0;
+
+pub struct EspeakOpts {
+    /// Synthesizer executable, looked up in `PATH` unless it is a path.
+    pub command: String,
+    /// Where synthesized files are written, served as `file://` URLs.
+    pub output_dir: PathBuf,
+    /// Synthesizers still running after it are killed.
+    pub timeout: Duration,
+}
+
+impl From<&EspeakSection> for EspeakOpts {
+    fn from(settings: &EspeakSection) -> Self {
+        Self {
+            command: settings.command.clone(),
+            output_dir: settings.output_dir.clone(),
+            timeout: Duration::from_millis(settings.timeout_ms),
+        }
+    }
+}
+
+/// Synthesizes WAV files with a locally installed `espeak-ng`, no network involved.
+pub struct EspeakTtsProvider {
+    options: EspeakOpts,
+}
+
+impl EspeakTtsProvider {
+    pub fn new(options: EspeakOpts) -> Self {
+        Self { options }
+    }
+
+    /// Text is written to the standard input: it can never be mistaken for an option.
+    /// espeak-ng names its voices after locales, like `it`, and catalogue voices name its variants, like `m3`.
+    fn arguments(&self, speech: &Speech, output: &Path) -> Vec<OsString> {
+        let speed = (DEFAULT_WORDS_PER_MINUTE * speech.rate).round();
+        let pitch = (DEFAULT_PITCH * speech.pitch).round().clamp(0.0, MAX_PITCH);
+
+        vec![
+            "-v".into(),
+            format!("{}+{}", speech.locale, speech.voice).into(),
+            "-s".into(),
+            format!("{speed}").into(),
+            "-p".into(),
+            format!("{pitch}").into(),
+            "-w".into(),
+            output.into(),
+            "--stdin".into(),
+        ]
+    }
+
+    fn output_file(&self) -> AppResult<PathBuf> {
+        std::fs::create_dir_all(&self.options.output_dir)
+            .and_then(|_| self.options.output_dir.canonicalize())
+            .map(|dir| dir.join(format!("{:032x}.wav", rand::random::<u128>())))
+            .map_err(|error| AppError::for_upload_synthesis(format!("{error}")))
+    }
+}
+
+/// Feeds `input` to `command`, killing it when still running after `timeout`.
+fn run(mut command: Command, input: &str, timeout: Duration) -> std::io::Result<Output> {
+    let deadline = Instant::now() + timeout;
+    let mut child = command.spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input.as_bytes())?;
+    }
+
+    while child.try_wait()?.is_none() {
+        if Instant::now() >= deadline {
+            child.kill()?;
+            child.wait()?;
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                format!("still running after {timeout:?}"),
+            ));
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    child.wait_with_output()
+}
+
+#[async_trait]
+impl TtsProvider for EspeakTtsProvider {
+    fn name(&self) -> &'static str {
+        "espeak"
+    }
+
+    async fn health(&self) -> AppResult<()> {
+        let mut command = Command::new(&self.options.command);
+        command
+            .arg("--version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+
+        spawn_blocking(move || command.status())
+            .await
+            .map_err(|error| format!("{error}"))
+            .and_then(|status| status.map_err(|error| format!("{error}")))
+            .and_then(|status| {
+                if status.success() {
+                    Ok(())
+                } else {
+                    Err(format!("version check exited with {status}"))
+                }
+            })
+            .map_err(AppError::for_infrastructure_synthesizer_unavailable)
+            .log_err("Local synthesizer is not healthy", LogLevel::Warning)
+    }
+
+    async fn upload(&self, request: Speech) -> AppResult<UploadResult> {
+        let output = self.output_file()?;
+        let mut command = Command::new(&self.options.command);
+        command
+            .args(self.arguments(&request, &output))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped());
+
+        let text = request.text;
+        let timeout = self.options.timeout;
+        let synthesized = spawn_blocking(move || run(command, &text, timeout))
+            .await
+            .map_err(|error| format!("{error}"))
+            .and_then(|output| output.map_err(|error| format!("{error}")))
+            .and_then(|output| {
+                if output.status.success() {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "{} exited with {}: {}",
+                        self.options.command,
+                        output.status,
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    ))
+                }
+            });
+
+        synthesized
+            .map_err(AppError::for_upload_synthesis)
+            .log_err("Unable to synthesize the requested speech", LogLevel::Error)?;
+
+        Url::from_file_path(&output)
+            .map(|url| UploadResult { url })
+            .map_err(|_| {
+                AppError::for_upload_synthesis(format!("{} is not absolute", output.display()))
+            })
+    }
+}
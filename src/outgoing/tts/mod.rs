@@ -0,0 +1,107 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::Client;
+
+use types::{Speech, UploadResult};
+
+use crate::app_core::AppResult;
+use crate::settings::{TtsProviderName, TtsSection, WrapperSection};
+
+use self::breaker::{BreakerSettings, CircuitState};
+use self::espeak::{EspeakOpts, EspeakTtsProvider};
+use self::probe::ProbeSettings;
+use self::wrapper::{RetryPolicy, SimpleTtsWrapperClient, TtsWrapperConnectionOpts};
+
+pub mod breaker;
+pub mod espeak;
+pub mod probe;
+pub mod types;
+pub mod wrapper;
+
+pub type AppTtsProvider = dyn TtsProvider + Send + Sync;
+
+/// Turns text into audio reachable at the returned URL.
+#[async_trait]
+pub trait TtsProvider {
+    /// Name of the provider in the voice catalogue, like its `TTS_PROVIDER` value.
+    fn name(&self) -> &'static str;
+
+    /// State of the circuit breaker guarding the provider, for the ones having one.
+    fn circuit(&self) -> Option<CircuitState> {
+        None
+    }
+
+    async fn health(&self) -> AppResult<()>;
+
+    async fn upload(&self, request: Speech) -> AppResult<UploadResult>;
+}
+
+/// Which provider synthesizes speeches, chosen through `tts.provider`.
+pub enum TtsSettings {
+    /// `wrapper`, the default: the external `tts-rest-wrapper`.
+    Wrapper(Box<TtsWrapperConnectionOpts>),
+    /// `espeak`: a local `espeak-ng`.
+    Espeak(EspeakOpts),
+}
+
+impl From<&TtsSection> for TtsSettings {
+    fn from(settings: &TtsSection) -> Self {
+        match settings.provider {
+            TtsProviderName::Wrapper => Self::Wrapper(Box::new((&settings.wrapper).into())),
+            TtsProviderName::Espeak => Self::Espeak((&settings.espeak).into()),
+        }
+    }
+}
+
+impl TtsSettings {
+    pub fn provider(self) -> Arc<AppTtsProvider> {
+        match self {
+            TtsSettings::Wrapper(options) => {
+                tracing::info!("Connecting to TTS wrapper at: {}", options.root_url);
+                let client = Client::builder()
+                    .connect_timeout(options.connect_timeout)
+                    .timeout(options.request_timeout)
+                    .build()
+                    .unwrap();
+                Arc::new(SimpleTtsWrapperClient::new(client, *options))
+            }
+            TtsSettings::Espeak(options) => {
+                tracing::info!(
+                    "Synthesizing locally with {}, into {}",
+                    options.command,
+                    options.output_dir.display()
+                );
+                Arc::new(EspeakTtsProvider::new(options))
+            }
+        }
+    }
+}
+
+impl From<&WrapperSection> for TtsWrapperConnectionOpts {
+    fn from(settings: &WrapperSection) -> Self {
+        Self {
+            root_url: settings.url.clone(),
+            connect_timeout: Duration::from_millis(settings.connect_timeout_ms),
+            request_timeout: Duration::from_millis(settings.request_timeout_ms),
+            retry: RetryPolicy {
+                max_retries: settings.max_retries,
+                backoff: Duration::from_millis(settings.retry_backoff_ms),
+            },
+            breaker: BreakerSettings {
+                failure_threshold: settings.breaker_failures,
+                cooldown: Duration::from_secs(settings.breaker_cooldown_secs),
+            },
+            probe: ProbeSettings {
+                url: settings
+                    .health_url
+                    .clone()
+                    .unwrap_or_else(|| settings.url.clone()),
+                method: settings.health_method,
+                timeout: Duration::from_millis(settings.health_timeout_ms),
+                ttl: Duration::from_secs(settings.health_ttl_secs),
+            },
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use reqwest::{Method, Url};
+
+use crate::app_core::AppResult;
+
+#[cfg(test)]
+#[path = "./unit_tests/probe.rs"]
+mod tests;
+
+/// How the health of a remote TTS provider is checked, without synthesizing anything.
+pub struct ProbeSettings {
+    /// Any answer but a server error means the provider is up.
+    pub url: Url,
+    pub method: ProbeMethod,
+    pub timeout: Duration,
+    /// How long a result is reused before probing again, zero probing every time.
+    pub ttl: Duration,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProbeMethod {
+    Head,
+    Get,
+}
+
+impl FromStr for ProbeMethod {
+    type Err = String;
+
+    fn from_str(method: &str) -> Result<Self, Self::Err> {
+        match method.to_ascii_uppercase().as_str() {
+            "HEAD" => Ok(Self::Head),
+            "GET" => Ok(Self::Get),
+            _ => Err(format!("Unsupported probe method '{method}'")),
+        }
+    }
+}
+
+impl From<ProbeMethod> for Method {
+    fn from(method: ProbeMethod) -> Self {
+        match method {
+            ProbeMethod::Head => Method::HEAD,
+            ProbeMethod::Get => Method::GET,
+        }
+    }
+}
+
+/// Last known health of a dependency, so that frequent checks do not all reach it.
+pub struct CachedHealth {
+    ttl: Duration,
+    last: Mutex<Option<(Instant, AppResult<()>)>>,
+}
+
+impl CachedHealth {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            last: Mutex::new(None),
+        }
+    }
+
+    /// The last result, unless it is older than the TTL.
+    pub fn get(&self) -> Option<AppResult<()>> {
+        self.get_at(Instant::now())
+    }
+
+    pub fn store(&self, result: AppResult<()>) {
+        self.store_at(Instant::now(), result)
+    }
+
+    fn get_at(&self, now: Instant) -> Option<AppResult<()>> {
+        match &*self.last.lock().unwrap() {
+            Some((checked, result)) if now < *checked + self.ttl => Some(result.clone()),
+            _ => None,
+        }
+    }
+
+    fn store_at(&self, now: Instant, result: AppResult<()>) {
+        *self.last.lock().unwrap() = Some((now, result));
+    }
+}
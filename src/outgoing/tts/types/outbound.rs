@@ -0,0 +1,51 @@
+use serde::Serialize;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(in super::super) struct SpeechRequest {
+    text: String,
+    is_male: bool,
+    language: String,
+    voice: String,
+    rate: f32,
+    pitch: f32,
+}
+
+impl From<Speech> for SpeechRequest {
+    fn from(this: Speech) -> Self {
+        Self {
+            text: this.text,
+            is_male: this.is_male,
+            language: this.locale,
+            voice: this.voice,
+            rate: this.rate,
+            pitch: this.pitch,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Speech {
+    pub text: String,
+    pub is_male: bool,
+    /// Locale of the text, like `it`.
+    pub locale: String,
+    /// Name the provider knows the voice by.
+    pub voice: String,
+    /// Speaking rate and pitch, relative to the provider defaults.
+    pub rate: f32,
+    pub pitch: f32,
+}
+
+impl From<crate::app_core::types::upload::Speech> for Speech {
+    fn from(speech: crate::app_core::types::upload::Speech) -> Self {
+        Self {
+            text: speech.text,
+            is_male: speech.voice.is_male,
+            locale: speech.voice.locale,
+            voice: speech.voice.name,
+            rate: speech.voice.rate,
+            pitch: speech.voice.pitch,
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use std::time::{Duration, Instant};
+
+use crate::outgoing::tts::breaker::{BreakerSettings, CircuitBreaker, CircuitState};
+
+const COOLDOWN: Duration = Duration::from_secs(30);
+
+fn breaker() -> CircuitBreaker {
+    CircuitBreaker::new(BreakerSettings {
+        failure_threshold: 3,
+        cooldown: COOLDOWN,
+    })
+}
+
+#[test]
+fn consecutive_failures_open_the_circuit() {
+    let breaker = breaker();
+    let now = Instant::now();
+
+    breaker.record_failure_at(now);
+    breaker.record_failure_at(now);
+    assert_eq!(breaker.state_at(now), CircuitState::Closed { failures: 2 });
+    assert!(breaker.acquire_at(now).is_ok());
+
+    breaker.record_failure_at(now);
+    assert_eq!(
+        breaker.state_at(now),
+        CircuitState::Open {
+            remaining: COOLDOWN
+        }
+    );
+    assert_eq!(
+        breaker.acquire_at(now + Duration::from_secs(10)),
+        Err(Duration::from_secs(20))
+    );
+}
+
+#[test]
+fn successes_reset_the_failure_count() {
+    let breaker = breaker();
+    let now = Instant::now();
+
+    breaker.record_failure_at(now);
+    breaker.record_failure_at(now);
+    breaker.record_success();
+    breaker.record_failure_at(now);
+
+    assert_eq!(breaker.state_at(now), CircuitState::Closed { failures: 1 });
+}
+
+#[test]
+fn a_single_trial_goes_through_after_the_cooldown() {
+    let breaker = breaker();
+    let now = Instant::now();
+    (0..3).for_each(|_| breaker.record_failure_at(now));
+
+    let later = now + COOLDOWN;
+    assert_eq!(breaker.state_at(later), CircuitState::HalfOpen);
+    assert!(breaker.acquire_at(later).is_ok());
+    assert!(breaker.acquire_at(later).is_err());
+
+    breaker.record_success();
+    assert!(breaker.acquire_at(later).is_ok());
+}
+
+#[test]
+fn failed_trials_open_the_circuit_again() {
+    let breaker = breaker();
+    let now = Instant::now();
+    (0..3).for_each(|_| breaker.record_failure_at(now));
+
+    let later = now + COOLDOWN;
+    assert!(breaker.acquire_at(later).is_ok());
+    breaker.record_failure_at(later);
+
+    assert_eq!(breaker.acquire_at(later), Err(COOLDOWN));
+}
+
+#[test]
+fn abandoned_trials_are_replaced_after_the_cooldown() {
+    let breaker = breaker();
+    let now = Instant::now();
+    (0..3).for_each(|_| breaker.record_failure_at(now));
+    assert!(breaker.acquire_at(now + COOLDOWN).is_ok());
+
+    assert!(breaker.acquire_at(now + COOLDOWN * 2).is_ok());
+}
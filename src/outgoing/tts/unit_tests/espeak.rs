@@ -0,0 +1,76 @@
+use std::ffi::OsString;
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use crate::outgoing::tts::espeak::{run, EspeakOpts, EspeakTtsProvider};
+use crate::outgoing::tts::types::Speech;
+
+fn provider() -> EspeakTtsProvider {
+    EspeakTtsProvider::new(EspeakOpts {
+        command: "espeak-ng".to_owned(),
+        output_dir: "./audio".into(),
+        timeout: Duration::from_secs(1),
+    })
+}
+
+fn arguments(locale: &str, voice: &str, rate: f32, pitch: f32) -> Vec<String> {
+    let speech = Speech {
+        text: "Ciao".to_owned(),
+        is_male: true,
+        locale: locale.to_owned(),
+        voice: voice.to_owned(),
+        rate,
+        pitch,
+    };
+
+    provider()
+        .arguments(&speech, Path::new("/tmp/speech.wav"))
+        .into_iter()
+        .map(OsString::into_string)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap()
+}
+
+#[test]
+fn voice_follows_locale_and_variant() {
+    assert_eq!(
+        arguments("it", "m3", 1.0, 1.0),
+        [
+            "-v",
+            "it+m3",
+            "-s",
+            "175",
+            "-p",
+            "50",
+            "-w",
+            "/tmp/speech.wav",
+            "--stdin"
+        ]
+    );
+    assert_eq!(arguments("en", "f5", 1.0, 1.0)[1], "en+f5");
+}
+
+#[test]
+fn rate_and_pitch_scale_the_defaults() {
+    let arguments = arguments("it", "m3", 1.5, 0.5);
+
+    assert_eq!(arguments[3], "263");
+    assert_eq!(arguments[5], "25");
+}
+
+#[test]
+fn pitch_is_capped() {
+    assert_eq!(arguments("it", "m3", 1.0, 4.0)[5], "99");
+}
+
+#[test]
+fn synthesizers_running_too_long_are_killed() {
+    let mut command = Command::new("sleep");
+    command.arg("5");
+    let started = Instant::now();
+
+    let error = run(command, "", Duration::from_millis(50)).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::TimedOut);
+    assert!(started.elapsed() < Duration::from_secs(2));
+}
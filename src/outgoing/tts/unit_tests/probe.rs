@@ -0,0 +1,40 @@
+use std::time::{Duration, Instant};
+
+use crate::app_core::errors::AppError;
+use crate::outgoing::tts::probe::{CachedHealth, ProbeMethod};
+
+const TTL: Duration = Duration::from_secs(10);
+
+#[test]
+fn results_are_reused_within_the_ttl() {
+    let cache = CachedHealth::new(TTL);
+    let now = Instant::now();
+    assert!(cache.get_at(now).is_none());
+
+    cache.store_at(
+        now,
+        Err(AppError::for_infrastructure_synthesizer_unavailable(
+            "down".to_owned(),
+        )),
+    );
+
+    assert!(matches!(cache.get_at(now + TTL / 2), Some(Err(_))));
+    assert!(cache.get_at(now + TTL).is_none());
+}
+
+#[test]
+fn a_zero_ttl_never_reuses_results() {
+    let cache = CachedHealth::new(Duration::ZERO);
+    let now = Instant::now();
+
+    cache.store_at(now, Ok(()));
+
+    assert!(cache.get_at(now).is_none());
+}
+
+#[test]
+fn probe_methods_ignore_case() {
+    assert_eq!("head".parse(), Ok(ProbeMethod::Head));
+    assert_eq!("GET".parse(), Ok(ProbeMethod::Get));
+    assert!("POST".parse::<ProbeMethod>().is_err());
+}
@@ -0,0 +1,15 @@
+use std::time::Duration;
+
+use crate::outgoing::tts::wrapper::RetryPolicy;
+
+#[test]
+fn backoff_doubles_at_every_retry() {
+    let retry = RetryPolicy {
+        max_retries: 3,
+        backoff: Duration::from_millis(250),
+    };
+
+    assert_eq!(retry.delay(0), Duration::from_millis(250));
+    assert_eq!(retry.delay(1), Duration::from_millis(500));
+    assert_eq!(retry.delay(2), Duration::from_secs(1));
+}
@@ -0,0 +1,161 @@
+use std::time::Duration;
+
+use actix_web::rt::time::sleep;
+use async_trait::async_trait;
+use reqwest::{Client, StatusCode, Url};
+
+use super::breaker::{BreakerSettings, CircuitBreaker, CircuitState};
+use super::probe::{CachedHealth, ProbeSettings};
+use super::types::{Speech, SpeechRequest, UploadResult};
+use super::TtsProvider;
+use crate::app_core::errors::AppError;
+use crate::app_core::AppResult;
+use crate::utils::{LogLevel, Loggable};
+
+#[cfg(test)]
+#[path = "./unit_tests/wrapper.rs"]
+mod tests;
+
+pub struct TtsWrapperConnectionOpts {
+    pub root_url: Url,
+    pub connect_timeout: Duration,
+    /// Bounds a whole attempt, synthesis included.
+    pub request_timeout: Duration,
+    pub retry: RetryPolicy,
+    pub breaker: BreakerSettings,
+    pub probe: ProbeSettings,
+}
+
+/// Transient failures are retried up to `max_retries` times, waiting `backoff` before the first retry and
+/// twice as long before each of the next ones.
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub backoff: Duration,
+}
+
+impl RetryPolicy {
+    fn delay(&self, retry: u32) -> Duration {
+        self.backoff * 2_u32.pow(retry.min(16))
+    }
+}
+
+/// Posts speeches to an external `tts-rest-wrapper`, which uploads them and answers with their URL.
+/// A wrapper that keeps failing is left alone for a while, see [`CircuitBreaker`].
+pub struct SimpleTtsWrapperClient {
+    client: Client,
+    connection_options: TtsWrapperConnectionOpts,
+    breaker: CircuitBreaker,
+    last_health: CachedHealth,
+}
+
+impl SimpleTtsWrapperClient {
+    /// `client` is expected to enforce the timeouts of the options.
+    pub fn new(client: Client, connection_options: TtsWrapperConnectionOpts) -> Self {
+        Self {
+            client,
+            breaker: CircuitBreaker::new(connection_options.breaker),
+            last_health: CachedHealth::new(connection_options.probe.ttl),
+            connection_options,
+        }
+    }
+
+    async fn speak(&self, request: &SpeechRequest) -> reqwest::Result<String> {
+        let retry = &self.connection_options.retry;
+        let mut retries = 0;
+
+        loop {
+            match self.post(request).await {
+                Err(error) if retries < retry.max_retries && is_transient(&error) => {
+                    let delay = retry.delay(retries);
+                    tracing::warn!("TTS wrapper failed, retrying in {delay:?}; error: {error}");
+                    sleep(delay).await;
+                    retries += 1;
+                }
+                response => return response,
+            }
+        }
+    }
+
+    async fn post(&self, request: &SpeechRequest) -> reqwest::Result<String> {
+        self.client
+            .post(format!("{}/speak", &self.connection_options.root_url))
+            .json(request)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await
+    }
+
+    /// Server errors are the only answers telling the wrapper is down.
+    async fn probe(&self) -> AppResult<()> {
+        let probe = &self.connection_options.probe;
+        self.client
+            .request(probe.method.into(), probe.url.clone())
+            .timeout(probe.timeout)
+            .send()
+            .await
+            .and_then(|response| {
+                if response.status().is_server_error() {
+                    response.error_for_status().map(|_| ())
+                } else {
+                    Ok(())
+                }
+            })
+            .log_err("Uploader is not healthy", LogLevel::Warning)
+            .map_err(AppError::for_infrastructure_http_client_failed)
+    }
+}
+
+/// Failures worth trying again: the wrapper was unreachable, too slow or overwhelmed.
+fn is_transient(error: &reqwest::Error) -> bool {
+    error.is_connect()
+        || error.is_timeout()
+        || error.status().map_or(false, |status| {
+            status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+        })
+}
+
+#[async_trait]
+impl TtsProvider for SimpleTtsWrapperClient {
+    fn name(&self) -> &'static str {
+        "wrapper"
+    }
+
+    fn circuit(&self) -> Option<CircuitState> {
+        Some(self.breaker.state())
+    }
+
+    /// Probes the wrapper without synthesizing anything, reusing the last result within its TTL.
+    async fn health(&self) -> AppResult<()> {
+        if let CircuitState::Open { remaining } = self.breaker.state() {
+            return Err(AppError::for_upload_circuit_open(remaining));
+        }
+        if let Some(result) = self.last_health.get() {
+            return result;
+        }
+
+        let result = self.probe().await;
+        self.last_health.store(result.clone());
+        result
+    }
+
+    /// Only transient failures count against the wrapper: rejected requests show it is answering.
+    async fn upload(&self, request: Speech) -> AppResult<UploadResult> {
+        self.breaker
+            .acquire()
+            .map_err(AppError::for_upload_circuit_open)
+            .log_err("TTS wrapper is not called", LogLevel::Warning)?;
+
+        let response = self.speak(&SpeechRequest::from(request)).await;
+        match &response {
+            Err(error) if is_transient(error) => self.breaker.record_failure(),
+            _ => self.breaker.record_success(),
+        }
+
+        response
+            .log_err("Unable to upload the requested speech", LogLevel::Error)
+            .map_err(AppError::for_upload)
+            .and_then(|url| UploadResult::parse(&url))
+    }
+}
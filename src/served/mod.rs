@@ -1,20 +1,95 @@
 pub mod types;
 
-use crate::app_core::AppCore;
+use crate::app_core::audio::range::ByteRange;
+use crate::app_core::audio::AudioKey;
+use crate::app_core::{AppCore, DependencyHealth};
 use std::sync::Arc;
 
-use actix_web::web::Data;
-use actix_web::{HttpResponse, Result};
+use actix_web::http::header;
+use actix_web::web::{Data, Path, Payload};
+use actix_web::{HttpRequest, HttpResponse, Result};
 use async_graphql::http::{playground_source, GraphQLPlaygroundConfig};
-use async_graphql::{EmptyMutation, EmptySubscription, Schema};
-use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse};
+use async_graphql::Schema;
+use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse, GraphQLSubscription};
+use serde::Serialize;
 
-pub type AppSchema = Schema<types::graphql::QueryRoot, EmptyMutation, EmptySubscription>;
+pub type AppSchema = Schema<
+    types::graphql::QueryRoot,
+    types::graphql::MutationRoot,
+    types::graphql::SubscriptionRoot,
+>;
 
+#[derive(Serialize)]
+struct Health {
+    status: Status,
+    dependencies: Dependencies,
+}
+
+#[derive(Serialize)]
+struct Dependencies {
+    db: DependencyStatus,
+    tts: DependencyStatus,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DependencyStatus {
+    status: Status,
+    latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    /// `closed`, `open` or `half-open`, for TTS providers having a circuit breaker.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    circuit: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+enum Status {
+    Up,
+    Down,
+}
+
+impl From<&DependencyHealth> for DependencyStatus {
+    fn from(health: &DependencyHealth) -> Self {
+        Self {
+            status: Status::from(health.result.is_ok()),
+            latency_ms: health.latency.as_millis() as u64,
+            error: health.result.as_ref().err().map(|error| format!("{error}")),
+            circuit: None,
+        }
+    }
+}
+
+impl From<bool> for Status {
+    fn from(is_up: bool) -> Self {
+        if is_up {
+            Status::Up
+        } else {
+            Status::Down
+        }
+    }
+}
+
+/// Status of every dependency, failing when one is down. An open circuit makes the TTS provider down, see
+/// [`crate::outgoing::tts::breaker`].
 pub async fn health(core: Data<Arc<AppCore>>) -> Result<HttpResponse> {
-    match core.is_healthy().await {
-        Ok(_) => Ok(HttpResponse::Ok().content_type("application/json").body("")),
-        Err(error) => Ok(HttpResponse::InternalServerError().body(format!("{}", error))),
+    let report = core.health().await;
+    let health = Health {
+        status: Status::from(report.is_healthy()),
+        dependencies: Dependencies {
+            db: DependencyStatus::from(&report.db),
+            tts: DependencyStatus {
+                circuit: core.uploader().circuit().map(|circuit| circuit.to_string()),
+                ..DependencyStatus::from(&report.tts)
+            },
+        },
+    };
+
+    if report.is_healthy() {
+        Ok(HttpResponse::Ok().json(health))
+    } else {
+        Ok(HttpResponse::InternalServerError().json(health))
     }
 }
 
@@ -22,10 +97,79 @@ pub async fn life() -> Result<HttpResponse> {
     Ok(HttpResponse::Ok().content_type("application/json").body(""))
 }
 
+/// Audio stored by this server. A single byte range can be requested, so that players can seek.
+pub async fn audio(
+    core: Data<Arc<AppCore>>,
+    path: Path<(String, i32)>,
+    request: HttpRequest,
+) -> Result<HttpResponse> {
+    let (phrase, voice) = path.into_inner();
+    let key = match sqlx::types::Uuid::parse_str(&phrase) {
+        Ok(phrase) => AudioKey { phrase, voice },
+        Err(_) => return Ok(HttpResponse::NotFound().finish()),
+    };
+
+    let audio = match core.audio().find(core.pool(), &key).await {
+        Ok(Some(audio)) => audio,
+        Ok(None) => return Ok(HttpResponse::NotFound().finish()),
+        Err(error) => return Ok(HttpResponse::InternalServerError().body(format!("{}", error))),
+    };
+
+    let range = request
+        .headers()
+        .get(header::RANGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(ByteRange::parse);
+    let (first, last) = match range.map(|range| range.resolve(audio.size)) {
+        Some(Some(bounds)) => bounds,
+        Some(None) => {
+            return Ok(HttpResponse::RangeNotSatisfiable()
+                .insert_header((header::CONTENT_RANGE, format!("bytes */{}", audio.size)))
+                .finish())
+        }
+        None if audio.size == 0 => {
+            return Ok(HttpResponse::Ok()
+                .content_type(audio.content_type.as_str())
+                .insert_header((header::ACCEPT_RANGES, "bytes"))
+                .finish())
+        }
+        None => (0, audio.size - 1),
+    };
+
+    let bytes = match core.audio().read(core.pool(), &audio, first, last).await {
+        Ok(bytes) => bytes,
+        Err(error) => return Ok(HttpResponse::InternalServerError().body(format!("{}", error))),
+    };
+
+    let mut response = if range.is_some() {
+        let mut response = HttpResponse::PartialContent();
+        response.insert_header((
+            header::CONTENT_RANGE,
+            format!("bytes {}-{}/{}", first, last, audio.size),
+        ));
+        response
+    } else {
+        HttpResponse::Ok()
+    };
+    Ok(response
+        .content_type(audio.content_type.as_str())
+        .insert_header((header::ACCEPT_RANGES, "bytes"))
+        .body(bytes))
+}
+
 pub async fn index(schema: Data<AppSchema>, req: GraphQLRequest) -> GraphQLResponse {
     schema.execute(req.into_inner()).await.into()
 }
 
+/// Subscriptions, over a WebSocket upgraded from `GET /`.
+pub async fn index_ws(
+    schema: Data<AppSchema>,
+    req: HttpRequest,
+    payload: Payload,
+) -> Result<HttpResponse> {
+    GraphQLSubscription::new(AppSchema::clone(&*schema)).start(&req, payload)
+}
+
 pub async fn index_playground() -> Result<HttpResponse> {
     let source = playground_source(GraphQLPlaygroundConfig::new("/").subscription_endpoint("/"));
     Ok(HttpResponse::Ok()
@@ -0,0 +1,120 @@
+use async_graphql::{Error, ErrorExtensionValues, ErrorExtensions};
+
+use crate::app_core::errors::{
+    AppError, DataError, EditError, ParseError, ProductionError, UploadError,
+};
+
+/// Every error carries a `code` extension naming its kind; parse, production and edit errors also carry
+/// their details, so that clients can point at the offending part of their input. Uploads refused by an open
+/// circuit tell when to try again.
+impl ErrorExtensions for AppError {
+    fn extend(&self) -> Error {
+        Error::new(self.to_string()).extend_with(|_, extensions| match self {
+            AppError::Upload(UploadError::CircuitOpen(seconds)) => {
+                extensions.set("code", "UPLOAD");
+                extensions.set("kind", "CIRCUIT_OPEN");
+                extensions.set("retryAfter", *seconds);
+            }
+            AppError::Upload(_) => extensions.set("code", "UPLOAD"),
+            AppError::Generation(_) => extensions.set("code", "GENERATION"),
+            AppError::Infrastructure(_) => extensions.set("code", "INFRASTRUCTURE"),
+            AppError::Storage(_) => extensions.set("code", "STORAGE"),
+            AppError::Settings(_) => extensions.set("code", "SETTINGS"),
+            AppError::Data(DataError::GrammarParse(error)) => {
+                extend_parse(std::slice::from_ref(error), extensions)
+            }
+            AppError::Data(DataError::GrammarParses(errors)) => extend_parse(errors, extensions),
+            AppError::Data(DataError::Production(error)) => extend_production(error, extensions),
+            AppError::Data(_) => extensions.set("code", "DATA"),
+            AppError::Edit(error) => extend_edit(error, extensions),
+            AppError::Multiple(errors) => {
+                extensions.set("code", "MULTIPLE");
+                extensions.set(
+                    "messages",
+                    errors.iter().map(ToString::to_string).collect::<Vec<_>>(),
+                );
+            }
+        })
+    }
+}
+
+/// A production may fail to parse in several tokens at once: kinds and texts are listed in parallel.
+fn extend_parse(errors: &[ParseError], extensions: &mut ErrorExtensionValues) {
+    let (kinds, texts): (Vec<_>, Vec<_>) = errors
+        .iter()
+        .map(|error| match error {
+            ParseError::RegexDidNotRecognize(text) => ("REGEX_DID_NOT_RECOGNIZE", text.clone()),
+            ParseError::CannotParseToNumber(text, _) => ("CANNOT_PARSE_TO_NUMBER", text.clone()),
+            ParseError::GroupNotFound(_, text) => ("GROUP_NOT_FOUND", text.clone()),
+            ParseError::UnrecognizedDependencyMarker(marker) => {
+                ("UNRECOGNIZED_DEPENDENCY_MARKER", marker.clone())
+            }
+        })
+        .unzip();
+
+    extensions.set("code", "PARSE");
+    extensions.set("kinds", kinds);
+    extensions.set("texts", texts);
+}
+
+fn extend_production(error: &ProductionError, extensions: &mut ErrorExtensionValues) {
+    extensions.set("code", "PRODUCTION");
+    match error {
+        ProductionError::IdClash(id) => {
+            extensions.set("kind", "ID_CLASH");
+            extensions.set("placeholders", vec![*id]);
+        }
+        ProductionError::CycleDetected(ids) => {
+            extensions.set("kind", "DEPENDENCY_CYCLE");
+            extensions.set("placeholders", ids.clone());
+        }
+        ProductionError::UnknownPlaceholder(id, dependency) => {
+            extensions.set("kind", "UNKNOWN_PLACEHOLDER");
+            extensions.set("placeholders", vec![*id, *dependency]);
+        }
+        ProductionError::UnknownNonTerminalSymbol(name) => {
+            extensions.set("kind", "UNKNOWN_NON_TERMINAL_SYMBOL");
+            extensions.set("nonTerminalSymbol", name.as_str());
+        }
+    }
+}
+
+fn extend_edit(error: &EditError, extensions: &mut ErrorExtensionValues) {
+    extensions.set("code", "EDIT");
+    match error {
+        EditError::DBFailed(_) => extensions.set("kind", "DB_FAILED"),
+        EditError::NotFound(entity, id) => {
+            extensions.set("kind", "NOT_FOUND");
+            extensions.set("entity", *entity);
+            extensions.set("id", *id);
+        }
+        EditError::UnknownName(entity, name) => {
+            extensions.set("kind", "NOT_FOUND");
+            extensions.set("entity", *entity);
+            extensions.set("name", name.as_str());
+        }
+        EditError::AlreadyExisting(entity, value) => {
+            extensions.set("kind", "ALREADY_EXISTING");
+            extensions.set("entity", *entity);
+            extensions.set("value", value.as_str());
+        }
+        EditError::EmptyValue(field) => {
+            extensions.set("kind", "EMPTY_VALUE");
+            extensions.set("field", *field);
+        }
+        EditError::ValueTooLong(field, max_length, _) => {
+            extensions.set("kind", "VALUE_TOO_LONG");
+            extensions.set("field", *field);
+            extensions.set("maxLength", *max_length as i32);
+        }
+        EditError::InvalidName(field, _) => {
+            extensions.set("kind", "INVALID_NAME");
+            extensions.set("field", *field);
+        }
+        EditError::NonPositiveWeight(_) => extensions.set("kind", "NON_POSITIVE_WEIGHT"),
+        EditError::OutOfRange(field, _, _, _) => {
+            extensions.set("kind", "OUT_OF_RANGE");
+            extensions.set("field", *field);
+        }
+    }
+}
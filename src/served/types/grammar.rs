@@ -0,0 +1,293 @@
+use async_graphql::{ComplexObject, Context, Enum, Object, Result, ResultExt, SimpleObject};
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::app_core::editing::grammar::{
+    GrammarLanguage, GrammarNonTerminalSymbol, GrammarProduction,
+};
+use crate::app_core::editing::PageRequest;
+use crate::app_core::engine::sources::DEFAULT_LANGUAGE;
+use crate::app_core::engine::types::parsing::Dependency;
+use crate::app_core::engine::types::{PlaceholderReference, ProductionBranch};
+use crate::app_core::AppCore;
+use crate::served::types::graphql::Page;
+
+#[derive(Default)]
+pub struct GrammarQuery;
+
+#[derive(Default)]
+pub struct GrammarMutation;
+
+/// A language phrases can be generated in, like `ita`. `locale`, like `it`, is handed to TTS providers.
+#[derive(SimpleObject)]
+pub struct Language {
+    pub code: String,
+    pub name: String,
+    pub locale: String,
+}
+
+impl From<GrammarLanguage> for Language {
+    fn from(language: GrammarLanguage) -> Self {
+        Self {
+            code: language.code,
+            name: language.name,
+            locale: language.locale,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(complex)]
+pub struct NonTerminalSymbol {
+    pub id: i32,
+    pub name: String,
+    /// Code of the language of the symbol and of its productions.
+    pub language: String,
+}
+
+impl From<GrammarNonTerminalSymbol> for NonTerminalSymbol {
+    fn from(non_terminal_symbol: GrammarNonTerminalSymbol) -> Self {
+        Self {
+            id: non_terminal_symbol.id,
+            name: non_terminal_symbol.name,
+            language: non_terminal_symbol.language,
+        }
+    }
+}
+
+#[ComplexObject]
+impl NonTerminalSymbol {
+    async fn productions<'ctx>(&self, ctx: &Context<'ctx>) -> Result<Vec<Production>> {
+        let core = ctx.data_unchecked::<Arc<AppCore>>();
+        core.grammar()
+            .productions_of(self.id)
+            .await
+            .map(|productions| productions.into_iter().map(Into::into).collect())
+            .extend()
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(complex)]
+pub struct Production {
+    pub id: i32,
+    pub non_terminal_symbol: String,
+    pub language: String,
+    pub production: String,
+    pub weight: i32,
+}
+
+impl From<GrammarProduction> for Production {
+    fn from(production: GrammarProduction) -> Self {
+        Self {
+            id: production.id,
+            non_terminal_symbol: production.non_terminal_symbol,
+            language: production.language,
+            production: production.production,
+            weight: production.weight,
+        }
+    }
+}
+
+#[ComplexObject]
+impl Production {
+    /// Placeholders in order of appearance, null when the production does not parse:
+    /// `grammarValidation` tells why.
+    async fn placeholders(&self) -> Option<Vec<Placeholder>> {
+        ProductionBranch::from_str(&self.production)
+            .ok()
+            .map(|branch| {
+                branch
+                    .placeholder_references()
+                    .iter()
+                    .map(Into::into)
+                    .collect()
+            })
+    }
+}
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+pub enum PlaceholderKind {
+    /// `{...}`, expanded through the productions of `reference`.
+    NonTerminalSymbol,
+    /// `<...>`, replaced by a word tagged with `reference`.
+    WordSelector,
+}
+
+#[derive(SimpleObject)]
+pub struct Placeholder {
+    pub id: i32,
+    pub kind: PlaceholderKind,
+    /// Non-terminal symbol or semantic tag name.
+    pub reference: String,
+    pub grammar: Propagation,
+    pub semantic: Propagation,
+}
+
+impl From<&PlaceholderReference> for Placeholder {
+    fn from(placeholder: &PlaceholderReference) -> Self {
+        let (kind, token) = match placeholder {
+            PlaceholderReference::NonTerminalSymbol(token) => {
+                (PlaceholderKind::NonTerminalSymbol, token)
+            }
+            PlaceholderReference::WordSelector(token) => (PlaceholderKind::WordSelector, token),
+        };
+
+        Self {
+            id: token.id(),
+            kind,
+            reference: token.reference().to_owned(),
+            grammar: Propagation::new(token.grammar_dependency(), token.grammar_can_propagate()),
+            semantic: Propagation::new(token.semantic_dependency(), token.semantic_can_propagate()),
+        }
+    }
+}
+
+/// How a placeholder gets its tags, and whether it hands them over to its context.
+#[derive(SimpleObject)]
+pub struct Propagation {
+    pub dependency: DependencyKind,
+    /// Id of the placeholder tags are taken from, if any.
+    pub depends_on: Option<i32>,
+    pub propagates: bool,
+}
+
+impl Propagation {
+    fn new(dependency: &Dependency, propagates: bool) -> Self {
+        let (dependency, depends_on) = match dependency {
+            Dependency::OnNothing => (DependencyKind::Nothing, None),
+            Dependency::OnContext => (DependencyKind::Context, None),
+            Dependency::On(id) => (DependencyKind::Placeholder, Some(*id)),
+            Dependency::OnContextAnd(id) => (DependencyKind::ContextAndPlaceholder, Some(*id)),
+        };
+
+        Self {
+            dependency,
+            depends_on,
+            propagates,
+        }
+    }
+}
+
+/// The `N`, `C`, `O(id)` and `CO(id)` markers of placeholders.
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+pub enum DependencyKind {
+    Nothing,
+    Context,
+    Placeholder,
+    ContextAndPlaceholder,
+}
+
+#[Object]
+impl GrammarQuery {
+    async fn languages<'ctx>(&self, ctx: &Context<'ctx>) -> Result<Vec<Language>> {
+        let core = ctx.data_unchecked::<Arc<AppCore>>();
+        core.grammar()
+            .languages()
+            .await
+            .map(|languages| languages.into_iter().map(Into::into).collect())
+            .extend()
+    }
+
+    /// Symbols of every language. Pages hold 20 symbols unless told otherwise, and at most 100.
+    async fn non_terminal_symbols<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        #[graphql(default = 0)] offset: i64,
+        #[graphql(default = 20)] limit: i64,
+    ) -> Result<Page<NonTerminalSymbol>> {
+        let core = ctx.data_unchecked::<Arc<AppCore>>();
+        core.grammar()
+            .non_terminal_symbols(PageRequest::new(offset, limit))
+            .await
+            .map(Into::into)
+            .extend()
+    }
+}
+
+/// Rejected productions come with a `code` error extension (`PARSE`, `PRODUCTION` or `EDIT`) and a `kind`
+/// detailing the problem, plus the offending text, placeholder ids or non-terminal symbol.
+#[Object]
+impl GrammarMutation {
+    /// Adds a language with no symbols and no words yet.
+    async fn create_language<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        code: String,
+        name: String,
+        locale: String,
+    ) -> Result<Language> {
+        let core = ctx.data_unchecked::<Arc<AppCore>>();
+        core.grammar()
+            .create_language(&code, &name, &locale)
+            .await
+            .map(Into::into)
+            .extend()
+    }
+
+    /// The symbol is added to the default language unless told otherwise.
+    async fn create_non_terminal_symbol<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        name: String,
+        language: Option<String>,
+    ) -> Result<NonTerminalSymbol> {
+        let core = ctx.data_unchecked::<Arc<AppCore>>();
+        core.grammar()
+            .create_non_terminal_symbol(&name, language.as_deref().unwrap_or(DEFAULT_LANGUAGE))
+            .await
+            .map(Into::into)
+            .extend()
+    }
+
+    /// The production must parse, have no placeholder id clashes or dependency cycles and only refer to
+    /// existing non-terminal symbols of its language, the default one unless told otherwise.
+    /// Weight defaults to 1.
+    async fn add_production<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        non_terminal_symbol: String,
+        language: Option<String>,
+        production: String,
+        weight: Option<i32>,
+    ) -> Result<Production> {
+        let core = ctx.data_unchecked::<Arc<AppCore>>();
+        core.grammar()
+            .add_production(
+                &non_terminal_symbol,
+                language.as_deref().unwrap_or(DEFAULT_LANGUAGE),
+                &production,
+                weight.unwrap_or(1),
+            )
+            .await
+            .map(Into::into)
+            .extend()
+    }
+
+    /// Missing arguments are left untouched, a new production text is checked like in `addProduction`.
+    async fn update_production<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        id: i32,
+        production: Option<String>,
+        weight: Option<i32>,
+    ) -> Result<Production> {
+        let core = ctx.data_unchecked::<Arc<AppCore>>();
+        core.grammar()
+            .update_production(id, production.as_deref(), weight)
+            .await
+            .map(Into::into)
+            .extend()
+    }
+
+    /// Deletes the production and returns it.
+    async fn delete_production<'ctx>(&self, ctx: &Context<'ctx>, id: i32) -> Result<Production> {
+        let core = ctx.data_unchecked::<Arc<AppCore>>();
+        core.grammar()
+            .delete_production(id)
+            .await
+            .map(Into::into)
+            .extend()
+    }
+}
@@ -1,34 +1,175 @@
-use async_graphql::{Context, Enum, InputObject, Object};
+use async_graphql::{
+    Context, Enum, InputObject, Json, MergedObject, MergedSubscription, Object, OutputType, Result,
+    ResultExt, SimpleObject, Subscription,
+};
+use futures::{stream, Stream, StreamExt};
 
 use std::sync::Arc;
+use std::time::Duration;
 
+use actix_web::rt::time::sleep;
+
+use crate::app_core::editing;
+use crate::app_core::editing::voices::VoiceChoice;
+use crate::app_core::engine::analysis::SymbolAnalysis;
+use crate::app_core::engine::sources::{Category, DEFAULT_LANGUAGE};
+use crate::app_core::engine::types::derivation::Derivation;
+use crate::app_core::engine::validation::{self, GrammarProblem, GrammarReport};
 use crate::app_core::errors::AppError;
-use crate::app_core::{AppCore, AppResult, SpeechGenerationOptions};
+use crate::app_core::{
+    AppCore, AppResult, BatchGenerationOptions, BatchItem, GenerationStrategy, PhraseOrigin,
+    SpeechGenerationOptions,
+};
+use crate::served::types::grammar::{GrammarMutation, GrammarQuery, NonTerminalSymbol};
+use crate::served::types::jobs::{
+    SpeechAudio, SpeechJobMutation, SpeechJobQuery, SpeechJobSubscription,
+};
+use crate::served::types::lexicon::{GrammarTag, LexiconMutation, LexiconQuery, SemanticTag, Word};
+use crate::served::types::phrases::{CachedSpeech, PhraseQuery};
+use crate::served::types::voices::{VoiceMutation, VoiceQuery};
+
+#[derive(MergedObject, Default)]
+pub struct QueryRoot(
+    GenerationQuery,
+    PhraseQuery,
+    LexiconQuery,
+    GrammarQuery,
+    VoiceQuery,
+    SpeechJobQuery,
+);
+
+#[derive(Default)]
+pub struct GenerationQuery;
+
+#[derive(MergedObject, Default)]
+pub struct MutationRoot(
+    LexiconMutation,
+    GrammarMutation,
+    VoiceMutation,
+    SpeechJobMutation,
+);
 
-pub struct QueryRoot;
+#[derive(MergedSubscription, Default)]
+pub struct SubscriptionRoot(GenerationSubscription, SpeechJobSubscription);
+
+#[derive(Default)]
+pub struct GenerationSubscription;
+
+/// Shortest interval between two phrases of a stream, in milliseconds.
+pub const MIN_STREAM_INTERVAL_MS: u64 = 100;
 
 #[derive(InputObject)]
 pub struct SpeechGenerationOpts {
+    /// Empty means no category: generation starts from the default start symbol.
     pub category: String,
+    /// Code of the language to generate in, like `ita`; the default language when missing.
+    pub language: Option<String>,
+    /// Reproduces the phrase generated with it. A seed always generates: `PROBABILISTIC` acts like `FRESH`,
+    /// `POOL` is refused.
+    pub seed: Option<u64>,
+    #[graphql(default)]
+    pub strategy: StrategyOpts,
+}
+
+/// How to choose between generating a new phrase and reusing a stored one.
+#[derive(InputObject, Default)]
+pub struct StrategyOpts {
+    #[graphql(default)]
+    pub mode: GenerationMode,
+    /// Pool size past which `PROBABILISTIC` only reuses phrases, at least 1. Defaults to
+    /// `generation.max_phrases` of the settings, 2048 unless told otherwise.
+    pub max_phrases: Option<u64>,
+}
+
+impl StrategyOpts {
+    fn into_strategy(self, default_max_phrases: u64) -> GenerationStrategy {
+        match self.mode {
+            GenerationMode::Fresh => GenerationStrategy::Fresh,
+            GenerationMode::Pool => GenerationStrategy::Pool,
+            GenerationMode::Probabilistic => GenerationStrategy::Probabilistic {
+                max_phrases: self.max_phrases.unwrap_or(default_max_phrases).max(1),
+            },
+        }
+    }
+}
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+pub enum GenerationMode {
+    /// Always generates a new phrase.
+    Fresh,
+    /// Always reuses a stored phrase, failing when there is none.
+    Pool,
+    /// Generates with probability `1 - stored / maxPhrases`, reuses otherwise.
+    Probabilistic,
+}
+
+impl Default for GenerationMode {
+    fn default() -> Self {
+        Self::Probabilistic
+    }
+}
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+pub enum SpeechOrigin {
+    Generated,
+    Reused,
+}
+
+impl From<PhraseOrigin> for SpeechOrigin {
+    fn from(origin: PhraseOrigin) -> Self {
+        match origin {
+            PhraseOrigin::Generated => Self::Generated,
+            PhraseOrigin::Reused => Self::Reused,
+        }
+    }
 }
 
 #[derive(InputObject)]
-pub struct Voice {
-    pub language: Language,
-    pub gender: Gender,
+pub struct BatchGenerationOpts {
+    /// Empty means no category: generation starts from the default start symbol.
+    #[graphql(default)]
+    pub category: String,
+    /// Code of the language to generate in, like `ita`; the default language when missing.
+    pub language: Option<String>,
+    /// Seed of the whole batch, every phrase gets its own seed from it. Like for single phrases, a seed always
+    /// generates.
+    pub seed: Option<u64>,
+    /// When true, no phrase appears twice in the batch.
+    #[graphql(default)]
+    pub distinct: bool,
+    #[graphql(default)]
+    pub strategy: StrategyOpts,
 }
 
-#[derive(Enum, Copy, Clone, Eq, PartialEq, sqlx::Type)]
-#[sqlx(type_name = "lang")] // May also be the name of a user defined enum type
-#[sqlx(rename_all = "lowercase")] // similar to serde rename_all
-pub enum Language {
-    Ita,
+#[derive(InputObject)]
+pub struct PhraseStreamOpts {
+    /// Empty means no category: generation starts from the default start symbol.
+    #[graphql(default)]
+    pub category: String,
+    /// Code of the language to generate in, like `ita`; the default language when missing.
+    pub language: Option<String>,
+    /// Milliseconds between two phrases, 1000 by default and at least 100.
+    #[graphql(default = 1000)]
+    pub interval_ms: u64,
 }
 
-impl ToString for Language {
-    fn to_string(&self) -> String {
-        match self {
-            Language::Ita => "ita".to_string(),
+/// Picks the voice with `id`, or the first voice of the TTS provider in use speaking `language` (the language
+/// of the phrase when missing) as `gender` (any when missing).
+#[derive(InputObject, Default)]
+pub struct VoiceSelection {
+    pub id: Option<i32>,
+    pub language: Option<String>,
+    pub gender: Option<Gender>,
+}
+
+impl VoiceSelection {
+    pub fn into_choice(self, phrase_language: &str) -> VoiceChoice {
+        match self.id {
+            Some(id) => VoiceChoice::Id(id),
+            None => VoiceChoice::Matching {
+                language: self.language.unwrap_or_else(|| phrase_language.to_owned()),
+                gender: self.gender.map(|gender| gender.to_string()),
+            },
         }
     }
 }
@@ -50,21 +191,284 @@ impl ToString for Gender {
     }
 }
 
+/// Listings are ordered by id; `total` counts every item, not only the ones in this page.
+#[derive(SimpleObject)]
+#[graphql(concrete(name = "WordPage", params(Word)))]
+#[graphql(concrete(name = "SemanticTagPage", params(SemanticTag)))]
+#[graphql(concrete(name = "GrammarTagPage", params(GrammarTag)))]
+#[graphql(concrete(name = "NonTerminalSymbolPage", params(NonTerminalSymbol)))]
+#[graphql(concrete(name = "SpeechPage", params(Speech)))]
+pub struct Page<T: OutputType> {
+    pub total: i64,
+    pub offset: i64,
+    pub items: Vec<T>,
+}
+
+impl<F, T: OutputType + From<F>> From<editing::Page<F>> for Page<T> {
+    fn from(page: editing::Page<F>) -> Self {
+        Self {
+            total: page.total,
+            offset: page.offset,
+            items: page.items.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
 #[Object]
-impl QueryRoot {
+impl GenerationQuery {
     async fn random<'ctx>(
         &self,
         ctx: &Context<'ctx>,
-        _opts: SpeechGenerationOpts,
+        opts: SpeechGenerationOpts,
     ) -> AppResult<Speech> {
+        let core = ctx.data_unchecked::<Arc<AppCore>>();
+        core.generator()
+            .generate(SpeechGenerationOptions {
+                category: Some(opts.category).filter(|category| !category.is_empty()),
+                language: opts.language,
+                seed: opts.seed,
+                strategy: opts
+                    .strategy
+                    .into_strategy(core.settings().generation.max_phrases),
+            })
+            .await
+    }
+
+    /// Generates `count` phrases, at most 100, in a single transaction.
+    /// A phrase that cannot be generated is reported in its own item instead of failing the batch.
+    async fn batch<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        count: u32,
+        opts: BatchGenerationOpts,
+    ) -> AppResult<Vec<BatchSpeech>> {
+        let core = ctx.data_unchecked::<Arc<AppCore>>();
+        core.generator()
+            .generate_batch(
+                count as usize,
+                BatchGenerationOptions {
+                    category: Some(opts.category).filter(|category| !category.is_empty()),
+                    language: opts.language,
+                    seed: opts.seed,
+                    distinct: opts.distinct,
+                    strategy: opts
+                        .strategy
+                        .into_strategy(core.settings().generation.max_phrases),
+                },
+            )
+            .await
+            .map(|items| items.into_iter().map(Into::into).collect())
+    }
+
+    async fn categories<'ctx>(&self, ctx: &Context<'ctx>) -> AppResult<Vec<SpeechCategory>> {
+        let generator = ctx.data_unchecked::<Arc<AppCore>>().generator();
+        generator
+            .categories()
+            .await
+            .map(|categories| categories.into_iter().map(Into::into).collect())
+    }
+
+    /// Statically checks every stored production of a language, the default one when missing, without
+    /// generating anything.
+    async fn grammar_validation<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        language: Option<String>,
+    ) -> AppResult<GrammarValidation> {
         let generator = ctx.data_unchecked::<Arc<AppCore>>().generator();
-        generator.generate(SpeechGenerationOptions {}).await
+        generator
+            .validate_grammar(language.as_deref().unwrap_or(DEFAULT_LANGUAGE))
+            .await
+            .map(Into::into)
+    }
+}
+
+#[Subscription]
+impl GenerationSubscription {
+    /// Newly generated phrases, the first one right away and the next ones every `intervalMs`. Failing to
+    /// start, like for an unknown category or when the server already streams `generation.max_streams`
+    /// subscriptions, fails the subscription; later failures are sent in place of their phrase.
+    async fn phrases<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        opts: PhraseStreamOpts,
+    ) -> Result<impl Stream<Item = Result<Speech>>> {
+        let core = ctx.data_unchecked::<Arc<AppCore>>().clone();
+        let options = SpeechGenerationOptions {
+            category: Some(opts.category).filter(|category| !category.is_empty()),
+            language: opts.language,
+            seed: None,
+            strategy: GenerationStrategy::Fresh,
+        };
+        let interval = Duration::from_millis(opts.interval_ms.max(MIN_STREAM_INTERVAL_MS));
+
+        let slot = core.phrase_streams().open().extend()?;
+
+        let first = core.generator().generate(options.clone()).await.extend()?;
+        let next = stream::unfold((core, slot), move |(core, slot)| {
+            let options = options.clone();
+            async move {
+                sleep(interval).await;
+                let speech = core.generator().generate(options).await.extend();
+                Some((speech, (core, slot)))
+            }
+        });
+
+        Ok(stream::once(async { Ok(first) }).chain(next))
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct GrammarValidation {
+    pub valid: bool,
+    pub problems: Vec<GrammarIssue>,
+    /// Non-terminal symbols no start symbol (default or of a category) can expand into.
+    pub unreachable_symbols: Vec<String>,
+    /// Non-terminal symbols that can only be expanded until the depth limit is hit.
+    pub unproductive_symbols: Vec<String>,
+    pub symbols: Vec<GrammarSymbol>,
+}
+
+impl From<GrammarReport> for GrammarValidation {
+    fn from(report: GrammarReport) -> Self {
+        Self {
+            valid: report.is_valid(),
+            unreachable_symbols: report
+                .unreachable_symbols()
+                .into_iter()
+                .map(str::to_owned)
+                .collect(),
+            unproductive_symbols: report
+                .unproductive_symbols()
+                .into_iter()
+                .map(str::to_owned)
+                .collect(),
+            problems: report.problems.into_iter().map(Into::into).collect(),
+            symbols: report.symbols.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct GrammarSymbol {
+    pub name: String,
+    pub reachable: bool,
+    pub productive: bool,
+    /// Height of the lowest complete derivation tree, null when unproductive.
+    pub min_depth: Option<u32>,
+    /// Non-terminal symbols expanded by the smallest complete derivation, null when unproductive.
+    pub min_non_terminal_symbols: Option<u32>,
+}
+
+impl From<SymbolAnalysis> for GrammarSymbol {
+    fn from(symbol: SymbolAnalysis) -> Self {
+        Self {
+            name: symbol.name,
+            reachable: symbol.reachable,
+            productive: symbol.productive,
+            min_depth: symbol.min_depth,
+            min_non_terminal_symbols: symbol.min_non_terminal_symbols,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct GrammarIssue {
+    /// Id of the offending `production` row.
+    pub production: i32,
+    pub non_terminal_symbol: String,
+    pub kind: GrammarProblemKind,
+    pub description: String,
+}
+
+impl From<GrammarProblem> for GrammarIssue {
+    fn from(problem: GrammarProblem) -> Self {
+        Self {
+            production: problem.production,
+            non_terminal_symbol: problem.non_terminal_symbol,
+            kind: problem.kind.into(),
+            description: problem.description,
+        }
+    }
+}
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+pub enum GrammarProblemKind {
+    Unparsable,
+    IdClash,
+    UnknownPlaceholder,
+    DependencyCycle,
+    UnknownNonTerminalSymbol,
+    UnknownSemanticTag,
+}
+
+impl From<validation::GrammarProblemKind> for GrammarProblemKind {
+    fn from(kind: validation::GrammarProblemKind) -> Self {
+        match kind {
+            validation::GrammarProblemKind::Unparsable => Self::Unparsable,
+            validation::GrammarProblemKind::IdClash => Self::IdClash,
+            validation::GrammarProblemKind::UnknownPlaceholder => Self::UnknownPlaceholder,
+            validation::GrammarProblemKind::DependencyCycle => Self::DependencyCycle,
+            validation::GrammarProblemKind::UnknownNonTerminalSymbol => {
+                Self::UnknownNonTerminalSymbol
+            }
+            validation::GrammarProblemKind::UnknownSemanticTag => Self::UnknownSemanticTag,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct SpeechCategory {
+    pub name: String,
+    /// Non terminal symbol the generation of this category starts from.
+    pub start_symbol: String,
+    /// Semantic tags every generation of this category is seeded with.
+    pub semantic_tags: Vec<String>,
+}
+
+impl From<Category> for SpeechCategory {
+    fn from(category: Category) -> Self {
+        Self {
+            name: category.name,
+            start_symbol: category.start_symbol,
+            semantic_tags: category.semantic_tags,
+        }
+    }
+}
+
+/// Either `speech` or `error` is set.
+#[derive(SimpleObject)]
+pub struct BatchSpeech {
+    /// Seed of the phrase, or of its last failed attempt.
+    pub seed: u64,
+    pub speech: Option<Speech>,
+    pub error: Option<String>,
+}
+
+impl From<BatchItem> for BatchSpeech {
+    fn from(item: BatchItem) -> Self {
+        let (speech, error) = match item.result {
+            Ok(speech) => (Some(speech), None),
+            Err(error) => (None, Some(error.to_string())),
+        };
+
+        Self {
+            seed: item.seed,
+            speech,
+            error,
+        }
     }
 }
 
 pub struct Speech {
     pub id: String,
     pub text: String,
+    /// Code of the language of the text.
+    pub language: String,
+    pub seed: Option<u64>,
+    pub origin: PhraseOrigin,
+    pub backtracks: Option<u32>,
+    pub derivation: Option<Derivation>,
 }
 
 #[Object]
@@ -77,48 +481,107 @@ impl Speech {
         &self.text
     }
 
-    //TODO: refactor this crap
-    pub async fn audio_url<'c>(&self, ctx: &Context<'c>, voice: Voice) -> AppResult<String> {
-        let text = &self.text;
+    /// Code of the language of the phrase, like `ita`.
+    pub async fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// Seed that drove every random choice; pass it back in `opts` to reproduce this phrase.
+    /// Null for phrases reused from the pool, or fetched by id or listing.
+    pub async fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// Whether the phrase was just generated or reused from the pool.
+    pub async fn origin(&self) -> SpeechOrigin {
+        self.origin.into()
+    }
+
+    /// How many failed production branches were retried, null when the phrase comes from the pool.
+    pub async fn backtracks(&self) -> Option<u32> {
+        self.backtracks
+    }
+
+    /// Audio already synthesized for this phrase, one per voice.
+    pub async fn speeches<'c>(&self, ctx: &Context<'c>) -> AppResult<Vec<CachedSpeech>> {
         let id =
             sqlx::types::Uuid::parse_str(&self.id).map_err(AppError::for_upload_in_sql_uuid)?;
-        let uploader = ctx.data_unchecked::<Arc<AppCore>>().uploader();
-        let mut transaction = ctx
-            .data_unchecked::<Arc<AppCore>>()
-            .pool()
-            .begin()
+        ctx.data_unchecked::<Arc<AppCore>>()
+            .phrases()
+            .speeches(id)
+            .await
+            .map(|speeches| speeches.into_iter().map(Into::into).collect())
+    }
+
+    /// Derivation tree of the phrase as JSON, null when the phrase comes from the pool.
+    /// Every node is a placeholder with its chosen production or word, and the tag ids it inherited,
+    /// registered for its siblings and propagated to its context.
+    pub async fn derivation(&self) -> Option<Json<Derivation>> {
+        self.derivation.clone().map(Json)
+    }
+
+    /// The phrase spoken by `voice`, picked like for `audioUrl`, without waiting for the synthesis: the URL
+    /// when already synthesized, the job synthesizing it otherwise (see `requestAudio`).
+    pub async fn audio<'c>(
+        &self,
+        ctx: &Context<'c>,
+        #[graphql(default)] voice: VoiceSelection,
+    ) -> Result<SpeechAudio> {
+        let phrase = sqlx::types::Uuid::parse_str(&self.id)
+            .map_err(AppError::for_upload_in_sql_uuid)
+            .extend()?;
+        let core = ctx.data_unchecked::<Arc<AppCore>>();
+        let voice = core
+            .voices()
+            .choose(
+                &voice.into_choice(&self.language),
+                core.uploader().provider(),
+            )
             .await
-            .map_err(AppError::for_upload_in_sql)?;
-
-        // See: https://docs.rs/sqlx/0.4.2/sqlx/macro.query.html#type-overrides-bind-parameters-postgres-only
-        if let Some(url) = sqlx::query!(
-            "SELECT url FROM generated_phrase_speech WHERE generated_phrase = $1 AND lang = $2 AND gender = $3",
-            id as sqlx::types::Uuid, voice.language as _, voice.gender as _
-        )
-        .fetch_optional(&mut transaction)
-        .await
-        .map_err(AppError::for_upload_in_sql)?
-            .map(|res| res.url) {
-            Ok(url)
-        } else {
-            let url = uploader
-                .upload(crate::app_core::types::upload::Speech {
-                    is_male: matches!(voice.gender, Gender::Male),
-                    text: text.clone(),
-                })
-                .await
-                .map(|res| res.url.to_string())?;
-
-            sqlx::query!(
-                "INSERT INTO generated_phrase_speech (generated_phrase, lang, gender, url) VALUES ($1, $2, $3, $4)",
-                id as _, voice.language as _, voice.gender as _, &url as _
-            ).execute(&mut transaction)
-                .await
-                .map_err(AppError::for_upload_in_sql)?;
-
-            transaction.commit().await.map_err(AppError::for_upload_in_sql)?;
-
-            Ok(url)
+            .extend()?;
+
+        if let Some(url) = core.stored_speech_url(phrase, &voice).await.extend()? {
+            return Ok(SpeechAudio {
+                url: Some(url),
+                job: None,
+            });
         }
+
+        core.jobs()
+            .enqueue(phrase, voice.id)
+            .await
+            .map(|job| SpeechAudio {
+                url: None,
+                job: Some(job.into()),
+            })
+            .extend()
+    }
+
+    /// URL of the phrase spoken by `voice`, a voice of its language unless told otherwise, synthesized on the
+    /// first request. Points at this server's `/audio` route when it stores audio.
+    /// While the TTS provider keeps failing, errors carry a `CIRCUIT_OPEN` kind and the seconds to wait in
+    /// `retryAfter`.
+    #[graphql(
+        deprecation = "Holds the request until the speech is synthesized, use `audio` or `requestAudio` instead."
+    )]
+    pub async fn audio_url<'c>(
+        &self,
+        ctx: &Context<'c>,
+        #[graphql(default)] voice: VoiceSelection,
+    ) -> Result<String> {
+        let phrase = sqlx::types::Uuid::parse_str(&self.id)
+            .map_err(AppError::for_upload_in_sql_uuid)
+            .extend()?;
+        let core = ctx.data_unchecked::<Arc<AppCore>>();
+        let voice = core
+            .voices()
+            .choose(
+                &voice.into_choice(&self.language),
+                core.uploader().provider(),
+            )
+            .await
+            .extend()?;
+
+        core.speech_url(phrase, &voice, &self.text).await.extend()
     }
 }
@@ -0,0 +1,147 @@
+use async_graphql::{Context, Enum, Object, Result, ResultExt, SimpleObject, Subscription};
+use futures::{future, Stream, StreamExt};
+
+use std::sync::Arc;
+
+use crate::app_core::errors::AppError;
+use crate::app_core::jobs::{self, SpeechJob};
+use crate::app_core::AppCore;
+use crate::served::types::graphql::VoiceSelection;
+
+#[derive(Default)]
+pub struct SpeechJobQuery;
+
+#[derive(Default)]
+pub struct SpeechJobMutation;
+
+#[derive(Default)]
+pub struct SpeechJobSubscription;
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+pub enum JobStatus {
+    /// Waiting for a worker, or being synthesized.
+    Pending,
+    Done,
+    Failed,
+}
+
+impl From<jobs::JobStatus> for JobStatus {
+    fn from(status: jobs::JobStatus) -> Self {
+        match status {
+            jobs::JobStatus::Pending => Self::Pending,
+            jobs::JobStatus::Done => Self::Done,
+            jobs::JobStatus::Failed => Self::Failed,
+        }
+    }
+}
+
+/// Synthesis of a phrase in a voice of the catalogue, run in the background.
+#[derive(SimpleObject)]
+pub struct AudioJob {
+    pub id: String,
+    pub phrase_id: String,
+    pub voice: i32,
+    pub status: JobStatus,
+    /// Same as `audioUrl` of the phrase, set once `DONE`.
+    pub url: Option<String>,
+    /// Why the synthesis failed, set once `FAILED`.
+    pub error: Option<String>,
+}
+
+/// Audio of a phrase in a voice: its `url` once synthesized, the `job` synthesizing it otherwise.
+#[derive(SimpleObject)]
+pub struct SpeechAudio {
+    pub url: Option<String>,
+    pub job: Option<AudioJob>,
+}
+
+impl From<SpeechJob> for AudioJob {
+    fn from(job: SpeechJob) -> Self {
+        Self {
+            id: job.id.to_string(),
+            phrase_id: job.phrase.to_string(),
+            voice: job.voice,
+            status: job.status.into(),
+            url: job.url,
+            error: job.error,
+        }
+    }
+}
+
+#[Object]
+impl SpeechJobQuery {
+    /// A job by its id, null when there is none.
+    async fn audio_job<'ctx>(&self, ctx: &Context<'ctx>, id: String) -> Result<Option<AudioJob>> {
+        let id = match sqlx::types::Uuid::parse_str(&id) {
+            Ok(id) => id,
+            Err(_) => return Ok(None),
+        };
+
+        let core = ctx.data_unchecked::<Arc<AppCore>>();
+        core.jobs()
+            .job(id)
+            .await
+            .map(|job| job.map(Into::into))
+            .extend()
+    }
+}
+
+#[Subscription]
+impl SpeechJobSubscription {
+    /// Jobs of a stored phrase as they end up `DONE` or `FAILED`, whichever server processed them. Jobs that
+    /// finished before subscribing are not sent: query them with `audioJob`.
+    async fn audio_job_finished<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        phrase_id: String,
+    ) -> Result<impl Stream<Item = AudioJob>> {
+        let core = ctx.data_unchecked::<Arc<AppCore>>();
+        let phrase = match sqlx::types::Uuid::parse_str(&phrase_id) {
+            Ok(id) => core.phrases().phrase(id).await.extend()?,
+            Err(_) => None,
+        }
+        .ok_or_else(|| AppError::for_unknown_phrase(phrase_id))
+        .extend()?;
+
+        Ok(core
+            .finished_jobs()
+            .subscribe()
+            .filter(move |job| future::ready(job.phrase == phrase.id))
+            .map(Into::into))
+    }
+}
+
+#[Object]
+impl SpeechJobMutation {
+    /// Queues the synthesis of a stored phrase in `voice`, picked like for `audioUrl`, and returns at once.
+    /// Poll `audioJob` until the job is `DONE` or `FAILED`. While a job for the same phrase and voice is
+    /// pending, that job is returned.
+    async fn request_audio<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        phrase_id: String,
+        #[graphql(default)] voice: VoiceSelection,
+    ) -> Result<AudioJob> {
+        let core = ctx.data_unchecked::<Arc<AppCore>>();
+        let phrase = match sqlx::types::Uuid::parse_str(&phrase_id) {
+            Ok(id) => core.phrases().phrase(id).await.extend()?,
+            Err(_) => None,
+        }
+        .ok_or_else(|| AppError::for_unknown_phrase(phrase_id))
+        .extend()?;
+        let voice = core
+            .voices()
+            .choose(
+                &voice.into_choice(&phrase.language),
+                core.uploader().provider(),
+            )
+            .await
+            .extend()?;
+
+        core.jobs()
+            .enqueue(phrase.id, voice.id)
+            .await
+            .map(Into::into)
+            .extend()
+    }
+}
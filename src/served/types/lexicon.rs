@@ -0,0 +1,416 @@
+use async_graphql::{
+    ComplexObject, Context, Enum, InputObject, Object, Result, ResultExt, SimpleObject,
+};
+
+use std::sync::Arc;
+
+use crate::app_core::editing::lexicon::{
+    self, LexiconGrammarTag, LexiconSemanticTag, LexiconWord, NewWord, TaggedWord, WordChanges,
+};
+use crate::app_core::editing::PageRequest;
+use crate::app_core::engine::sources::DEFAULT_LANGUAGE;
+use crate::app_core::AppCore;
+use crate::served::types::graphql::Page;
+
+#[derive(Default)]
+pub struct LexiconQuery;
+
+#[derive(Default)]
+pub struct LexiconMutation;
+
+#[derive(SimpleObject)]
+pub struct Word {
+    pub id: i32,
+    pub content: String,
+    /// Code of the language of the word.
+    pub language: String,
+    pub non_repeatable: bool,
+    pub weight: i32,
+    pub semantic_tags: Vec<String>,
+    /// Grammar tags the word accepts from its context.
+    pub grammar_compatibilities: Vec<String>,
+    /// Grammar tags the word imposes on its context.
+    pub grammar_requirements: Vec<String>,
+}
+
+impl From<LexiconWord> for Word {
+    fn from(word: LexiconWord) -> Self {
+        Self {
+            id: word.id,
+            content: word.content,
+            language: word.language,
+            non_repeatable: word.non_repeatable,
+            weight: word.weight,
+            semantic_tags: word.semantic_tags,
+            grammar_compatibilities: word.grammar_compatibilities,
+            grammar_requirements: word.grammar_requirements,
+        }
+    }
+}
+
+/// A word as seen from one of its tags.
+#[derive(SimpleObject)]
+pub struct WordReference {
+    pub id: i32,
+    pub content: String,
+}
+
+impl From<TaggedWord> for WordReference {
+    fn from(word: TaggedWord) -> Self {
+        Self {
+            id: word.id,
+            content: word.content,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(complex)]
+pub struct SemanticTag {
+    pub id: i32,
+    pub name: String,
+    /// Whether picked words propagate this tag to their context.
+    pub sticky: bool,
+}
+
+impl From<LexiconSemanticTag> for SemanticTag {
+    fn from(tag: LexiconSemanticTag) -> Self {
+        Self {
+            id: tag.id,
+            name: tag.name,
+            sticky: tag.sticky,
+        }
+    }
+}
+
+#[ComplexObject]
+impl SemanticTag {
+    async fn words<'ctx>(&self, ctx: &Context<'ctx>) -> Result<Vec<WordReference>> {
+        let core = ctx.data_unchecked::<Arc<AppCore>>();
+        core.lexicon()
+            .words_with_semantic_tag(self.id)
+            .await
+            .map(|words| words.into_iter().map(Into::into).collect())
+            .extend()
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(complex)]
+pub struct GrammarTag {
+    pub id: i32,
+    pub name: String,
+}
+
+#[ComplexObject]
+impl GrammarTag {
+    /// Words accepting this tag from their context.
+    async fn compatible_words<'ctx>(&self, ctx: &Context<'ctx>) -> Result<Vec<WordReference>> {
+        self.words(ctx, lexicon::GrammarRelation::Compatibility)
+            .await
+    }
+
+    /// Words imposing this tag on their context.
+    async fn requiring_words<'ctx>(&self, ctx: &Context<'ctx>) -> Result<Vec<WordReference>> {
+        self.words(ctx, lexicon::GrammarRelation::Requirement).await
+    }
+}
+
+impl GrammarTag {
+    async fn words(
+        &self,
+        ctx: &Context<'_>,
+        relation: lexicon::GrammarRelation,
+    ) -> Result<Vec<WordReference>> {
+        let core = ctx.data_unchecked::<Arc<AppCore>>();
+        core.lexicon()
+            .words_with_grammar_tag(self.id, relation)
+            .await
+            .map(|words| words.into_iter().map(Into::into).collect())
+            .extend()
+    }
+}
+
+impl From<LexiconGrammarTag> for GrammarTag {
+    fn from(tag: LexiconGrammarTag) -> Self {
+        Self {
+            id: tag.id,
+            name: tag.name,
+        }
+    }
+}
+
+#[derive(InputObject)]
+pub struct WordInput {
+    pub content: String,
+    /// Code of the language of the word, the default language when missing.
+    pub language: Option<String>,
+    /// Defaults to true: the word appears at most once per phrase.
+    pub non_repeatable: Option<bool>,
+    /// Defaults to 1.
+    pub weight: Option<i32>,
+}
+
+impl From<WordInput> for NewWord {
+    fn from(input: WordInput) -> Self {
+        Self {
+            content: input.content,
+            language: input
+                .language
+                .unwrap_or_else(|| DEFAULT_LANGUAGE.to_owned()),
+            non_repeatable: input.non_repeatable.unwrap_or(true),
+            weight: input.weight.unwrap_or(1),
+        }
+    }
+}
+
+/// Missing fields are left untouched.
+#[derive(InputObject)]
+pub struct WordPatch {
+    pub content: Option<String>,
+    pub non_repeatable: Option<bool>,
+    pub weight: Option<i32>,
+}
+
+impl From<WordPatch> for WordChanges {
+    fn from(patch: WordPatch) -> Self {
+        Self {
+            content: patch.content,
+            non_repeatable: patch.non_repeatable,
+            weight: patch.weight,
+        }
+    }
+}
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+pub enum GrammarRelation {
+    Compatibility,
+    Requirement,
+}
+
+impl From<GrammarRelation> for lexicon::GrammarRelation {
+    fn from(relation: GrammarRelation) -> Self {
+        match relation {
+            GrammarRelation::Compatibility => Self::Compatibility,
+            GrammarRelation::Requirement => Self::Requirement,
+        }
+    }
+}
+
+/// Pages hold 20 items unless told otherwise, and at most 100.
+#[Object]
+impl LexiconQuery {
+    async fn words<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        #[graphql(default = 0)] offset: i64,
+        #[graphql(default = 20)] limit: i64,
+    ) -> Result<Page<Word>> {
+        let core = ctx.data_unchecked::<Arc<AppCore>>();
+        core.lexicon()
+            .words(PageRequest::new(offset, limit))
+            .await
+            .map(Into::into)
+            .extend()
+    }
+
+    async fn semantic_tags<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        #[graphql(default = 0)] offset: i64,
+        #[graphql(default = 20)] limit: i64,
+    ) -> Result<Page<SemanticTag>> {
+        let core = ctx.data_unchecked::<Arc<AppCore>>();
+        core.lexicon()
+            .semantic_tags(PageRequest::new(offset, limit))
+            .await
+            .map(Into::into)
+            .extend()
+    }
+
+    async fn grammar_tags<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        #[graphql(default = 0)] offset: i64,
+        #[graphql(default = 20)] limit: i64,
+    ) -> Result<Page<GrammarTag>> {
+        let core = ctx.data_unchecked::<Arc<AppCore>>();
+        core.lexicon()
+            .grammar_tags(PageRequest::new(offset, limit))
+            .await
+            .map(Into::into)
+            .extend()
+    }
+}
+
+#[Object]
+impl LexiconMutation {
+    async fn create_word<'ctx>(&self, ctx: &Context<'ctx>, word: WordInput) -> Result<Word> {
+        let core = ctx.data_unchecked::<Arc<AppCore>>();
+        core.lexicon()
+            .create_word(word.into())
+            .await
+            .map(Into::into)
+            .extend()
+    }
+
+    async fn update_word<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        id: i32,
+        changes: WordPatch,
+    ) -> Result<Word> {
+        let core = ctx.data_unchecked::<Arc<AppCore>>();
+        core.lexicon()
+            .update_word(id, changes.into())
+            .await
+            .map(Into::into)
+            .extend()
+    }
+
+    /// Detaches every tag from the word, then deletes it and returns it.
+    async fn delete_word<'ctx>(&self, ctx: &Context<'ctx>, id: i32) -> Result<Word> {
+        let core = ctx.data_unchecked::<Arc<AppCore>>();
+        core.lexicon()
+            .delete_word(id)
+            .await
+            .map(Into::into)
+            .extend()
+    }
+
+    /// Tags are sticky unless told otherwise.
+    async fn create_semantic_tag<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        name: String,
+        sticky: Option<bool>,
+    ) -> Result<SemanticTag> {
+        let core = ctx.data_unchecked::<Arc<AppCore>>();
+        core.lexicon()
+            .create_semantic_tag(&name, sticky.unwrap_or(true))
+            .await
+            .map(Into::into)
+            .extend()
+    }
+
+    /// Productions mentioning the old name are not rewritten, `grammarValidation` reports them.
+    async fn update_semantic_tag<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        id: i32,
+        name: Option<String>,
+        sticky: Option<bool>,
+    ) -> Result<SemanticTag> {
+        let core = ctx.data_unchecked::<Arc<AppCore>>();
+        core.lexicon()
+            .update_semantic_tag(id, name.as_deref(), sticky)
+            .await
+            .map(Into::into)
+            .extend()
+    }
+
+    /// Detaches the tag from words and categories, then deletes it and returns it.
+    async fn delete_semantic_tag<'ctx>(&self, ctx: &Context<'ctx>, id: i32) -> Result<SemanticTag> {
+        let core = ctx.data_unchecked::<Arc<AppCore>>();
+        core.lexicon()
+            .delete_semantic_tag(id)
+            .await
+            .map(Into::into)
+            .extend()
+    }
+
+    async fn create_grammar_tag<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        name: String,
+    ) -> Result<GrammarTag> {
+        let core = ctx.data_unchecked::<Arc<AppCore>>();
+        core.lexicon()
+            .create_grammar_tag(&name)
+            .await
+            .map(Into::into)
+            .extend()
+    }
+
+    async fn update_grammar_tag<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        id: i32,
+        name: String,
+    ) -> Result<GrammarTag> {
+        let core = ctx.data_unchecked::<Arc<AppCore>>();
+        core.lexicon()
+            .update_grammar_tag(id, &name)
+            .await
+            .map(Into::into)
+            .extend()
+    }
+
+    /// Detaches the tag from words, then deletes it and returns it.
+    async fn delete_grammar_tag<'ctx>(&self, ctx: &Context<'ctx>, id: i32) -> Result<GrammarTag> {
+        let core = ctx.data_unchecked::<Arc<AppCore>>();
+        core.lexicon()
+            .delete_grammar_tag(id)
+            .await
+            .map(Into::into)
+            .extend()
+    }
+
+    async fn attach_semantic_tag<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        word: i32,
+        tag: i32,
+    ) -> Result<Word> {
+        let core = ctx.data_unchecked::<Arc<AppCore>>();
+        core.lexicon()
+            .attach_semantic_tag(word, tag)
+            .await
+            .map(Into::into)
+            .extend()
+    }
+
+    async fn detach_semantic_tag<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        word: i32,
+        tag: i32,
+    ) -> Result<Word> {
+        let core = ctx.data_unchecked::<Arc<AppCore>>();
+        core.lexicon()
+            .detach_semantic_tag(word, tag)
+            .await
+            .map(Into::into)
+            .extend()
+    }
+
+    async fn attach_grammar_tag<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        word: i32,
+        tag: i32,
+        relation: GrammarRelation,
+    ) -> Result<Word> {
+        let core = ctx.data_unchecked::<Arc<AppCore>>();
+        core.lexicon()
+            .attach_grammar_tag(word, tag, relation.into())
+            .await
+            .map(Into::into)
+            .extend()
+    }
+
+    async fn detach_grammar_tag<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        word: i32,
+        tag: i32,
+        relation: GrammarRelation,
+    ) -> Result<Word> {
+        let core = ctx.data_unchecked::<Arc<AppCore>>();
+        core.lexicon()
+            .detach_grammar_tag(word, tag, relation.into())
+            .await
+            .map(Into::into)
+            .extend()
+    }
+}
@@ -1 +1,7 @@
+pub mod errors;
+pub mod grammar;
 pub mod graphql;
+pub mod jobs;
+pub mod lexicon;
+pub mod phrases;
+pub mod voices;
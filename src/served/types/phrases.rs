@@ -0,0 +1,78 @@
+use async_graphql::{Context, Object, SimpleObject};
+
+use std::sync::Arc;
+
+use crate::app_core::editing::PageRequest;
+use crate::app_core::phrases::{StoredPhrase, StoredSpeech};
+use crate::app_core::{AppCore, AppResult, PhraseOrigin};
+use crate::served::types::graphql::{Page, Speech};
+
+#[derive(Default)]
+pub struct PhraseQuery;
+
+/// Audio of a phrase in one voice of the catalogue, which speaks a language, like `ita`, as a gender.
+#[derive(SimpleObject)]
+pub struct CachedSpeech {
+    pub voice: i32,
+    pub language: String,
+    pub gender: String,
+    pub url: String,
+}
+
+impl From<StoredSpeech> for CachedSpeech {
+    fn from(speech: StoredSpeech) -> Self {
+        Self {
+            voice: speech.voice,
+            language: speech.language,
+            gender: speech.gender,
+            url: speech.url,
+        }
+    }
+}
+
+impl From<StoredPhrase> for Speech {
+    fn from(phrase: StoredPhrase) -> Self {
+        Self {
+            id: phrase.id.to_string(),
+            text: phrase.content,
+            language: phrase.language,
+            seed: None,
+            origin: PhraseOrigin::Reused,
+            backtracks: None,
+            derivation: None,
+        }
+    }
+}
+
+#[Object]
+impl PhraseQuery {
+    /// A phrase of the pool by its id, null when there is none.
+    async fn phrase<'ctx>(&self, ctx: &Context<'ctx>, id: String) -> AppResult<Option<Speech>> {
+        let id = match sqlx::types::Uuid::parse_str(&id) {
+            Ok(id) => id,
+            Err(_) => return Ok(None),
+        };
+
+        let core = ctx.data_unchecked::<Arc<AppCore>>();
+        core.phrases()
+            .phrase(id)
+            .await
+            .map(|phrase| phrase.map(Into::into))
+    }
+
+    /// Phrases of the pool ordered by text, keeping the ones containing `search` regardless of case.
+    /// Pages hold 20 phrases unless told otherwise, and at most 100.
+    async fn phrases<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        search: Option<String>,
+        #[graphql(default = 0)] offset: i64,
+        #[graphql(default = 20)] limit: i64,
+    ) -> AppResult<Page<Speech>> {
+        let core = ctx.data_unchecked::<Arc<AppCore>>();
+        core.phrases()
+            .phrases(search.as_deref(), PageRequest::new(offset, limit))
+            .await
+            .map(Into::into)
+    }
+}
@@ -0,0 +1,101 @@
+use async_graphql::{Context, InputObject, Object, Result, ResultExt, SimpleObject};
+
+use std::sync::Arc;
+
+use crate::app_core::editing::voices::{CatalogueVoice, NewVoice};
+use crate::app_core::engine::sources::DEFAULT_LANGUAGE;
+use crate::app_core::AppCore;
+use crate::served::types::graphql::Gender;
+
+#[derive(Default)]
+pub struct VoiceQuery;
+
+#[derive(Default)]
+pub struct VoiceMutation;
+
+/// A voice of the catalogue. `name` is the one its provider knows it by, `rate` and `pitch` scale the
+/// provider defaults.
+#[derive(SimpleObject)]
+pub struct Voice {
+    pub id: i32,
+    /// Code of the language the voice speaks.
+    pub language: String,
+    pub gender: String,
+    pub provider: String,
+    pub name: String,
+    pub rate: f32,
+    pub pitch: f32,
+}
+
+impl From<CatalogueVoice> for Voice {
+    fn from(voice: CatalogueVoice) -> Self {
+        Self {
+            id: voice.id,
+            language: voice.language,
+            gender: voice.gender,
+            provider: voice.provider,
+            name: voice.name,
+            rate: voice.rate,
+            pitch: voice.pitch,
+        }
+    }
+}
+
+#[derive(InputObject)]
+pub struct VoiceInput {
+    pub name: String,
+    /// Defaults to the default language.
+    pub language: Option<String>,
+    pub gender: Gender,
+    /// Defaults to the TTS provider in use.
+    pub provider: Option<String>,
+    /// Defaults to 1.
+    pub rate: Option<f32>,
+    /// Defaults to 1.
+    pub pitch: Option<f32>,
+}
+
+impl VoiceInput {
+    fn into_new_voice(self, provider: &str) -> NewVoice {
+        NewVoice {
+            language: self.language.unwrap_or_else(|| DEFAULT_LANGUAGE.to_owned()),
+            gender: self.gender.to_string(),
+            provider: self.provider.unwrap_or_else(|| provider.to_owned()),
+            name: self.name,
+            rate: self.rate.unwrap_or(1.0),
+            pitch: self.pitch.unwrap_or(1.0),
+        }
+    }
+}
+
+#[Object]
+impl VoiceQuery {
+    /// Voices of the TTS provider in use unless told otherwise, of every language unless one is given.
+    async fn voices<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        language: Option<String>,
+        provider: Option<String>,
+    ) -> Result<Vec<Voice>> {
+        let core = ctx.data_unchecked::<Arc<AppCore>>();
+        let provider = provider.unwrap_or_else(|| core.uploader().provider().to_owned());
+        core.voices()
+            .voices(&provider, language.as_deref())
+            .await
+            .map(|voices| voices.into_iter().map(Into::into).collect())
+            .extend()
+    }
+}
+
+#[Object]
+impl VoiceMutation {
+    /// Rejected voices come with an `EDIT` error, like the other edits.
+    async fn create_voice<'ctx>(&self, ctx: &Context<'ctx>, voice: VoiceInput) -> Result<Voice> {
+        let core = ctx.data_unchecked::<Arc<AppCore>>();
+        core.voices()
+            .create_voice(voice.into_new_voice(core.uploader().provider()))
+            .await
+            .map(Into::into)
+            .extend()
+    }
+}
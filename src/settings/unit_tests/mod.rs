@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+
+use crate::app_core::errors::{AppError, SettingsError};
+use crate::outgoing::tts::probe::ProbeMethod;
+use crate::settings::{AudioStorageName, Settings, TtsProviderName};
+
+fn env<'a>(variables: &'a [(&str, &str)]) -> impl Fn(&str) -> Option<String> + 'a {
+    let variables: HashMap<_, _> = variables.iter().copied().collect();
+    move |name| variables.get(name).map(|value| value.to_string())
+}
+
+#[test]
+fn defaults_keep_the_previous_hard_coded_values() {
+    let settings = Settings::default();
+
+    assert_eq!(
+        settings.server.bind_address,
+        "0.0.0.0:8000".parse::<SocketAddr>().unwrap()
+    );
+    assert_eq!(settings.database.max_connections, 8);
+    assert_eq!(settings.generation.max_phrases, 2048);
+    assert_eq!(settings.generation.max_depth, 100);
+    assert_eq!(settings.generation.max_length, 500);
+    assert_eq!(settings.generation.max_streams, 16);
+    assert_eq!(settings.tts.provider, TtsProviderName::Wrapper);
+    assert!(settings.validate().is_ok());
+}
+
+#[test]
+fn files_set_only_the_values_they_mention() {
+    let settings: Settings = toml::from_str(
+        r#"
+        [database]
+        max_connections = 16
+
+        [tts]
+        provider = "espeak"
+
+        [tts.wrapper]
+        health_method = "get"
+
+        [audio]
+        storage = "database"
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(settings.database.max_connections, 16);
+    assert_eq!(settings.tts.provider, TtsProviderName::Espeak);
+    assert_eq!(settings.tts.wrapper.health_method, ProbeMethod::Get);
+    assert_eq!(settings.audio.storage, AudioStorageName::Database);
+    assert_eq!(settings.jobs, Settings::default().jobs);
+}
+
+#[test]
+fn files_with_unknown_or_malformed_values_are_rejected() {
+    assert!(toml::from_str::<Settings>("[database]\nmax_conections = 16").is_err());
+    assert!(toml::from_str::<Settings>("[tts]\nprovider = \"festival\"").is_err());
+    assert!(toml::from_str::<Settings>("[server]\nbind_address = \"localhost\"").is_err());
+}
+
+#[test]
+fn environment_variables_override_the_file() {
+    let mut settings: Settings = toml::from_str("[generation]\nmax_depth = 20").unwrap();
+
+    settings
+        .override_with(&env(&[
+            ("GENERATION_MAX_DEPTH", "30"),
+            ("TTS_HEALTH_URL", "http://tts:8080/health"),
+        ]))
+        .unwrap();
+
+    assert_eq!(settings.generation.max_depth, 30);
+    assert_eq!(
+        settings.tts.wrapper.health_url.unwrap().as_str(),
+        "http://tts:8080/health"
+    );
+}
+
+#[test]
+fn malformed_environment_variables_are_named() {
+    let result = Settings::load_with(env(&[("DB_MAX_CONNECTIONS", "many")]));
+
+    assert!(matches!(
+        result,
+        Err(AppError::Settings(SettingsError::Environment("DB_MAX_CONNECTIONS", value, _))) if value == "many"
+    ));
+}
+
+#[test]
+fn validation_reports_every_problem() {
+    let result = Settings::load_with(env(&[
+        ("DB_MAX_CONNECTIONS", "1"),
+        ("GENERATION_MAX_LENGTH", "0"),
+    ]));
+
+    match result {
+        Err(AppError::Settings(SettingsError::Invalid(problems))) => assert_eq!(
+            problems,
+            vec![
+                "database.max_connections must be at least 2",
+                "generation.max_length must be at least 1",
+            ]
+        ),
+        _ => panic!("Settings should be invalid"),
+    }
+}
+
+#[test]
+fn jobs_need_a_worker() {
+    assert!(matches!(
+        Settings::load_with(env(&[("SPEECH_WORKERS", "0")])),
+        Err(AppError::Settings(SettingsError::Invalid(problems))) if problems == ["jobs.workers must be at least 1"]
+    ));
+}
+
+#[test]
+fn worst_case_wrapper_time_counts_every_attempt_and_backoff() {
+    let settings = Settings::load_with(env(&[
+        ("TTS_REQUEST_TIMEOUT_MS", "10000"),
+        ("TTS_MAX_RETRIES", "3"),
+        ("TTS_RETRY_BACKOFF_MS", "100"),
+    ]))
+    .unwrap();
+
+    assert_eq!(
+        settings.tts.wrapper.worst_case_ms(),
+        40_000 + 100 + 200 + 400
+    );
+}
+
+#[test]
+fn jobs_outlive_the_worst_case_wrapper_time() {
+    let wrapper = [
+        ("TTS_REQUEST_TIMEOUT_MS", "30000"),
+        ("TTS_MAX_RETRIES", "1"),
+        ("TTS_RETRY_BACKOFF_MS", "1000"),
+    ];
+
+    assert!(Settings::load_with(env(&[
+        wrapper[0],
+        wrapper[1],
+        wrapper[2],
+        ("SPEECH_JOB_STALE_SECS", "62")
+    ]))
+    .is_ok());
+    for stale_after in ["61", "60"] {
+        assert!(matches!(
+            Settings::load_with(env(&[wrapper[0], wrapper[1], wrapper[2], ("SPEECH_JOB_STALE_SECS", stale_after)])),
+            Err(AppError::Settings(SettingsError::Invalid(problems))) if problems[0].starts_with("jobs.stale_after_secs")
+        ));
+    }
+}
+
+#[test]
+fn jobs_outlive_the_espeak_timeout_and_the_download() {
+    let espeak = [
+        ("TTS_PROVIDER", "espeak"),
+        ("ESPEAK_TIMEOUT_MS", "20000"),
+        ("AUDIO_DOWNLOAD_TIMEOUT_MS", "10000"),
+    ];
+    let stale_after = |secs: &str, storage: &str| {
+        Settings::load_with(env(&[
+            espeak[0],
+            espeak[1],
+            espeak[2],
+            ("SPEECH_JOB_STALE_SECS", secs),
+            ("AUDIO_STORAGE", storage),
+        ]))
+    };
+
+    assert_eq!(
+        stale_after("31", "filesystem").unwrap().job_worst_case_ms(),
+        30_000
+    );
+    assert!(stale_after("30", "filesystem").is_err());
+    // Nothing is downloaded when the audio is not kept
+    assert!(stale_after("21", "remote").is_ok());
+    assert!(stale_after("20", "remote").is_err());
+}